@@ -0,0 +1,439 @@
+//! Reading and writing CBOR items asynchronously.
+//!
+//! An [`AsyncReader`](struct.AsyncReader.html) reads from a `futures::io::AsyncRead` until a
+//! complete data item is buffered and then deserializes it. The end of the item is found by
+//! scanning its headers with an
+//! [`IncrementalDecoder`](../incremental/struct.IncrementalDecoder.html), so no task waits on a
+//! partial item and no length prefix is needed.
+//!
+//! An [`AsyncWriter`](struct.AsyncWriter.html) serializes items into a buffer and writes it to a
+//! `futures::io::AsyncWrite` when it is flushed. It is a `Sink` of the items.
+//! [`to_async_writer`](fn.to_async_writer.html) writes a single item.
+//!
+//! Readers and writers of tokio can be adapted with `tokio_util::compat`.
+//!
+//! This module requires the `async` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use futures::executor::block_on;
+//! use futures::stream::StreamExt;
+//! use serde_cbor::async_io::AsyncReader;
+//!
+//! // A CBOR sequence of three integers.
+//! let input: &[u8] = b"\x01\x18\x64\x19\x03\xe8";
+//!
+//! block_on(async {
+//!     let mut reader = AsyncReader::new(input);
+//!     let first: Option<u16> = reader.read_item().await.unwrap();
+//!     assert_eq!(first, Some(1));
+//!
+//!     let items = reader.into_stream::<u16>();
+//!     let rest: Vec<u16> = items.map(|item| item.unwrap()).collect().await;
+//!     assert_eq!(rest, [100, 1000]);
+//! });
+//! ```
+//!
+//! ```
+//! use futures::executor::block_on;
+//! use futures::sink::SinkExt;
+//! use serde_cbor::async_io::{to_async_writer, AsyncWriter};
+//!
+//! block_on(async {
+//!     let mut output = Vec::new();
+//!     to_async_writer(&mut output, &"first").await.unwrap();
+//!
+//!     let mut writer = AsyncWriter::new(&mut output);
+//!     writer.send(1).await.unwrap();
+//!     writer.send(2).await.unwrap();
+//!     assert_eq!(output, b"\x65first\x01\x02");
+//! });
+//! ```
+
+use core::future::Future;
+use core::marker::PhantomData;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use std::io;
+
+use futures_core::{ready, Stream};
+use futures_io::{AsyncRead, AsyncWrite};
+use futures_sink::Sink;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::de::DecodeLimits;
+use crate::error::{Error, ErrorCode, Result};
+use crate::incremental::{IncrementalDecoder, Progress};
+use crate::ser::{SequenceWriter, Serializer};
+
+// The number of bytes read from the input at once, and the size up to which output is buffered
+// by a `Sink` before it is written.
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// Serializes a value and writes it to an asynchronous writer, which is flushed afterwards.
+pub async fn to_async_writer<W, T>(writer: W, value: &T) -> Result<()>
+where
+    W: AsyncWrite + Unpin,
+    T: ?Sized + Serialize,
+{
+    let mut writer = AsyncWriter::new(writer);
+    writer.write(value)?;
+    writer.flush().await
+}
+
+/// Reads data items from an asynchronous reader.
+///
+/// Bytes that were read after the end of an item are kept for the next one.
+#[derive(Debug)]
+pub struct AsyncReader<R> {
+    reader: R,
+    decoder: IncrementalDecoder,
+    chunk: Vec<u8>,
+    // The number of bytes read from the input.
+    offset: u64,
+    eof: bool,
+}
+
+impl<R> AsyncReader<R> {
+    /// Creates a reader of the items in `reader`.
+    pub fn new(reader: R) -> Self {
+        AsyncReader {
+            reader,
+            decoder: IncrementalDecoder::new(),
+            chunk: Vec::new(),
+            offset: 0,
+            eof: false,
+        }
+    }
+
+    /// Limits the length of items in bytes, see
+    /// [`ItemScanner::max_item_len`](../incremental/struct.ItemScanner.html#method.max_item_len).
+    ///
+    /// Items are buffered completely before they are deserialized, so this limit should be set
+    /// for untrusted input.
+    pub fn max_item_len(mut self, len: usize) -> Self {
+        self.decoder = self.decoder.max_item_len(len);
+        self
+    }
+
+    /// Sets the limits for deserializing items, see
+    /// [`IncrementalDecoder::limits`](../incremental/struct.IncrementalDecoder.html#method.limits).
+    pub fn limits(mut self, limits: DecodeLimits) -> Self {
+        self.decoder = self.decoder.limits(limits);
+        self
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    ///
+    /// Reading from it directly skips the data that is buffered.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Returns the data that has been read but not decoded yet.
+    pub fn buffered(&self) -> &[u8] {
+        self.decoder.buffered()
+    }
+
+    /// Unwraps the reader. Data that has been read but not decoded is lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R> AsyncReader<R>
+where
+    R: AsyncRead + Unpin,
+{
+    /// Reads the next item, `None` if the input ends before it starts.
+    ///
+    /// The input must not end in the middle of an item. An item that is complete but can't be
+    /// deserialized into a `T` is skipped before the error is returned.
+    pub fn read_item<T>(&mut self) -> ReadItem<'_, R, T>
+    where
+        T: DeserializeOwned,
+    {
+        ReadItem {
+            reader: self,
+            item: PhantomData,
+        }
+    }
+
+    /// Turns the reader into a stream of the items of a CBOR sequence.
+    ///
+    /// The stream ends after the last item or after the first error that is not a data error.
+    pub fn into_stream<T>(self) -> ItemStream<R, T>
+    where
+        T: DeserializeOwned,
+    {
+        ItemStream {
+            reader: self,
+            done: false,
+            item: PhantomData,
+        }
+    }
+
+    /// Attempts to read the next item, like [`read_item`](#method.read_item).
+    pub fn poll_read_item<T>(&mut self, cx: &mut Context<'_>) -> Poll<Result<Option<T>>>
+    where
+        T: DeserializeOwned,
+    {
+        loop {
+            if let Progress::Complete(item) = self.decoder.decode()? {
+                return Poll::Ready(Ok(Some(item)));
+            }
+            if self.eof {
+                if self.decoder.buffered().is_empty() {
+                    return Poll::Ready(Ok(None));
+                }
+                let err = Error::syntax(ErrorCode::EofWhileParsingValue, self.offset);
+                return Poll::Ready(Err(err));
+            }
+            if self.chunk.is_empty() {
+                self.chunk.resize(CHUNK_SIZE, 0);
+            }
+            let n = match ready!(Pin::new(&mut self.reader).poll_read(cx, &mut self.chunk)) {
+                Ok(n) => n,
+                Err(err) => return Poll::Ready(Err(Error::io(err))),
+            };
+            if n == 0 {
+                self.eof = true;
+            }
+            self.offset += n as u64;
+            self.decoder.feed(&self.chunk[..n]);
+        }
+    }
+}
+
+/// The future returned by [`AsyncReader::read_item`](struct.AsyncReader.html#method.read_item).
+#[derive(Debug)]
+pub struct ReadItem<'a, R, T> {
+    reader: &'a mut AsyncReader<R>,
+    item: PhantomData<fn() -> T>,
+}
+
+impl<'a, R, T> Future for ReadItem<'a, R, T>
+where
+    R: AsyncRead + Unpin,
+    T: DeserializeOwned,
+{
+    type Output = Result<Option<T>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.reader.poll_read_item(cx)
+    }
+}
+
+/// A stream of the items read by an [`AsyncReader`](struct.AsyncReader.html).
+#[derive(Debug)]
+pub struct ItemStream<R, T> {
+    reader: AsyncReader<R>,
+    done: bool,
+    item: PhantomData<fn() -> T>,
+}
+
+impl<R, T> ItemStream<R, T> {
+    /// Unwraps the reader.
+    pub fn into_inner(self) -> AsyncReader<R> {
+        self.reader
+    }
+}
+
+impl<R, T> Stream for ItemStream<R, T>
+where
+    R: AsyncRead + Unpin,
+    T: DeserializeOwned,
+{
+    type Item = Result<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+        let result = ready!(self.reader.poll_read_item(cx));
+        match result {
+            Ok(Some(item)) => Poll::Ready(Some(Ok(item))),
+            Ok(None) => {
+                self.done = true;
+                Poll::Ready(None)
+            }
+            Err(err) => {
+                // Items that can't be deserialized are skipped, other errors can't be recovered.
+                self.done = !err.is_data();
+                Poll::Ready(Some(Err(err)))
+            }
+        }
+    }
+}
+
+/// Writes data items to an asynchronous writer.
+///
+/// Items are serialized into a buffer, which is written when the writer is flushed. As a `Sink`
+/// it also writes the buffer once it holds more than 8 KiB.
+#[derive(Debug)]
+pub struct AsyncWriter<W> {
+    writer: W,
+    sequence: SequenceWriter<Vec<u8>>,
+    // The number of buffered bytes that have been written.
+    written: usize,
+}
+
+impl<W> AsyncWriter<W> {
+    /// Creates a writer that serializes items with the default options.
+    pub fn new(writer: W) -> Self {
+        AsyncWriter::from_serializer(writer, Serializer::new(Vec::new()))
+    }
+
+    /// Creates a writer that serializes items with the options of `ser`, for example in packed
+    /// format or with deterministic encoding. Data in the buffer of `ser` is written first.
+    pub fn from_serializer(writer: W, ser: Serializer<Vec<u8>>) -> Self {
+        AsyncWriter {
+            writer,
+            sequence: SequenceWriter::from_serializer(ser),
+            written: 0,
+        }
+    }
+
+    /// Serializes a value and appends it to the buffer.
+    pub fn write<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.sequence.write(value)
+    }
+
+    /// Returns the data that has been serialized but not written yet.
+    pub fn buffered(&self) -> &[u8] {
+        &self.sequence.get_ref()[self.written..]
+    }
+
+    /// Gets a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Gets a mutable reference to the underlying writer.
+    ///
+    /// Writing to it directly puts the data before the items that are buffered.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Unwraps the writer. Data that has not been written yet is lost.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W> AsyncWriter<W>
+where
+    W: AsyncWrite + Unpin,
+{
+    /// Writes the buffered data and flushes the underlying writer.
+    pub fn flush(&mut self) -> Flush<'_, W> {
+        Flush { writer: self }
+    }
+
+    /// Writes the buffered data and closes the underlying writer.
+    pub fn close(&mut self) -> Close<'_, W> {
+        Close { writer: self }
+    }
+
+    /// Attempts to write the buffered data and flush the underlying writer, like
+    /// [`flush`](#method.flush).
+    pub fn poll_flush(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        ready!(self.poll_write_buffer(cx))?;
+        Pin::new(&mut self.writer).poll_flush(cx).map_err(Error::io)
+    }
+
+    /// Attempts to write the buffered data and close the underlying writer, like
+    /// [`close`](#method.close).
+    pub fn poll_close(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        ready!(self.poll_write_buffer(cx))?;
+        Pin::new(&mut self.writer).poll_close(cx).map_err(Error::io)
+    }
+
+    fn poll_write_buffer(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        while self.written < self.sequence.get_ref().len() {
+            let buf = &self.sequence.get_ref()[self.written..];
+            let n = ready!(Pin::new(&mut self.writer).poll_write(cx, buf)).map_err(Error::io)?;
+            if n == 0 {
+                let err = io::Error::new(io::ErrorKind::WriteZero, "failed to write the buffer");
+                return Poll::Ready(Err(Error::io(err)));
+            }
+            self.written += n;
+        }
+        self.sequence.get_mut().clear();
+        self.written = 0;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W, T> Sink<T> for AsyncWriter<W>
+where
+    W: AsyncWrite + Unpin,
+    T: Serialize,
+{
+    type Error = Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        if self.sequence.get_ref().len() < CHUNK_SIZE {
+            return Poll::Ready(Ok(()));
+        }
+        self.poll_write_buffer(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: T) -> Result<()> {
+        self.write(&item)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        AsyncWriter::poll_flush(&mut *self, cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        AsyncWriter::poll_close(&mut *self, cx)
+    }
+}
+
+/// The future returned by [`AsyncWriter::flush`](struct.AsyncWriter.html#method.flush).
+#[derive(Debug)]
+pub struct Flush<'a, W> {
+    writer: &'a mut AsyncWriter<W>,
+}
+
+impl<'a, W> Future for Flush<'a, W>
+where
+    W: AsyncWrite + Unpin,
+{
+    type Output = Result<()>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.writer.poll_flush(cx)
+    }
+}
+
+/// The future returned by [`AsyncWriter::close`](struct.AsyncWriter.html#method.close).
+#[derive(Debug)]
+pub struct Close<'a, W> {
+    writer: &'a mut AsyncWriter<W>,
+}
+
+impl<'a, W> Future for Close<'a, W>
+where
+    W: AsyncWrite + Unpin,
+{
+    type Output = Result<()>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.writer.poll_close(cx)
+    }
+}
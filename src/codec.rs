@@ -0,0 +1,143 @@
+//! A codec for framed transports.
+//!
+//! [`CborCodec`](struct.CborCodec.html) implements the `Decoder` and `Encoder` traits of
+//! `tokio_util::codec`, so a `Framed` transport reads and writes data items. CBOR items are
+//! self-delimiting and are used as frames without a length prefix. The decoder finds the end of
+//! an item in the read buffer with an [`ItemScanner`](../incremental/struct.ItemScanner.html),
+//! which only scans the bytes that arrived since the previous call.
+//!
+//! This module requires the `codec` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use bytes::BytesMut;
+//! use serde_cbor::codec::CborCodec;
+//! use tokio_util::codec::{Decoder, Encoder};
+//!
+//! let mut codec = CborCodec::<(String, u8)>::new();
+//! let mut buf = BytesMut::new();
+//! codec.encode(("hello", 1), &mut buf).unwrap();
+//!
+//! // The frame is incomplete.
+//! let mut partial = buf.split_to(4);
+//! assert_eq!(codec.decode(&mut partial).unwrap(), None);
+//! partial.unsplit(buf);
+//! assert_eq!(codec.decode(&mut partial).unwrap(), Some(("hello".to_owned(), 1)));
+//! ```
+
+use core::marker::PhantomData;
+
+use bytes::{BufMut, BytesMut};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::de::DecodeLimits;
+use crate::error::{Error, Result};
+use crate::incremental::{self, ItemScanner, Progress};
+
+// The same default as the one of `tokio_util::codec::LengthDelimitedCodec`.
+const DEFAULT_MAX_FRAME_LEN: usize = 8 * 1024 * 1024;
+
+/// Decodes frames into values of type `T` and encodes any serializable values.
+///
+/// The length of frames is limited to 8 MiB by default. The decoder fails as soon as the headers
+/// of a frame show that it is longer, without reserving space for it.
+///
+/// Error offsets are relative to the start of all data decoded by the codec.
+#[derive(Debug)]
+pub struct CborCodec<T> {
+    scanner: ItemScanner,
+    limits: DecodeLimits,
+    // The number of bytes of the frames split off so far.
+    consumed: u64,
+    item: PhantomData<fn() -> T>,
+}
+
+impl<T> CborCodec<T> {
+    /// Creates a codec with the default frame length limit.
+    pub fn new() -> Self {
+        CborCodec {
+            scanner: ItemScanner::new().max_item_len(DEFAULT_MAX_FRAME_LEN),
+            limits: DecodeLimits::new(),
+            consumed: 0,
+            item: PhantomData,
+        }
+    }
+
+    /// Limits the length of decoded frames in bytes.
+    pub fn max_frame_len(mut self, len: usize) -> Self {
+        self.scanner = self.scanner.max_item_len(len);
+        self
+    }
+
+    /// Sets the limits for deserializing frames. The scanner takes its depth limit from them.
+    pub fn limits(mut self, limits: DecodeLimits) -> Self {
+        self.scanner = self.scanner.max_depth(limits.max_depth);
+        self.limits = limits;
+        self
+    }
+}
+
+impl<T> Default for CborCodec<T> {
+    fn default() -> Self {
+        CborCodec::new()
+    }
+}
+
+impl<T> Clone for CborCodec<T> {
+    fn clone(&self) -> Self {
+        CborCodec {
+            scanner: self.scanner.clone(),
+            limits: self.limits,
+            consumed: self.consumed,
+            item: PhantomData,
+        }
+    }
+}
+
+impl<T> Decoder for CborCodec<T>
+where
+    T: DeserializeOwned,
+{
+    type Item = T;
+    type Error = Error;
+
+    /// Decodes the frame at the start of `src` once it is complete.
+    ///
+    /// A frame that is complete but can't be deserialized into a `T` is removed from `src`
+    /// before the error is returned. After a syntax error the codec can't continue.
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<T>> {
+        let progress = self
+            .scanner
+            .scan(src)
+            .map_err(|err| err.offset_by(self.consumed))?;
+        match progress {
+            Progress::Complete(len) => {
+                let frame = src.split_to(len);
+                let base = self.consumed;
+                self.consumed += len as u64;
+                incremental::from_slice(&frame, self.limits)
+                    .map(Some)
+                    .map_err(|err| err.offset_by(base))
+            }
+            Progress::NeedMore(n) => {
+                // The scanner has checked that the frame fits in the limit.
+                src.reserve(n);
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl<T, U> Encoder<U> for CborCodec<T>
+where
+    U: Serialize,
+{
+    type Error = Error;
+
+    fn encode(&mut self, item: U, dst: &mut BytesMut) -> Result<()> {
+        crate::to_writer(dst.writer(), &item)
+    }
+}
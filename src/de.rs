@@ -2,25 +2,293 @@
 
 use byteorder::{BigEndian, ByteOrder};
 use core::f32;
+use core::fmt;
 use core::marker::PhantomData;
+use core::ops::Range;
 use core::result;
 use core::str;
 use half::f16;
 use serde::de;
+#[cfg(any(feature = "std", feature = "alloc"))]
+use serde::ser;
 #[cfg(feature = "std")]
 use std::io;
 
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::collections::BTreeSet;
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(any(feature = "std", feature = "alloc"))]
+use core::cmp::Ordering;
+#[cfg(any(feature = "std", feature = "alloc"))]
+use core::mem;
+#[cfg(feature = "std")]
+use std::collections::{BTreeMap, BTreeSet};
+
 use crate::error::{Error, ErrorCode, Result};
+use crate::raw::RAW_NAME;
 #[cfg(not(feature = "unsealed_read_write"))]
 use crate::read::EitherLifetime;
 #[cfg(feature = "unsealed_read_write")]
 pub use crate::read::EitherLifetime;
-#[cfg(feature = "std")]
-pub use crate::read::IoRead;
 use crate::read::Offset;
 #[cfg(any(feature = "std", feature = "alloc"))]
 pub use crate::read::SliceRead;
+#[cfg(feature = "std")]
+pub use crate::read::{BufIoRead, IoRead};
 pub use crate::read::{MutSliceRead, Read, SliceReadFixed};
+#[cfg(any(feature = "std", feature = "alloc"))]
+use crate::ser::KeyOrder;
+use crate::tags::{visit_bignum, SimpleAccess, TagAccess, TAG_NAME};
+#[cfg(feature = "std")]
+use crate::value::{tagged_value, Value};
+
+// Captures the exact encoding of map keys and raw values. Recordings can be nested, so the bytes of
+// an inner recording are also appended to the outer one when it finishes.
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[derive(Debug, Default)]
+struct Recorder(Option<Vec<u8>>);
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl Recorder {
+    #[inline]
+    fn push(&mut self, bytes: &[u8]) {
+        if let Some(ref mut buf) = self.0 {
+            buf.extend_from_slice(bytes);
+        }
+    }
+
+    #[inline]
+    fn is_recording(&self) -> bool {
+        self.0.is_some()
+    }
+
+    fn start(&mut self) -> Option<Vec<u8>> {
+        self.0.replace(Vec::new())
+    }
+
+    fn finish(&mut self, outer: Option<Vec<u8>>) -> Vec<u8> {
+        let bytes = self.0.take().unwrap_or_default();
+        if let Some(mut outer) = outer {
+            outer.extend_from_slice(&bytes);
+            self.0 = Some(outer);
+        }
+        bytes
+    }
+
+    // The content of the chunks of indefinite strings is collected in the scratch buffer of the
+    // reader, only their headers are recorded. `mark` returns the position of the first chunk.
+    fn mark(&self) -> Option<usize> {
+        self.0.as_ref().map(Vec::len)
+    }
+
+    // Inserts the content of the chunks recorded after `mark` behind their headers.
+    fn insert_chunks(&mut self, mark: Option<usize>, mut content: &[u8]) {
+        if let (Some(ref mut buf), Some(mark)) = (&mut self.0, mark) {
+            let headers = buf.split_off(mark);
+            let mut input = &headers[..];
+            while !input.is_empty() {
+                let rest = input;
+                let len = match split_header(&mut input) {
+                    Some((_, len)) => len,
+                    None => break,
+                };
+                buf.extend_from_slice(&rest[..rest.len() - input.len()]);
+                if let Some(chunk) = len.and_then(|len| split_input(&mut content, len as usize)) {
+                    buf.extend_from_slice(chunk);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(any(feature = "std", feature = "alloc")))]
+#[derive(Debug, Default)]
+struct Recorder;
+
+#[cfg(not(any(feature = "std", feature = "alloc")))]
+impl Recorder {
+    #[inline]
+    fn push(&mut self, _bytes: &[u8]) {}
+
+    #[inline]
+    fn is_recording(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    fn mark(&self) {}
+
+    #[inline]
+    fn insert_chunks(&mut self, _mark: (), _content: &[u8]) {}
+}
+
+// Re-encodes a recorded map key with shortest arguments, definite lengths and preferred floats,
+// so that different encodings of the same key compare equal.
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn normalize_key(key: &[u8]) -> Vec<u8> {
+    let mut input = key;
+    let mut out = Vec::with_capacity(key.len());
+    match normalize_item(&mut input, &mut out) {
+        Some(()) => out,
+        // The key has already been decoded successfully, this is not expected to happen.
+        None => key.to_vec(),
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn split_input<'a>(input: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+    if input.len() < n {
+        return None;
+    }
+    let (head, tail) = input.split_at(n);
+    *input = tail;
+    Some(head)
+}
+
+// Returns the major type and argument of the next header, `None` as argument for indefinite
+// lengths.
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn split_header(input: &mut &[u8]) -> Option<(u8, Option<u64>)> {
+    let byte = split_input(input, 1)?[0];
+    let argument = match byte & 0x1f {
+        info @ 0..=23 => u64::from(info),
+        24 => u64::from(split_input(input, 1)?[0]),
+        25 => u64::from(BigEndian::read_u16(split_input(input, 2)?)),
+        26 => u64::from(BigEndian::read_u32(split_input(input, 4)?)),
+        27 => BigEndian::read_u64(split_input(input, 8)?),
+        31 => return Some((byte >> 5, None)),
+        _ => return None,
+    };
+    Some((byte >> 5, Some(argument)))
+}
+
+// An array, map or tag whose items are being normalized.
+#[cfg(any(feature = "std", feature = "alloc"))]
+struct NormalizeFrame {
+    major: u8,
+    // Items still needed, `None` for indefinite lengths, which end with a break.
+    remaining: Option<u64>,
+    // For indefinite lengths, the items so far and the output before the array or map. The
+    // content is written to a buffer of its own until its length is known.
+    items: u64,
+    outer: Vec<u8>,
+}
+
+// Arrays, maps and tags are kept on a stack, so deeply nested keys don't overflow the call stack.
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[allow(clippy::mem_replace_with_default)] // `mem::take` requires Rust 1.40.
+fn normalize_item(input: &mut &[u8], out: &mut Vec<u8>) -> Option<()> {
+    let mut stack: Vec<NormalizeFrame> = Vec::new();
+    loop {
+        if let Some(top) = stack.last_mut() {
+            match top.remaining {
+                Some(ref mut remaining) => *remaining -= 1,
+                None => top.items += 1,
+            }
+        }
+        let first = *input.first()?;
+        let (major, argument) = split_header(input)?;
+        let mut ser = crate::ser::Serializer::new(&mut *out);
+        match (major, argument) {
+            (0, Some(value)) | (1, Some(value)) => ser.write_u64(major, value).ok()?,
+            (6, Some(tag)) => {
+                ser.write_u64(major, tag).ok()?;
+                stack.push(NormalizeFrame {
+                    major,
+                    remaining: Some(1),
+                    items: 0,
+                    outer: Vec::new(),
+                });
+            }
+            (2, Some(len)) | (3, Some(len)) => {
+                ser.write_u64(major, len).ok()?;
+                out.extend_from_slice(split_input(input, len as usize)?);
+            }
+            (2, None) | (3, None) => {
+                let mut content = Vec::new();
+                while *input.first()? != 0xff {
+                    match split_header(input)? {
+                        (chunk_major, Some(len)) if chunk_major == major => {
+                            content.extend_from_slice(split_input(input, len as usize)?);
+                        }
+                        _ => return None,
+                    }
+                }
+                *input = &input[1..];
+                ser.write_u64(major, content.len() as u64).ok()?;
+                out.extend_from_slice(&content);
+            }
+            (4, Some(len)) | (5, Some(len)) => {
+                ser.write_u64(major, len).ok()?;
+                let items = if major == 5 { len.checked_mul(2)? } else { len };
+                stack.push(NormalizeFrame {
+                    major,
+                    remaining: Some(items),
+                    items: 0,
+                    outer: Vec::new(),
+                });
+            }
+            (4, None) | (5, None) => {
+                let outer = mem::replace(out, Vec::new());
+                stack.push(NormalizeFrame {
+                    major,
+                    remaining: None,
+                    items: 0,
+                    outer,
+                });
+            }
+            (7, Some(bits)) => match first {
+                0xf9 => {
+                    let value = f32::from(f16::from_bits(bits as u16));
+                    ser::Serializer::serialize_f32(&mut ser, value).ok()?;
+                }
+                0xfa => {
+                    let value = f32::from_bits(bits as u32);
+                    ser::Serializer::serialize_f32(&mut ser, value).ok()?;
+                }
+                0xfb => {
+                    let value = f64::from_bits(bits);
+                    ser::Serializer::serialize_f64(&mut ser, value).ok()?;
+                }
+                0xf8 => out.extend_from_slice(&[first, bits as u8]),
+                _ => out.push(first),
+            },
+            _ => return None,
+        }
+
+        // Finish the arrays, maps and tags that are complete.
+        loop {
+            let complete = match stack.last() {
+                None => return Some(()),
+                Some(top) => match top.remaining {
+                    Some(remaining) => remaining == 0,
+                    None => *input.first()? == 0xff,
+                },
+            };
+            if !complete {
+                break;
+            }
+            let frame = stack.pop()?;
+            if frame.remaining.is_none() {
+                *input = &input[1..];
+                let content = mem::replace(out, frame.outer);
+                let len = if frame.major == 5 {
+                    frame.items / 2
+                } else {
+                    frame.items
+                };
+                crate::ser::Serializer::new(&mut *out)
+                    .write_u64(frame.major, len)
+                    .ok()?;
+                out.extend_from_slice(&content);
+            }
+        }
+    }
+}
+
 /// Decodes a value from CBOR data in a slice.
 ///
 /// # Examples
@@ -92,6 +360,9 @@ where
 
 /// Decodes a value from CBOR data in a reader.
 ///
+/// Headers are read from the reader one byte at a time. For readers without their own buffer,
+/// like files and sockets, [`from_buf_reader`](fn.from_buf_reader.html) is much faster.
+///
 /// # Examples
 ///
 /// Deserialize a `String`
@@ -123,15 +394,157 @@ where
     Ok(value)
 }
 
+/// Decodes a value from CBOR data in a buffered reader.
+///
+/// Files and sockets should be wrapped in a `BufReader` and decoded with this function instead
+/// of [`from_reader`](fn.from_reader.html), which reads them one header byte at a time.
+///
+/// # Examples
+///
+/// ```
+/// # use serde_cbor::de;
+/// use std::io::BufReader;
+///
+/// let v: Vec<u8> = vec![0x82, 0x01, 0x02];
+/// let value: Vec<u8> = de::from_buf_reader(BufReader::new(&v[..])).unwrap();
+/// assert_eq!(value, [1, 2]);
+/// ```
+#[cfg(feature = "std")]
+pub fn from_buf_reader<T, R>(reader: R) -> Result<T>
+where
+    T: de::DeserializeOwned,
+    R: io::BufRead,
+{
+    let mut deserializer = Deserializer::from_buf_reader(reader);
+    let value = de::Deserialize::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+// The number of levels that earlier versions accepted.
+pub(crate) const DEFAULT_MAX_DEPTH: usize = 127;
+
+/// Limits on the resources used to decode untrusted input.
+///
+/// Each limit fails decoding with its own error, at the offset where it was exceeded. By default
+/// only the nesting depth is limited, to 127 levels.
+///
+/// The limits apply to skipped items as well, except for the total length limit, which only
+/// counts strings that are decoded.
+///
+/// # Examples
+///
+/// ```
+/// use serde_cbor::de::{DecodeLimits, Deserializer};
+/// use serde::Deserialize;
+///
+/// let limits = DecodeLimits::new().max_depth(16).max_length(1024).max_elements(64);
+/// let bytes = serde_cbor::to_vec(&vec![0; 100]).unwrap();
+/// let mut deserializer = Deserializer::from_slice(&bytes).limits(limits);
+/// assert!(Vec::<u8>::deserialize(&mut deserializer).is_err());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeLimits {
+    pub(crate) max_depth: usize,
+    max_tag_depth: usize,
+    max_length: usize,
+    max_elements: usize,
+    max_chunks: usize,
+    max_total_length: usize,
+}
+
+impl DecodeLimits {
+    /// Creates the default limits.
+    pub fn new() -> DecodeLimits {
+        DecodeLimits {
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_tag_depth: usize::max_value(),
+            max_length: usize::max_value(),
+            max_elements: usize::max_value(),
+            max_chunks: usize::max_value(),
+            max_total_length: usize::max_value(),
+        }
+    }
+
+    /// Limits the number of arrays, maps and tags that can be nested in each other.
+    ///
+    /// A limit of `n` accepts `n` levels and fails at level `n + 1`. The default of 127 levels is
+    /// the limit of earlier versions, which always applied it.
+    ///
+    /// Deserializing uses the call stack for each level, so a high limit can overflow it.
+    /// `Deserializer::skip_value` and `Deserializer::read_value` keep their state on the heap
+    /// instead.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Limits the number of tags that can enclose an item, directly or with other items in
+    /// between.
+    pub fn max_tag_depth(mut self, depth: usize) -> Self {
+        self.max_tag_depth = depth;
+        self
+    }
+
+    /// Limits the length of each string and byte string in bytes. For indefinite length strings
+    /// the total length of all chunks is limited.
+    pub fn max_length(mut self, len: usize) -> Self {
+        self.max_length = len;
+        self
+    }
+
+    /// Limits the number of elements of each array and the number of entries of each map.
+    pub fn max_elements(mut self, len: usize) -> Self {
+        self.max_elements = len;
+        self
+    }
+
+    /// Limits the number of chunks of each indefinite length string.
+    pub fn max_chunks(mut self, chunks: usize) -> Self {
+        self.max_chunks = chunks;
+        self
+    }
+
+    /// Limits the total length of all strings and byte strings that are decoded by the
+    /// deserializer in bytes.
+    ///
+    /// This bounds the memory used for the contents of strings, but not the memory used for
+    /// arrays, maps and other values, which only `max_elements` and `max_depth` bound. The
+    /// length is counted over the lifetime of the deserializer, for a `StreamDeserializer` it
+    /// includes all items read so far.
+    pub fn max_total_length(mut self, len: usize) -> Self {
+        self.max_total_length = len;
+        self
+    }
+}
+
+impl Default for DecodeLimits {
+    fn default() -> DecodeLimits {
+        DecodeLimits::new()
+    }
+}
+
 /// A Serde `Deserialize`r of CBOR data.
 #[derive(Debug)]
 pub struct Deserializer<R> {
     read: R,
-    remaining_depth: u8,
+    limits: DecodeLimits,
+    remaining_depth: usize,
+    remaining_tags: usize,
+    total_length: u64,
     accept_named: bool,
     accept_packed: bool,
     accept_standard_enums: bool,
     accept_legacy_enums: bool,
+    strict: bool,
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    key_order: KeyOrder,
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    deny_duplicate_keys: bool,
+    // The location of the current item, if it is tracked.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    path: Option<String>,
+    recorder: Recorder,
 }
 
 #[cfg(feature = "std")]
@@ -145,6 +558,20 @@ where
     }
 }
 
+#[cfg(feature = "std")]
+impl<R> Deserializer<BufIoRead<R>>
+where
+    R: io::BufRead,
+{
+    /// Constructs a `Deserializer` which reads from a `BufRead`er.
+    ///
+    /// Bytes after the decoded data stay in the buffer of the reader, so a reader passed by
+    /// reference can be used to read them afterwards.
+    pub fn from_buf_reader(reader: R) -> Deserializer<BufIoRead<R>> {
+        Deserializer::new(BufIoRead::new(reader))
+    }
+}
+
 #[cfg(any(feature = "std", feature = "alloc"))]
 impl<'a> Deserializer<SliceRead<'a>> {
     /// Constructs a `Deserializer` which reads from a slice.
@@ -185,11 +612,22 @@ where
     pub fn new(read: R) -> Self {
         Deserializer {
             read,
-            remaining_depth: 128,
+            limits: DecodeLimits::new(),
+            remaining_depth: DEFAULT_MAX_DEPTH,
+            remaining_tags: usize::max_value(),
+            total_length: 0,
             accept_named: true,
             accept_packed: true,
             accept_standard_enums: true,
             accept_legacy_enums: true,
+            strict: false,
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            key_order: KeyOrder::Bytewise,
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            deny_duplicate_keys: false,
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            path: None,
+            recorder: Recorder::default(),
         }
     }
 
@@ -217,6 +655,102 @@ where
         self
     }
 
+    /// Limit the resources used to decode the input.
+    ///
+    /// The limits replace the default limit of 127 nested arrays, maps and tags.
+    pub fn limits(mut self, limits: DecodeLimits) -> Self {
+        self.remaining_depth = limits.max_depth;
+        self.remaining_tags = limits.max_tag_depth;
+        self.limits = limits;
+        self
+    }
+
+    /// Only accept input in deterministic encoding.
+    ///
+    /// This is useful to verify that signed data received from a third party has a single
+    /// representation. The following encodings are rejected, with the offset of the offending
+    /// item in the error:
+    ///
+    /// * integers, lengths and tags that are not encoded in their shortest form,
+    /// * indefinite length strings, arrays and maps,
+    /// * floats that can be encoded in a shorter precision without losing information, as well
+    ///   as infinities and NaNs not encoded as half precision floats,
+    /// * map keys that are not sorted as required by `key_order` and duplicate map keys. These
+    ///   are only checked with the `std` or `alloc` feature.
+    ///
+    /// Data written by a [`Serializer`](../ser/struct.Serializer.html) in canonical mode is
+    /// accepted.
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    /// Choose the order of map keys required in strict mode.
+    ///
+    /// The default is `KeyOrder::Bytewise`.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    pub fn key_order(mut self, order: KeyOrder) -> Self {
+        self.key_order = order;
+        self
+    }
+
+    /// Reject maps and structs that contain the same key more than once.
+    ///
+    /// Otherwise the result depends on the deserialized type, for example a `HashMap` or a
+    /// `Value` keeps the last entry. Keys are compared by their encoding, after integers,
+    /// lengths and floats have been converted to their shortest form and indefinite lengths to
+    /// definite ones. Strict mode rejects duplicate keys as well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serde_cbor::Deserializer;
+    /// use std::collections::HashMap;
+    ///
+    /// // {"a": 1, "a": 2}
+    /// let bytes = b"\xa2\x61a\x01\x61a\x02";
+    /// let mut deserializer = Deserializer::from_slice(bytes).deny_duplicate_keys();
+    /// let result: Result<HashMap<String, u8>, _> = serde::Deserialize::deserialize(&mut deserializer);
+    /// assert_eq!(result.unwrap_err().offset(), 4);
+    /// ```
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    pub fn deny_duplicate_keys(mut self) -> Self {
+        self.deny_duplicate_keys = true;
+        self
+    }
+
+    /// Track the location of the current item, so that errors report it with
+    /// [`Error::path`](../error/struct.Error.html#method.path).
+    ///
+    /// The path is made of the indices of array elements like `[3]` and the keys of map
+    /// entries. Text keys such as the names of fields are written as `.name`, other keys in
+    /// diagnostic notation in brackets, so the fields of packed structs appear as `[2]`. This
+    /// records the encoding of every map key and is off by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serde_cbor::Deserializer;
+    /// use std::collections::BTreeMap;
+    ///
+    /// // {"servers": [{"port": 80}, {"port": null}]}
+    /// let bytes = b"\xa1\x67servers\x82\xa1\x64port\x18\x50\xa1\x64port\xf6";
+    /// let mut deserializer = Deserializer::from_slice(bytes).track_path();
+    /// let result: Result<BTreeMap<String, Vec<BTreeMap<String, u16>>>, _> =
+    ///     serde::Deserialize::deserialize(&mut deserializer);
+    /// let err = result.unwrap_err();
+    /// assert_eq!(err.path(), Some(".servers[1].port"));
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "invalid type: null, expected u16 at .servers[1].port"
+    /// );
+    /// ```
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    pub fn track_path(mut self) -> Self {
+        self.path = Some(String::new());
+        self
+    }
+
     /// This method should be called after a value has been deserialized to ensure there is no
     /// trailing data in the input source.
     pub fn end(&mut self) -> Result<()> {
@@ -226,6 +760,207 @@ where
         }
     }
 
+    /// Skips the next data item without decoding it.
+    ///
+    /// Only the headers are parsed, the content of strings is neither copied (if the input source
+    /// allows it) nor checked to be valid UTF-8. Unassigned simple values are skipped as well. In
+    /// strict mode and when duplicate keys are denied the item is checked as if it was decoded.
+    pub fn skip_value(&mut self) -> Result<()> {
+        if self.checks_ignored() {
+            return self.parse_value(de::IgnoredAny).map(|_| ());
+        }
+        self.skip_item()
+    }
+
+    /// Decodes the next data item into a `Value` without recursion.
+    ///
+    /// Arrays, maps and tags are kept on a stack on the heap, so deeply nested items don't
+    /// overflow the call stack. Their nesting is still limited by `DecodeLimits::max_depth`, which
+    /// can be raised for trusted input. The result is the same as deserializing a `Value`.
+    ///
+    /// Dropping a `Value` doesn't recurse either, but cloning, comparing and formatting one do.
+    ///
+    /// ```
+    /// use serde_cbor::de::{DecodeLimits, Deserializer};
+    /// use serde_cbor::Serializer;
+    ///
+    /// let mut bytes = vec![0x81; 1000];
+    /// bytes.push(0x01);
+    /// let mut deserializer =
+    ///     Deserializer::from_slice(&bytes).limits(DecodeLimits::new().max_depth(1000));
+    /// let value = deserializer.read_value().unwrap();
+    ///
+    /// let mut serializer = Serializer::new(Vec::new());
+    /// serializer.write_value(&value).unwrap();
+    /// assert_eq!(serializer.into_inner(), bytes);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn read_value(&mut self) -> Result<Value> {
+        let mut stack = Vec::new();
+        let result = self.read_value_with(&mut stack);
+        // Finish the recordings of map keys that were interrupted by an error.
+        for frame in stack.into_iter().rev() {
+            if let ValueContent::Map {
+                recording: Some((outer, _)),
+                ..
+            } = frame.content
+            {
+                self.recorder.finish(outer);
+            }
+        }
+        result
+    }
+
+    #[cfg(feature = "std")]
+    fn read_value_with(&mut self, stack: &mut Vec<ValueFrame>) -> Result<Value> {
+        let mut tags = 0;
+        loop {
+            let more = match stack.last_mut() {
+                Some(frame) => self.next_item(&mut frame.items)?,
+                None => true,
+            };
+            let mut value = if more {
+                if let Some(frame) = stack.last_mut() {
+                    self.start_value_item(frame)?;
+                }
+                match self.read_value_header(stack.len(), &mut tags)? {
+                    Some(frame) => {
+                        stack.push(frame);
+                        continue;
+                    }
+                    None => de::Deserialize::deserialize(&mut *self)?,
+                }
+            } else {
+                match stack.pop().map(|frame| frame.content) {
+                    Some(ValueContent::Array(items)) => Value::Array(items),
+                    Some(ValueContent::Map { entries, .. }) => Value::Map(entries),
+                    _ => unreachable!(),
+                }
+            };
+            // Add the finished item to the enclosing array, map or tag.
+            loop {
+                let frame = match stack.last_mut() {
+                    Some(frame) => frame,
+                    None => return Ok(value),
+                };
+                match frame.content {
+                    ValueContent::Array(ref mut items) => items.push(value),
+                    ValueContent::Map {
+                        ref mut entries,
+                        ref mut key,
+                        ref mut keys,
+                        ref mut recording,
+                        ..
+                    } => match key.take() {
+                        Some(key) => {
+                            entries.insert(key, value);
+                        }
+                        None => {
+                            if let Some((outer, offset)) = recording.take() {
+                                let encoded = self.recorder.finish(outer);
+                                self.check_key(encoded, offset, keys)?;
+                            }
+                            *key = Some(value);
+                        }
+                    },
+                    ValueContent::Tag(tag) => {
+                        stack.pop();
+                        tags -= 1;
+                        value = tagged_value(tag, value);
+                        continue;
+                    }
+                }
+                break;
+            }
+        }
+    }
+
+    // Checks the start of a map key for `read_value` like `MapAccess` does.
+    #[cfg(feature = "std")]
+    fn start_value_item(&mut self, frame: &mut ValueFrame) -> Result<()> {
+        if let ValueContent::Map {
+            key: None,
+            ref mut recording,
+            accept_packed,
+            accept_named,
+            ..
+        } = frame.content
+        {
+            match self.peek()? {
+                Some(0x00..=0x1b) if !accept_packed => {
+                    return Err(self.error(ErrorCode::WrongStructFormat));
+                }
+                Some(0x60..=0x7f) if !accept_named => {
+                    return Err(self.error(ErrorCode::WrongStructFormat));
+                }
+                _ => {}
+            }
+            if self.checks_keys() {
+                let offset = self.read.offset();
+                *recording = Some((self.recorder.start(), offset));
+            }
+        }
+        Ok(())
+    }
+
+    // Reads the header of the next item for `read_value` if it is an array, map or tag, and
+    // returns a frame for its content. Other items are left to the `Value` deserializer.
+    #[cfg(feature = "std")]
+    fn read_value_header(&mut self, depth: usize, tags: &mut usize) -> Result<Option<ValueFrame>> {
+        let byte = match self.peek()? {
+            Some(byte @ 0x80..=0x9b)
+            | Some(byte @ 0x9f)
+            | Some(byte @ 0xa0..=0xbb)
+            | Some(byte @ 0xbf)
+            | Some(byte @ 0xc0..=0xdb) => byte,
+            _ => return Ok(None),
+        };
+        self.consume();
+        let major = byte >> 5;
+        if major == 6 {
+            let tag = self.parse_tag(byte)?;
+            self.enter_tag(tags)?;
+            self.check_depth(depth)?;
+            return Ok(Some(ValueFrame {
+                items: SkipFrame::definite(major, 1),
+                content: ValueContent::Tag(tag),
+            }));
+        }
+        let items = match byte & 0x1f {
+            31 => {
+                self.check_definite()?;
+                self.check_depth(depth)?;
+                SkipFrame::indefinite(major)
+            }
+            additional => {
+                let len = if additional < 24 {
+                    u64::from(additional)
+                } else {
+                    self.parse_argument(additional)?
+                };
+                if len > usize::max_value() as u64 {
+                    return Err(self.error(ErrorCode::LengthOutOfRange));
+                }
+                self.check_elements(len)?;
+                self.check_depth(depth)?;
+                SkipFrame::definite(major, len)
+            }
+        };
+        let content = if major == 4 {
+            ValueContent::Array(Vec::new())
+        } else {
+            ValueContent::Map {
+                entries: BTreeMap::new(),
+                key: None,
+                accept_packed: self.accept_packed,
+                accept_named: self.accept_named,
+                keys: BTreeSet::new(),
+                recording: None,
+            }
+        };
+        Ok(Some(ValueFrame { items, content }))
+    }
+
     /// Turn a CBOR deserializer into an iterator over values of type T.
     #[allow(clippy::should_implement_trait)] // Trait doesn't allow unconstrained T.
     pub fn into_iter<T>(self) -> StreamDeserializer<'de, R, T>
@@ -233,14 +968,21 @@ where
         T: de::Deserialize<'de>,
     {
         StreamDeserializer {
+            valid_up_to: self.read.offset(),
             de: self,
+            allow_truncated: false,
+            truncated: false,
             output: PhantomData,
             lifetime: PhantomData,
         }
     }
 
     fn next(&mut self) -> Result<Option<u8>> {
-        self.read.next()
+        let next = self.read.next()?;
+        if let Some(byte) = next {
+            self.recorder.push(&[byte]);
+        }
+        Ok(next)
     }
 
     fn peek(&mut self) -> Result<Option<u8>> {
@@ -248,9 +990,18 @@ where
     }
 
     fn consume(&mut self) {
+        if let Ok(Some(byte)) = self.read.peek() {
+            self.recorder.push(&[byte]);
+        }
         self.read.discard();
     }
 
+    fn read_into(&mut self, buf: &mut [u8]) -> Result<()> {
+        self.read.read_into(buf)?;
+        self.recorder.push(buf);
+        Ok(())
+    }
+
     fn error(&self, reason: ErrorCode) -> Error {
         let offset = self.read.offset();
         Error::syntax(reason, offset)
@@ -263,31 +1014,233 @@ where
         }
     }
 
-    fn parse_u16(&mut self) -> Result<u16> {
-        let mut buf = [0; 2];
-        self.read.read_into(&mut buf)?;
-        Ok(BigEndian::read_u16(&buf))
+    fn parse_u16(&mut self) -> Result<u16> {
+        let mut buf = [0; 2];
+        self.read_into(&mut buf)?;
+        Ok(BigEndian::read_u16(&buf))
+    }
+
+    fn parse_u32(&mut self) -> Result<u32> {
+        let mut buf = [0; 4];
+        self.read_into(&mut buf)?;
+        Ok(BigEndian::read_u32(&buf))
+    }
+
+    fn parse_u64(&mut self) -> Result<u64> {
+        let mut buf = [0; 8];
+        self.read_into(&mut buf)?;
+        Ok(BigEndian::read_u64(&buf))
+    }
+
+    // Reads the argument of an item with `additional` information between 24 and 27. In strict
+    // mode the argument must not fit into a shorter encoding.
+    fn parse_argument(&mut self, additional: u8) -> Result<u64> {
+        let (value, width) = match additional {
+            24 => (u64::from(self.parse_u8()?), 1),
+            25 => (u64::from(self.parse_u16()?), 2),
+            26 => (u64::from(self.parse_u32()?), 4),
+            _ => (self.parse_u64()?, 8),
+        };
+        let shortest = match width {
+            1 => value >= 24,
+            2 => value > 0xff,
+            4 => value > 0xffff,
+            _ => value > 0xffff_ffff,
+        };
+        if self.strict && !shortest {
+            let offset = self.read.offset() - 1 - width;
+            return Err(Error::syntax(ErrorCode::NonShortestArgument, offset));
+        }
+        Ok(value)
+    }
+
+    fn parse_arg_u8(&mut self) -> Result<u8> {
+        self.parse_argument(24).map(|value| value as u8)
+    }
+
+    fn parse_arg_u16(&mut self) -> Result<u16> {
+        self.parse_argument(25).map(|value| value as u16)
+    }
+
+    fn parse_arg_u32(&mut self) -> Result<u32> {
+        self.parse_argument(26).map(|value| value as u32)
+    }
+
+    fn parse_arg_u64(&mut self) -> Result<u64> {
+        self.parse_argument(27)
+    }
+
+    // Indefinite length items are rejected in strict mode, the header has already been read.
+    fn check_definite(&self) -> Result<()> {
+        if self.strict {
+            let offset = self.read.offset() - 1;
+            return Err(Error::syntax(ErrorCode::IndefiniteLength, offset));
+        }
+        Ok(())
+    }
+
+    // Rejects floats in strict mode that are not encoded with the preferred precision.
+    fn check_preferred_float(&self, preferred: bool, width: u64) -> Result<()> {
+        if self.strict && !preferred {
+            let offset = self.read.offset() - 1 - width;
+            return Err(Error::syntax(ErrorCode::NonPreferredFloat, offset));
+        }
+        Ok(())
+    }
+
+    fn check_limit(&self, value: u64, limit: usize, code: ErrorCode) -> Result<()> {
+        if value > limit as u64 {
+            return Err(self.error(code));
+        }
+        Ok(())
+    }
+
+    fn check_length(&self, len: u64) -> Result<()> {
+        self.check_limit(len, self.limits.max_length, ErrorCode::LengthLimitExceeded)
+    }
+
+    fn check_elements(&self, len: u64) -> Result<()> {
+        self.check_limit(
+            len,
+            self.limits.max_elements,
+            ErrorCode::ElementLimitExceeded,
+        )
+    }
+
+    fn check_chunks(&self, chunks: u64) -> Result<()> {
+        self.check_limit(
+            chunks,
+            self.limits.max_chunks,
+            ErrorCode::ChunkLimitExceeded,
+        )
+    }
+
+    // Counts the length of a decoded string towards the total length limit.
+    fn count_length(&mut self, len: u64) -> Result<()> {
+        self.total_length = self.total_length.saturating_add(len);
+        self.check_limit(
+            self.total_length,
+            self.limits.max_total_length,
+            ErrorCode::TotalLengthLimitExceeded,
+        )
+    }
+
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn checks_keys(&self) -> bool {
+        self.strict || self.deny_duplicate_keys
+    }
+
+    // Deserializes a map key. Its encoding is recorded if it is checked or if the path is
+    // tracked, in which case it is kept in `last_key`.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn parse_key<K>(
+        &mut self,
+        seed: K,
+        keys: &mut BTreeSet<Vec<u8>>,
+        last_key: &mut Vec<u8>,
+    ) -> Result<K::Value>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        if !self.checks_keys() && self.path.is_none() {
+            return seed.deserialize(self);
+        }
+        let offset = self.read.offset();
+        let outer = self.recorder.start();
+        let value = seed.deserialize(&mut *self);
+        let key = self.recorder.finish(outer);
+        let value = value?;
+        if self.path.is_some() {
+            last_key.clone_from(&key);
+        }
+        if self.checks_keys() {
+            self.check_key(key, offset, keys)?;
+        }
+        Ok(value)
+    }
+
+    // Deserializes an array element or map value with `segment` appended to the tracked path.
+    // Errors get the path of the innermost item they occurred in.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn tracked<F, T>(&mut self, segment: PathSegment<'_>, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Deserializer<R>) -> Result<T>,
+    {
+        let len = match self.path {
+            Some(ref mut path) => {
+                let len = path.len();
+                segment.push_to(path);
+                len
+            }
+            None => return f(self),
+        };
+        let result = f(self).map_err(|err| self.at_path(err));
+        if let Some(ref mut path) = self.path {
+            path.truncate(len);
+        }
+        result
+    }
+
+    #[cfg(not(any(feature = "std", feature = "alloc")))]
+    fn tracked<F, T>(&mut self, _segment: PathSegment<'_>, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Deserializer<R>) -> Result<T>,
+    {
+        f(self)
     }
 
-    fn parse_u32(&mut self) -> Result<u32> {
-        let mut buf = [0; 4];
-        self.read.read_into(&mut buf)?;
-        Ok(BigEndian::read_u32(&buf))
+    // Adds the tracked path to an error that doesn't have one yet.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn at_path(&self, err: Error) -> Error {
+        match self.path {
+            Some(ref path) => err.with_path(path),
+            None => err,
+        }
     }
 
-    fn parse_u64(&mut self) -> Result<u64> {
-        let mut buf = [0; 8];
-        self.read.read_into(&mut buf)?;
-        Ok(BigEndian::read_u64(&buf))
+    #[cfg(not(any(feature = "std", feature = "alloc")))]
+    fn at_path(&self, err: Error) -> Error {
+        err
+    }
+
+    // Checks the recorded encoding of a map key that starts at `offset`. In strict mode it must
+    // follow the previous key, which is the only one kept in `keys`. Otherwise `keys` holds the
+    // normalized encodings of all previous keys.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn check_key(&self, key: Vec<u8>, offset: u64, keys: &mut BTreeSet<Vec<u8>>) -> Result<()> {
+        if self.strict {
+            if let Some(last_key) = keys.iter().next_back() {
+                match self.key_order.compare(last_key, &key) {
+                    Ordering::Less => {}
+                    Ordering::Equal => return Err(Error::syntax(ErrorCode::DuplicateKey, offset)),
+                    Ordering::Greater => {
+                        return Err(Error::syntax(ErrorCode::UnsortedKeys, offset))
+                    }
+                }
+            }
+            keys.clear();
+            keys.insert(key);
+        } else if !keys.insert(normalize_key(&key)) {
+            return Err(Error::syntax(ErrorCode::DuplicateKey, offset));
+        }
+        Ok(())
     }
 
     fn parse_bytes<V>(&mut self, len: usize, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
+        self.check_length(len as u64)?;
+        self.count_length(len as u64)?;
         match self.read.read(len)? {
-            EitherLifetime::Long(buf) => visitor.visit_borrowed_bytes(buf),
-            EitherLifetime::Short(buf) => visitor.visit_bytes(buf),
+            EitherLifetime::Long(buf) => {
+                self.recorder.push(buf);
+                visitor.visit_borrowed_bytes(buf)
+            }
+            EitherLifetime::Short(buf) => {
+                self.recorder.push(buf);
+                visitor.visit_bytes(buf)
+            }
         }
     }
 
@@ -295,14 +1248,18 @@ where
     where
         V: de::Visitor<'de>,
     {
+        self.check_definite()?;
+        let mark = self.recorder.mark();
         self.read.clear_buffer();
+        let mut chunks = 0;
+        let mut total = 0;
         loop {
             let byte = self.parse_u8()?;
             let len = match byte {
                 0x40..=0x57 => byte as usize - 0x40,
-                0x58 => self.parse_u8()? as usize,
-                0x59 => self.parse_u16()? as usize,
-                0x5a => self.parse_u32()? as usize,
+                0x58 => self.parse_arg_u8()? as usize,
+                0x59 => self.parse_arg_u16()? as usize,
+                0x5a => self.parse_arg_u32()? as usize,
                 0x5b => {
                     let len = self.parse_u64()?;
                     if len > usize::max_value() as u64 {
@@ -314,12 +1271,23 @@ where
                 _ => return Err(self.error(ErrorCode::UnexpectedCode)),
             };
 
+            chunks += 1;
+            total += len as u64;
+            self.check_chunks(chunks)?;
+            self.check_length(total)?;
+            self.count_length(len as u64)?;
             self.read.read_to_buffer(len)?;
         }
 
         match self.read.take_buffer() {
-            EitherLifetime::Long(buf) => visitor.visit_borrowed_bytes(buf),
-            EitherLifetime::Short(buf) => visitor.visit_bytes(buf),
+            EitherLifetime::Long(buf) => {
+                self.recorder.insert_chunks(mark, buf);
+                visitor.visit_borrowed_bytes(buf)
+            }
+            EitherLifetime::Short(buf) => {
+                self.recorder.insert_chunks(mark, buf);
+                visitor.visit_bytes(buf)
+            }
         }
     }
 
@@ -338,13 +1306,17 @@ where
     where
         V: de::Visitor<'de>,
     {
+        self.check_length(len as u64)?;
+        self.count_length(len as u64)?;
         if let Some(offset) = self.read.offset().checked_add(len as u64) {
             match self.read.read(len)? {
                 EitherLifetime::Long(buf) => {
+                    self.recorder.push(buf);
                     let s = Self::convert_str(buf, offset)?;
                     visitor.visit_borrowed_str(s)
                 }
                 EitherLifetime::Short(buf) => {
+                    self.recorder.push(buf);
                     let s = Self::convert_str(buf, offset)?;
                     visitor.visit_str(s)
                 }
@@ -362,14 +1334,18 @@ where
     where
         V: de::Visitor<'de>,
     {
+        self.check_definite()?;
+        let mark = self.recorder.mark();
         self.read.clear_buffer();
+        let mut chunks = 0;
+        let mut total = 0;
         loop {
             let byte = self.parse_u8()?;
             let len = match byte {
                 0x60..=0x77 => byte as usize - 0x60,
-                0x78 => self.parse_u8()? as usize,
-                0x79 => self.parse_u16()? as usize,
-                0x7a => self.parse_u32()? as usize,
+                0x78 => self.parse_arg_u8()? as usize,
+                0x79 => self.parse_arg_u16()? as usize,
+                0x7a => self.parse_arg_u32()? as usize,
                 0x7b => {
                     let len = self.parse_u64()?;
                     if len > usize::max_value() as u64 {
@@ -381,41 +1357,297 @@ where
                 _ => return Err(self.error(ErrorCode::UnexpectedCode)),
             };
 
+            chunks += 1;
+            total += len as u64;
+            self.check_chunks(chunks)?;
+            self.check_length(total)?;
+            self.count_length(len as u64)?;
             self.read.read_to_buffer(len)?;
         }
 
         let offset = self.read.offset();
         match self.read.take_buffer() {
             EitherLifetime::Long(buf) => {
+                self.recorder.insert_chunks(mark, buf);
                 let s = Self::convert_str(buf, offset)?;
                 visitor.visit_borrowed_str(s)
             }
             EitherLifetime::Short(buf) => {
+                self.recorder.insert_chunks(mark, buf);
                 let s = Self::convert_str(buf, offset)?;
                 visitor.visit_str(s)
             }
         }
     }
 
+    // Items that are ignored are only skipped if they don't have to be checked.
+    fn checks_ignored(&self) -> bool {
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        {
+            self.checks_keys()
+        }
+        #[cfg(not(any(feature = "std", feature = "alloc")))]
+        {
+            self.strict
+        }
+    }
+
+    // Skips the next item with a stack on the heap instead of recursion.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn skip_item(&mut self) -> Result<()> {
+        let mut tags = 0;
+        let mut frame = match self.skip_header(0, &mut tags)? {
+            Some(frame) => frame,
+            None => return Ok(()),
+        };
+        let mut stack = Vec::new();
+        loop {
+            if self.next_item(&mut frame)? {
+                if let Some(inner) = self.skip_header(stack.len() + 1, &mut tags)? {
+                    stack.push(mem::replace(&mut frame, inner));
+                }
+            } else {
+                if frame.major == 6 {
+                    tags -= 1;
+                }
+                frame = match stack.pop() {
+                    Some(outer) => outer,
+                    None => return Ok(()),
+                };
+            }
+        }
+    }
+
+    #[cfg(not(any(feature = "std", feature = "alloc")))]
+    fn skip_item(&mut self) -> Result<()> {
+        self.skip_nested(0, &mut 0)
+    }
+
+    #[cfg(not(any(feature = "std", feature = "alloc")))]
+    fn skip_nested(&mut self, depth: usize, tags: &mut usize) -> Result<()> {
+        if let Some(mut frame) = self.skip_header(depth, tags)? {
+            while self.next_item(&mut frame)? {
+                self.skip_nested(depth + 1, tags)?;
+            }
+            if frame.major == 6 {
+                *tags -= 1;
+            }
+        }
+        Ok(())
+    }
+
+    // Reads the argument of the header `byte` without the checks of strict mode. Returns `None`
+    // for indefinite lengths.
+    fn parse_skipped_argument(&mut self, byte: u8) -> Result<Option<u64>> {
+        match byte & 0x1f {
+            info @ 0..=23 => Ok(Some(u64::from(info))),
+            24 => Ok(Some(u64::from(self.parse_u8()?))),
+            25 => Ok(Some(u64::from(self.parse_u16()?))),
+            26 => Ok(Some(u64::from(self.parse_u32()?))),
+            27 => Ok(Some(self.parse_u64()?)),
+            31 => Ok(None),
+            _ => Err(self.error(ErrorCode::UnassignedCode)),
+        }
+    }
+
+    // Fails if an array, map or tag would be nested in `depth` others.
+    fn check_depth(&self, depth: usize) -> Result<()> {
+        if depth >= self.remaining_depth {
+            return Err(self.error(ErrorCode::RecursionLimitExceeded));
+        }
+        Ok(())
+    }
+
+    // Fails if a tag would be enclosed by `tags` others, otherwise counts it.
+    fn enter_tag(&self, tags: &mut usize) -> Result<()> {
+        if *tags >= self.remaining_tags {
+            return Err(self.error(ErrorCode::TagLimitExceeded));
+        }
+        *tags += 1;
+        Ok(())
+    }
+
+    // Skips the next item if it is not an array, map or tag. Otherwise only its header is read
+    // and a frame for its content is returned. `depth` is the number of enclosing frames and
+    // `tags` the number of enclosing tags.
+    fn skip_header(&mut self, depth: usize, tags: &mut usize) -> Result<Option<SkipFrame>> {
+        let byte = self.parse_u8()?;
+        let major = byte >> 5;
+        let argument = match self.parse_skipped_argument(byte)? {
+            Some(argument) => argument,
+            None => {
+                return match major {
+                    2 | 3 => self.skip_chunks(major).map(|_| None),
+                    4 | 5 => {
+                        self.check_depth(depth)?;
+                        Ok(Some(SkipFrame::indefinite(major)))
+                    }
+                    7 => Err(self.error(ErrorCode::UnexpectedCode)),
+                    _ => Err(self.error(ErrorCode::UnassignedCode)),
+                };
+            }
+        };
+        match major {
+            0 | 1 => Ok(None),
+            2 | 3 => {
+                self.check_length(argument)?;
+                self.skip_bytes(argument).map(|_| None)
+            }
+            4 | 5 => {
+                self.check_elements(argument)?;
+                self.check_depth(depth)?;
+                Ok(Some(SkipFrame::definite(major, argument)))
+            }
+            6 => {
+                self.enter_tag(tags)?;
+                self.check_depth(depth)?;
+                Ok(Some(SkipFrame::definite(major, 1)))
+            }
+            _ if byte == 0xf8 && argument < 0x20 => Err(self.error(ErrorCode::UnexpectedCode)),
+            _ => Ok(None),
+        }
+    }
+
+    // Moves to the next item in an array, map or tag. Returns false after the last one, the
+    // break that ends an indefinite length is consumed.
+    fn next_item(&mut self, frame: &mut SkipFrame) -> Result<bool> {
+        if !frame.indefinite {
+            if frame.items == 0 {
+                return Ok(false);
+            }
+            frame.items -= 1;
+            return Ok(true);
+        }
+        // A break in place of a map value is rejected as the start of the value.
+        if frame.major == 4 || frame.items & 1 == 0 {
+            if self.peek()? == Some(0xff) {
+                self.consume();
+                return Ok(false);
+            }
+            let len = if frame.major == 5 {
+                frame.items / 2
+            } else {
+                frame.items
+            };
+            self.check_elements(len + 1)?;
+        }
+        frame.items += 1;
+        Ok(true)
+    }
+
+    // Skips the chunks of an indefinite length string after its header.
+    fn skip_chunks(&mut self, major: u8) -> Result<()> {
+        let mut chunks = 0;
+        let mut total: u64 = 0;
+        loop {
+            let byte = self.parse_u8()?;
+            if byte == 0xff {
+                return Ok(());
+            }
+            if byte >> 5 != major {
+                return Err(self.error(ErrorCode::UnexpectedCode));
+            }
+            let len = match self.parse_skipped_argument(byte)? {
+                Some(len) => len,
+                None => return Err(self.error(ErrorCode::UnexpectedCode)),
+            };
+            chunks += 1;
+            total = total.saturating_add(len);
+            self.check_chunks(chunks)?;
+            self.check_length(total)?;
+            self.skip_bytes(len)?;
+        }
+    }
+
+    fn skip_bytes(&mut self, len: u64) -> Result<()> {
+        if len > usize::max_value() as u64 {
+            return Err(self.error(ErrorCode::LengthOutOfRange));
+        }
+        if !self.recorder.is_recording() {
+            return self.read.skip(len as usize);
+        }
+        match self.read.read(len as usize)? {
+            EitherLifetime::Long(buf) => self.recorder.push(buf),
+            EitherLifetime::Short(buf) => self.recorder.push(buf),
+        }
+        Ok(())
+    }
+
+    // Passes the encoding of the next item to the visitor, borrowed from the input if possible.
+    fn parse_raw<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let start = self.read.offset();
+        if self.read.slice_since(start).is_none() {
+            return self.parse_raw_recorded(visitor);
+        }
+        self.skip_value()?;
+        let bytes = self.read.slice_since(start).unwrap_or(&[]);
+        visitor.visit_borrowed_bytes(bytes)
+    }
+
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn parse_raw_recorded<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let outer = self.recorder.start();
+        let value = self.skip_value();
+        let bytes = self.recorder.finish(outer);
+        value?;
+        visitor.visit_byte_buf(bytes)
+    }
+
+    #[cfg(not(any(feature = "std", feature = "alloc")))]
+    fn parse_raw_recorded<V>(&mut self, _visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err(Error::message(
+            "raw values can only be read from a slice without the alloc feature",
+        ))
+    }
+
     fn recursion_checked<F, T>(&mut self, f: F) -> Result<T>
     where
         F: FnOnce(&mut Deserializer<R>) -> Result<T>,
     {
-        self.remaining_depth -= 1;
         if self.remaining_depth == 0 {
             return Err(self.error(ErrorCode::RecursionLimitExceeded));
         }
-        let r = f(self);
+        self.remaining_depth -= 1;
+        // Errors that are not inside an element or value, like missing fields, get the path of
+        // the array or map.
+        let r = f(self).map_err(|err| self.at_path(err));
         self.remaining_depth += 1;
         r
     }
 
+    fn tag_checked<F, T>(&mut self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Deserializer<R>) -> Result<T>,
+    {
+        if self.remaining_tags == 0 {
+            return Err(self.error(ErrorCode::TagLimitExceeded));
+        }
+        self.remaining_tags -= 1;
+        let r = self.recursion_checked(f);
+        self.remaining_tags += 1;
+        r
+    }
+
     fn parse_array<V>(&mut self, mut len: usize, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
+        self.check_elements(len as u64)?;
         self.recursion_checked(|de| {
-            let value = visitor.visit_seq(SeqAccess { de, len: &mut len })?;
+            let value = visitor.visit_seq(SeqAccess {
+                de,
+                len: &mut len,
+                index: 0,
+            })?;
 
             if len != 0 {
                 Err(de.error(ErrorCode::TrailingData))
@@ -429,8 +1661,9 @@ where
     where
         V: de::Visitor<'de>,
     {
+        self.check_definite()?;
         self.recursion_checked(|de| {
-            let value = visitor.visit_seq(IndefiniteSeqAccess { de })?;
+            let value = visitor.visit_seq(IndefiniteSeqAccess { de, len: 0 })?;
             match de.next()? {
                 Some(0xff) => Ok(value),
                 Some(_) => Err(de.error(ErrorCode::TrailingData)),
@@ -443,6 +1676,7 @@ where
     where
         V: de::Visitor<'de>,
     {
+        self.check_elements(len as u64)?;
         let accept_packed = self.accept_packed;
         let accept_named = self.accept_named;
         self.recursion_checked(|de| {
@@ -451,6 +1685,10 @@ where
                 len: &mut len,
                 accept_named,
                 accept_packed,
+                #[cfg(any(feature = "std", feature = "alloc"))]
+                keys: BTreeSet::new(),
+                #[cfg(any(feature = "std", feature = "alloc"))]
+                last_key: Vec::new(),
             })?;
 
             if len != 0 {
@@ -465,13 +1703,19 @@ where
     where
         V: de::Visitor<'de>,
     {
+        self.check_definite()?;
         let accept_named = self.accept_named;
         let accept_packed = self.accept_packed;
         self.recursion_checked(|de| {
             let value = visitor.visit_map(IndefiniteMapAccess {
                 de,
+                len: 0,
                 accept_packed,
                 accept_named,
+                #[cfg(any(feature = "std", feature = "alloc"))]
+                keys: BTreeSet::new(),
+                #[cfg(any(feature = "std", feature = "alloc"))]
+                last_key: Vec::new(),
             })?;
             match de.next()? {
                 Some(0xff) => Ok(value),
@@ -485,9 +1729,14 @@ where
     where
         V: de::Visitor<'de>,
     {
+        self.check_elements(len as u64)?;
         self.recursion_checked(|de| {
             let value = visitor.visit_enum(VariantAccess {
-                seq: SeqAccess { de, len: &mut len },
+                seq: SeqAccess {
+                    de,
+                    len: &mut len,
+                    index: 0,
+                },
             })?;
 
             if len != 0 {
@@ -512,6 +1761,10 @@ where
                     len: &mut len,
                     accept_packed,
                     accept_named,
+                    #[cfg(any(feature = "std", feature = "alloc"))]
+                    keys: BTreeSet::new(),
+                    #[cfg(any(feature = "std", feature = "alloc"))]
+                    last_key: Vec::new(),
                 },
             })?;
 
@@ -527,9 +1780,10 @@ where
     where
         V: de::Visitor<'de>,
     {
+        self.check_definite()?;
         self.recursion_checked(|de| {
             let value = visitor.visit_enum(VariantAccess {
-                seq: IndefiniteSeqAccess { de },
+                seq: IndefiniteSeqAccess { de, len: 0 },
             })?;
             match de.next()? {
                 Some(0xff) => Ok(value),
@@ -539,19 +1793,68 @@ where
         })
     }
 
+    fn parse_tag(&mut self, byte: u8) -> Result<u64> {
+        match byte {
+            0xc0..=0xd7 => Ok(u64::from(byte - 0xc0)),
+            0xd8..=0xdb => self.parse_argument(byte - 0xc0),
+            _ => Err(self.error(ErrorCode::UnexpectedCode)),
+        }
+    }
+
+    fn parse_tagged<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        let tag = match self.peek()? {
+            Some(byte @ 0xc0..=0xdb) => {
+                self.consume();
+                self.parse_tag(byte)?
+            }
+            Some(byte @ 0xe0..=0xf3) => {
+                self.consume();
+                return visitor.visit_enum(SimpleAccess::new(byte - 0xe0));
+            }
+            Some(0xf8) => {
+                self.consume();
+                let value = self.parse_u8()?;
+                if value < 0x20 {
+                    return Err(self.error(ErrorCode::UnexpectedCode));
+                }
+                return visitor.visit_enum(SimpleAccess::new(value));
+            }
+            _ => return visitor.visit_newtype_struct(self),
+        };
+        self.tag_checked(|de| visitor.visit_enum(TagAccess::new(tag, de)))
+    }
+
+    // Bignums are only decoded when a 128-bit integer is requested, other types skip the tag.
+    fn parse_integer<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.peek()? {
+            Some(byte @ 0xc2..=0xc3) => {
+                self.consume();
+                let negative = byte == 0xc3;
+                self.tag_checked(|de| de.parse_value(BignumVisitor { negative, visitor }))
+            }
+            _ => self.parse_value(visitor),
+        }
+    }
+
     fn parse_f16(&mut self) -> Result<f32> {
         Ok(f32::from(f16::from_bits(self.parse_u16()?)))
     }
 
     fn parse_f32(&mut self) -> Result<f32> {
         let mut buf = [0; 4];
-        self.read.read_into(&mut buf)?;
+        self.read_into(&mut buf)?;
         Ok(BigEndian::read_f32(&buf))
     }
 
     fn parse_f64(&mut self) -> Result<f64> {
         let mut buf = [0; 8];
-        self.read.read_into(&mut buf)?;
+        self.read_into(&mut buf)?;
         Ok(BigEndian::read_f64(&buf))
     }
 
@@ -567,19 +1870,19 @@ where
             // Major type 0: an unsigned integer
             0x00..=0x17 => visitor.visit_u8(byte),
             0x18 => {
-                let value = self.parse_u8()?;
+                let value = self.parse_arg_u8()?;
                 visitor.visit_u8(value)
             }
             0x19 => {
-                let value = self.parse_u16()?;
+                let value = self.parse_arg_u16()?;
                 visitor.visit_u16(value)
             }
             0x1a => {
-                let value = self.parse_u32()?;
+                let value = self.parse_arg_u32()?;
                 visitor.visit_u32(value)
             }
             0x1b => {
-                let value = self.parse_u64()?;
+                let value = self.parse_arg_u64()?;
                 visitor.visit_u64(value)
             }
             0x1c..=0x1f => Err(self.error(ErrorCode::UnassignedCode)),
@@ -587,19 +1890,19 @@ where
             // Major type 1: a negative integer
             0x20..=0x37 => visitor.visit_i8(-1 - (byte - 0x20) as i8),
             0x38 => {
-                let value = self.parse_u8()?;
+                let value = self.parse_arg_u8()?;
                 visitor.visit_i16(-1 - i16::from(value))
             }
             0x39 => {
-                let value = self.parse_u16()?;
+                let value = self.parse_arg_u16()?;
                 visitor.visit_i32(-1 - i32::from(value))
             }
             0x3a => {
-                let value = self.parse_u32()?;
+                let value = self.parse_arg_u32()?;
                 visitor.visit_i64(-1 - i64::from(value))
             }
             0x3b => {
-                let value = self.parse_u64()?;
+                let value = self.parse_arg_u64()?;
                 if value > i64::max_value() as u64 {
                     return visitor.visit_i128(-1 - i128::from(value));
                 }
@@ -610,19 +1913,19 @@ where
             // Major type 2: a byte string
             0x40..=0x57 => self.parse_bytes(byte as usize - 0x40, visitor),
             0x58 => {
-                let len = self.parse_u8()?;
+                let len = self.parse_arg_u8()?;
                 self.parse_bytes(len as usize, visitor)
             }
             0x59 => {
-                let len = self.parse_u16()?;
+                let len = self.parse_arg_u16()?;
                 self.parse_bytes(len as usize, visitor)
             }
             0x5a => {
-                let len = self.parse_u32()?;
+                let len = self.parse_arg_u32()?;
                 self.parse_bytes(len as usize, visitor)
             }
             0x5b => {
-                let len = self.parse_u64()?;
+                let len = self.parse_arg_u64()?;
                 if len > usize::max_value() as u64 {
                     return Err(self.error(ErrorCode::LengthOutOfRange));
                 }
@@ -634,19 +1937,19 @@ where
             // Major type 3: a text string
             0x60..=0x77 => self.parse_str(byte as usize - 0x60, visitor),
             0x78 => {
-                let len = self.parse_u8()?;
+                let len = self.parse_arg_u8()?;
                 self.parse_str(len as usize, visitor)
             }
             0x79 => {
-                let len = self.parse_u16()?;
+                let len = self.parse_arg_u16()?;
                 self.parse_str(len as usize, visitor)
             }
             0x7a => {
-                let len = self.parse_u32()?;
+                let len = self.parse_arg_u32()?;
                 self.parse_str(len as usize, visitor)
             }
             0x7b => {
-                let len = self.parse_u64()?;
+                let len = self.parse_arg_u64()?;
                 if len > usize::max_value() as u64 {
                     return Err(self.error(ErrorCode::LengthOutOfRange));
                 }
@@ -658,19 +1961,19 @@ where
             // Major type 4: an array of data items
             0x80..=0x97 => self.parse_array(byte as usize - 0x80, visitor),
             0x98 => {
-                let len = self.parse_u8()?;
+                let len = self.parse_arg_u8()?;
                 self.parse_array(len as usize, visitor)
             }
             0x99 => {
-                let len = self.parse_u16()?;
+                let len = self.parse_arg_u16()?;
                 self.parse_array(len as usize, visitor)
             }
             0x9a => {
-                let len = self.parse_u32()?;
+                let len = self.parse_arg_u32()?;
                 self.parse_array(len as usize, visitor)
             }
             0x9b => {
-                let len = self.parse_u64()?;
+                let len = self.parse_arg_u64()?;
                 if len > usize::max_value() as u64 {
                     return Err(self.error(ErrorCode::LengthOutOfRange));
                 }
@@ -682,19 +1985,19 @@ where
             // Major type 5: a map of pairs of data items
             0xa0..=0xb7 => self.parse_map(byte as usize - 0xa0, visitor),
             0xb8 => {
-                let len = self.parse_u8()?;
+                let len = self.parse_arg_u8()?;
                 self.parse_map(len as usize, visitor)
             }
             0xb9 => {
-                let len = self.parse_u16()?;
+                let len = self.parse_arg_u16()?;
                 self.parse_map(len as usize, visitor)
             }
             0xba => {
-                let len = self.parse_u32()?;
+                let len = self.parse_arg_u32()?;
                 self.parse_map(len as usize, visitor)
             }
             0xbb => {
-                let len = self.parse_u64()?;
+                let len = self.parse_arg_u64()?;
                 if len > usize::max_value() as u64 {
                     return Err(self.error(ErrorCode::LengthOutOfRange));
                 }
@@ -704,22 +2007,9 @@ where
             0xbf => self.parse_indefinite_map(visitor),
 
             // Major type 6: optional semantic tagging of other major types
-            0xc0..=0xd7 => self.recursion_checked(|de| de.parse_value(visitor)),
-            0xd8 => {
-                self.parse_u8()?;
-                self.recursion_checked(|de| de.parse_value(visitor))
-            }
-            0xd9 => {
-                self.parse_u16()?;
-                self.recursion_checked(|de| de.parse_value(visitor))
-            }
-            0xda => {
-                self.parse_u32()?;
-                self.recursion_checked(|de| de.parse_value(visitor))
-            }
-            0xdb => {
-                self.parse_u64()?;
-                self.recursion_checked(|de| de.parse_value(visitor))
+            0xc0..=0xdb => {
+                self.parse_tag(byte)?;
+                self.tag_checked(|de| de.parse_value(visitor))
             }
             0xdc..=0xdf => Err(self.error(ErrorCode::UnassignedCode)),
 
@@ -736,10 +2026,14 @@ where
             }
             0xfa => {
                 let value = self.parse_f32()?;
+                let preferred = value.is_finite() && f32::from(f16::from_f32(value)) != value;
+                self.check_preferred_float(preferred, 4)?;
                 visitor.visit_f32(value)
             }
             0xfb => {
                 let value = self.parse_f64()?;
+                let preferred = value.is_finite() && f64::from(value as f32) != value;
+                self.check_preferred_float(preferred, 8)?;
                 visitor.visit_f64(value)
             }
             0xfc..=0xfe => Err(self.error(ErrorCode::UnassignedCode)),
@@ -779,10 +2073,16 @@ where
     }
 
     #[inline]
-    fn deserialize_newtype_struct<V>(self, _name: &str, visitor: V) -> Result<V::Value>
+    fn deserialize_newtype_struct<V>(self, name: &str, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
     {
+        if name == TAG_NAME {
+            return self.parse_tagged(visitor);
+        }
+        if name == RAW_NAME {
+            return self.parse_raw(visitor);
+        }
         visitor.visit_newtype_struct(self)
     }
 
@@ -808,19 +2108,19 @@ where
                 match byte {
                     0x80..=0x97 => self.parse_enum(byte as usize - 0x80, visitor),
                     0x98 => {
-                        let len = self.parse_u8()?;
+                        let len = self.parse_arg_u8()?;
                         self.parse_enum(len as usize, visitor)
                     }
                     0x99 => {
-                        let len = self.parse_u16()?;
+                        let len = self.parse_arg_u16()?;
                         self.parse_enum(len as usize, visitor)
                     }
                     0x9a => {
-                        let len = self.parse_u32()?;
+                        let len = self.parse_arg_u32()?;
                         self.parse_enum(len as usize, visitor)
                     }
                     0x9b => {
-                        let len = self.parse_u64()?;
+                        let len = self.parse_arg_u64()?;
                         if len > usize::max_value() as u64 {
                             return Err(self.error(ErrorCode::LengthOutOfRange));
                         }
@@ -849,18 +2149,101 @@ where
         }
     }
 
+    #[inline]
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.parse_integer(visitor)
+    }
+
+    #[inline]
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.parse_integer(visitor)
+    }
+
     #[inline]
     fn is_human_readable(&self) -> bool {
         false
     }
 
+    #[inline]
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: de::Visitor<'de>,
+    {
+        if self.checks_ignored() {
+            return self.parse_value(visitor);
+        }
+        self.skip_item()?;
+        visitor.visit_unit()
+    }
+
     serde::forward_to_deserialize_any! {
-        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string unit
-        unit_struct seq tuple tuple_struct map struct identifier ignored_any
-        bytes byte_buf
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string unit
+        unit_struct seq tuple tuple_struct map struct identifier bytes
+        byte_buf
+    }
+}
+
+// An array, map or tag whose content is skipped or decoded. `items` counts the items that are
+// left, or the items so far for indefinite lengths. Map keys and values are counted separately.
+#[derive(Debug)]
+struct SkipFrame {
+    major: u8,
+    items: u64,
+    indefinite: bool,
+}
+
+impl SkipFrame {
+    fn definite(major: u8, len: u64) -> SkipFrame {
+        let items = if major == 5 {
+            len.saturating_mul(2)
+        } else {
+            len
+        };
+        SkipFrame {
+            major,
+            items,
+            indefinite: false,
+        }
+    }
+
+    fn indefinite(major: u8) -> SkipFrame {
+        SkipFrame {
+            major,
+            items: 0,
+            indefinite: true,
+        }
     }
 }
 
+// An array, map or tag that is decoded by `read_value`.
+#[cfg(feature = "std")]
+struct ValueFrame {
+    items: SkipFrame,
+    content: ValueContent,
+}
+
+#[cfg(feature = "std")]
+enum ValueContent {
+    Array(Vec<Value>),
+    // `key` is set between a key and its value. `recording` holds the outer recording and the
+    // offset of a key that is checked while it's decoded.
+    Map {
+        entries: BTreeMap<Value, Value>,
+        key: Option<Value>,
+        accept_packed: bool,
+        accept_named: bool,
+        keys: BTreeSet<Vec<u8>>,
+        recording: Option<(Option<Vec<u8>>, u64)>,
+    },
+    Tag(u64),
+}
+
 impl<R> Deserializer<R>
 where
     R: Offset,
@@ -876,9 +2259,53 @@ trait MakeError {
     fn error(&self, code: ErrorCode) -> Error;
 }
 
+// A step from an array, map or struct to one of its items in the path of an error.
+#[cfg_attr(not(any(feature = "std", feature = "alloc")), allow(dead_code))]
+pub(crate) enum PathSegment<'a> {
+    Index(u64),
+    // The encoding of a map key.
+    Key(&'a [u8]),
+    Field(&'a str),
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'a> PathSegment<'a> {
+    pub(crate) fn push_to(&self, path: &mut String) {
+        let key = match *self {
+            PathSegment::Index(index) => {
+                let _ = fmt::Write::write_fmt(path, format_args!("[{}]", index));
+                return;
+            }
+            PathSegment::Key(key) => key,
+            PathSegment::Field(name) => {
+                path.push('.');
+                path.push_str(name);
+                return;
+            }
+        };
+        let mut input = key;
+        if let Some((3, Some(len))) = split_header(&mut input) {
+            if let Ok(name) = str::from_utf8(input) {
+                if name.len() as u64 == len {
+                    path.push('.');
+                    path.push_str(name);
+                    return;
+                }
+            }
+        }
+        path.push('[');
+        match crate::diag::to_string(key) {
+            Ok(diag) => path.push_str(&diag),
+            Err(_) => path.push('?'),
+        }
+        path.push(']');
+    }
+}
+
 struct SeqAccess<'a, R> {
     de: &'a mut Deserializer<R>,
     len: &'a mut usize,
+    index: u64,
 }
 
 impl<'de, 'a, R> de::SeqAccess<'de> for SeqAccess<'a, R>
@@ -895,8 +2322,12 @@ where
             return Ok(None);
         }
         *self.len -= 1;
+        let index = self.index;
+        self.index += 1;
 
-        let value = seed.deserialize(&mut *self.de)?;
+        let value = self
+            .de
+            .tracked(PathSegment::Index(index), |de| seed.deserialize(de))?;
         Ok(Some(value))
     }
 
@@ -916,6 +2347,7 @@ where
 
 struct IndefiniteSeqAccess<'a, R> {
     de: &'a mut Deserializer<R>,
+    len: u64,
 }
 
 impl<'de, 'a, R> de::SeqAccess<'de> for IndefiniteSeqAccess<'a, R>
@@ -933,8 +2365,13 @@ where
             Some(_) => {}
             None => return Err(self.de.error(ErrorCode::EofWhileParsingArray)),
         }
+        self.len += 1;
+        self.de.check_elements(self.len)?;
 
-        let value = seed.deserialize(&mut *self.de)?;
+        let index = self.len - 1;
+        let value = self
+            .de
+            .tracked(PathSegment::Index(index), |de| seed.deserialize(de))?;
         Ok(Some(value))
     }
 }
@@ -953,6 +2390,10 @@ struct MapAccess<'a, R> {
     len: &'a mut usize,
     accept_named: bool,
     accept_packed: bool,
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    keys: BTreeSet<Vec<u8>>,
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    last_key: Vec<u8>,
 }
 
 impl<'de, 'a, R> de::MapAccess<'de> for MapAccess<'a, R>
@@ -980,6 +2421,11 @@ where
             _ => {}
         };
 
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        let value = self
+            .de
+            .parse_key(seed, &mut self.keys, &mut self.last_key)?;
+        #[cfg(not(any(feature = "std", feature = "alloc")))]
         let value = seed.deserialize(&mut *self.de)?;
         Ok(Some(value))
     }
@@ -988,6 +2434,13 @@ where
     where
         V: de::DeserializeSeed<'de>,
     {
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        {
+            if self.de.path.is_some() {
+                let key = PathSegment::Key(&self.last_key);
+                return self.de.tracked(key, |de| seed.deserialize(de));
+            }
+        }
         seed.deserialize(&mut *self.de)
     }
 
@@ -1007,8 +2460,13 @@ where
 
 struct IndefiniteMapAccess<'a, R> {
     de: &'a mut Deserializer<R>,
+    len: u64,
     accept_packed: bool,
     accept_named: bool,
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    keys: BTreeSet<Vec<u8>>,
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    last_key: Vec<u8>,
 }
 
 impl<'de, 'a, R> de::MapAccess<'de> for IndefiniteMapAccess<'a, R>
@@ -1032,7 +2490,14 @@ where
             Some(_) => {}
             None => return Err(self.de.error(ErrorCode::EofWhileParsingMap)),
         }
-
+        self.len += 1;
+        self.de.check_elements(self.len)?;
+
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        let value = self
+            .de
+            .parse_key(seed, &mut self.keys, &mut self.last_key)?;
+        #[cfg(not(any(feature = "std", feature = "alloc")))]
         let value = seed.deserialize(&mut *self.de)?;
         Ok(Some(value))
     }
@@ -1041,6 +2506,13 @@ where
     where
         V: de::DeserializeSeed<'de>,
     {
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        {
+            if self.de.path.is_some() {
+                let key = PathSegment::Key(&self.last_key);
+                return self.de.tracked(key, |de| seed.deserialize(de));
+            }
+        }
         seed.deserialize(&mut *self.de)
     }
 }
@@ -1193,10 +2665,34 @@ where
     }
 }
 
+struct BignumVisitor<V> {
+    negative: bool,
+    visitor: V,
+}
+
+impl<'de, V> de::Visitor<'de> for BignumVisitor<V>
+where
+    V: de::Visitor<'de>,
+{
+    type Value = V::Value;
+
+    fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str("the byte string of a bignum")
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> result::Result<V::Value, E>
+    where
+        E: de::Error,
+    {
+        visit_bignum(self.negative, v, self.visitor)
+    }
+}
+
 /// Iterator that deserializes a stream into multiple CBOR values.
 ///
 /// A stream deserializer can be created from any CBOR deserializer using the
-/// `Deserializer::into_iter` method.
+/// `Deserializer::into_iter` method. It reads CBOR sequences as written by
+/// [`SequenceWriter`](../ser/struct.SequenceWriter.html).
 ///
 /// ```
 /// # extern crate serde_cbor;
@@ -1221,6 +2717,9 @@ where
 #[derive(Debug)]
 pub struct StreamDeserializer<'de, R, T> {
     de: Deserializer<R>,
+    allow_truncated: bool,
+    truncated: bool,
+    valid_up_to: u64,
     output: PhantomData<T>,
     lifetime: PhantomData<&'de ()>,
 }
@@ -1238,10 +2737,54 @@ where
     /// * `Deserializer::from_slice(...).into_iter()`
     /// * `Deserializer::from_reader(...).into_iter()`
     pub fn new(read: R) -> StreamDeserializer<'de, R, T> {
-        StreamDeserializer {
-            de: Deserializer::new(read),
-            output: PhantomData,
-            lifetime: PhantomData,
+        Deserializer::new(read).into_iter()
+    }
+
+    /// Stop at a truncated item at the end of the input instead of returning an error.
+    ///
+    /// This is useful when reading a sequence that is still being written, like a log file. If
+    /// the input ends in the middle of an item, the iterator ends and `is_truncated` returns
+    /// `true`. Other errors are still returned. The truncated item can't be resumed, once more
+    /// data is available a new deserializer has to start at `valid_up_to`.
+    pub fn allow_truncated(mut self) -> Self {
+        self.allow_truncated = true;
+        self
+    }
+
+    /// Returns `true` if the iteration stopped at a truncated item.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Returns the offset after the last item that was deserialized successfully.
+    pub fn valid_up_to(&self) -> u64 {
+        self.valid_up_to
+    }
+
+    /// Deserializes the next item and returns it with its offsets in the input.
+    ///
+    /// The range starts at the first byte of the item and ends after its last byte.
+    pub fn next_with_offsets(&mut self) -> Option<Result<(T, Range<u64>)>> {
+        if self.truncated {
+            return None;
+        }
+        let start = self.de.read.offset();
+        match self.de.peek() {
+            Ok(Some(_)) => {}
+            Ok(None) => return None,
+            Err(e) => return Some(Err(e)),
+        }
+        match T::deserialize(&mut self.de) {
+            Ok(value) => {
+                let end = self.de.read.offset();
+                self.valid_up_to = end;
+                Some(Ok((value, start..end)))
+            }
+            Err(ref e) if self.allow_truncated && e.is_eof() => {
+                self.truncated = true;
+                None
+            }
+            Err(e) => Some(Err(e)),
         }
     }
 }
@@ -1266,11 +2809,8 @@ where
     type Item = Result<T>;
 
     fn next(&mut self) -> Option<Result<T>> {
-        match self.de.peek() {
-            Ok(Some(_)) => Some(T::deserialize(&mut self.de)),
-            Ok(None) => None,
-            Err(e) => Some(Err(e)),
-        }
+        self.next_with_offsets()
+            .map(|result| result.map(|(value, _)| value))
     }
 }
 
@@ -0,0 +1,190 @@
+//! Low-level pull parser for CBOR data.
+//!
+//! A [`Decoder`](struct.Decoder.html) reads one header at a time from any of the input sources
+//! of the [`de`](../de/index.html) module and returns it as an [`Event`](enum.Event.html). No
+//! types have to be defined and nothing is allocated, which makes it a good fit for scanning
+//! data or for hand-written decoders of hot paths.
+//!
+//! The decoder does not keep track of nesting. Arrays and maps of known length are followed by
+//! the given number of items (twice as many for maps), items of indefinite length end with an
+//! `Event::Break`. The content of byte and text strings is read with
+//! [`read_bytes`](struct.Decoder.html#method.read_bytes) after their header.
+//!
+//! # Examples
+//!
+//! Sum an array of integers:
+//!
+//! ```
+//! use serde_cbor::de::SliceReadFixed;
+//! use serde_cbor::decoder::{Decoder, Event};
+//!
+//! let bytes = [0x83, 0x01, 0x18, 0x2a, 0x20];
+//! let mut decoder = Decoder::new(SliceReadFixed::new(&bytes, &mut []));
+//! assert_eq!(decoder.next_event().unwrap(), Some(Event::ArrayStart(Some(3))));
+//! let mut sum = 0;
+//! for _ in 0..3 {
+//!     match decoder.next_event().unwrap() {
+//!         Some(Event::UnsignedInt(n)) => sum += n as i64,
+//!         Some(Event::NegativeInt(n)) => sum -= 1 + n as i64,
+//!         _ => panic!("expected an integer"),
+//!     }
+//! }
+//! assert_eq!(sum, 42);
+//! assert_eq!(decoder.next_event().unwrap(), None);
+//! ```
+
+use byteorder::{BigEndian, ByteOrder};
+use half::f16;
+
+use crate::error::{Error, ErrorCode, Result};
+pub use crate::read::EitherLifetime;
+use crate::read::Read;
+#[cfg(any(feature = "std", feature = "alloc"))]
+use crate::read::SliceRead;
+
+/// A single header of CBOR data.
+///
+/// Lengths and arguments are `None` for items of indefinite length.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event {
+    /// An unsigned integer (major type 0).
+    UnsignedInt(u64),
+    /// A negative integer (major type 1). The value is `-1 - n`.
+    NegativeInt(u64),
+    /// The header of a byte string (major type 2), followed by its content for a known length
+    /// or by chunks of known length and a break otherwise.
+    Bytes(Option<u64>),
+    /// The header of a text string (major type 3), followed by its content for a known length
+    /// or by chunks of known length and a break otherwise.
+    Text(Option<u64>),
+    /// The start of an array (major type 4).
+    ArrayStart(Option<u64>),
+    /// The start of a map (major type 5), the length is the number of pairs.
+    MapStart(Option<u64>),
+    /// A tag (major type 6), followed by the tagged item.
+    Tag(u64),
+    /// A simple value that is not `false`, `true`, `null` or `undefined`.
+    Simple(u8),
+    /// The simple values `false` and `true`.
+    Bool(bool),
+    /// The simple value `null`.
+    Null,
+    /// The simple value `undefined`.
+    Undefined,
+    /// A half, single or double precision float.
+    Float(f64),
+    /// The end of an item of indefinite length.
+    Break,
+}
+
+/// A pull parser that returns the headers of CBOR data as events.
+#[derive(Debug)]
+pub struct Decoder<R> {
+    read: R,
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'a> Decoder<SliceRead<'a>> {
+    /// Constructs a `Decoder` that reads from a slice.
+    pub fn from_slice(bytes: &'a [u8]) -> Decoder<SliceRead<'a>> {
+        Decoder::new(SliceRead::new(bytes))
+    }
+}
+
+impl<'de, R> Decoder<R>
+where
+    R: Read<'de>,
+{
+    /// Constructs a `Decoder` from one of the possible serde_cbor input sources.
+    pub fn new(read: R) -> Self {
+        Decoder { read }
+    }
+
+    /// Unwraps the input source of the decoder.
+    pub fn into_inner(self) -> R {
+        self.read
+    }
+
+    /// Returns the offset of the next byte in the input.
+    pub fn offset(&self) -> u64 {
+        self.read.offset()
+    }
+
+    fn error(&self, code: ErrorCode) -> Error {
+        Error::syntax(code, self.read.offset())
+    }
+
+    /// Reads the next header.
+    ///
+    /// Returns `None` at the end of the input.
+    pub fn next_event(&mut self) -> Result<Option<Event>> {
+        let byte = match self.read.next()? {
+            Some(byte) => byte,
+            None => return Ok(None),
+        };
+        let info = byte & 0x1f;
+        let argument = match info {
+            0..=23 => Some(u64::from(info)),
+            24 => Some(u64::from(self.parse_u8()?)),
+            25 => {
+                let mut buf = [0; 2];
+                self.read.read_into(&mut buf)?;
+                Some(u64::from(BigEndian::read_u16(&buf)))
+            }
+            26 => {
+                let mut buf = [0; 4];
+                self.read.read_into(&mut buf)?;
+                Some(u64::from(BigEndian::read_u32(&buf)))
+            }
+            27 => {
+                let mut buf = [0; 8];
+                self.read.read_into(&mut buf)?;
+                Some(BigEndian::read_u64(&buf))
+            }
+            31 => None,
+            _ => return Err(self.error(ErrorCode::UnassignedCode)),
+        };
+        let event = match (byte >> 5, argument) {
+            (0, Some(value)) => Event::UnsignedInt(value),
+            (1, Some(value)) => Event::NegativeInt(value),
+            (2, len) => Event::Bytes(len),
+            (3, len) => Event::Text(len),
+            (4, len) => Event::ArrayStart(len),
+            (5, len) => Event::MapStart(len),
+            (6, Some(tag)) => Event::Tag(tag),
+            (7, Some(value)) => match info {
+                20 => Event::Bool(false),
+                21 => Event::Bool(true),
+                22 => Event::Null,
+                23 => Event::Undefined,
+                24 if value < 0x20 => return Err(self.error(ErrorCode::UnexpectedCode)),
+                0..=24 => Event::Simple(value as u8),
+                25 => Event::Float(f64::from(f16::from_bits(value as u16))),
+                26 => Event::Float(f64::from(f32::from_bits(value as u32))),
+                _ => Event::Float(f64::from_bits(value)),
+            },
+            (7, None) => Event::Break,
+            _ => return Err(self.error(ErrorCode::UnassignedCode)),
+        };
+        Ok(Some(event))
+    }
+
+    /// Reads `len` bytes of content after a `Bytes` or `Text` header.
+    ///
+    /// Input sources that read from a slice return a slice of their input, others return a
+    /// slice of their scratch buffer. The content of text strings is not checked to be valid
+    /// UTF-8.
+    pub fn read_bytes<'a>(&'a mut self, len: u64) -> Result<EitherLifetime<'a, 'de>> {
+        if len > usize::max_value() as u64 {
+            return Err(self.error(ErrorCode::LengthOutOfRange));
+        }
+        self.read.read(len as usize)
+    }
+
+    fn parse_u8(&mut self) -> Result<u8> {
+        match self.read.next()? {
+            Some(byte) => Ok(byte),
+            None => Err(self.error(ErrorCode::EofWhileParsingValue)),
+        }
+    }
+}
@@ -0,0 +1,449 @@
+//! Diagnostic notation for debugging CBOR data.
+//!
+//! Diagnostic notation is the human readable text format described in
+//! [RFC 8949 section 8](https://tools.ietf.org/html/rfc8949#section-8). It looks like JSON
+//! with a few extensions: byte strings are written as `h'0102'`, tags as `1(1363896240)` and
+//! simple values without a name as `simple(16)`.
+//!
+//! Data items are printed directly from the encoded bytes, so details of the encoding are shown
+//! as well. Indefinite length items are marked with an underscore (`[_ 1, 2]`, `(_ "a", "b")`).
+//! An argument that is not encoded in its shortest form is followed by an encoding indicator
+//! `_0` to `_3`, giving the width of the argument as 1, 2, 4 or 8 bytes (`1_0` is the integer
+//! one encoded as `0x18 0x01`).
+//!
+//! A [`Value`](../value/enum.Value.html) can be printed with its `Display` implementation.
+//!
+//! The [`parse`](fn.parse.html) functions turn diagnostic notation back into CBOR. They accept
+//! the extensions from [RFC 8610 appendix G](https://tools.ietf.org/html/rfc8610#appendix-G),
+//! like `b64''` and single quoted byte strings, hexadecimal integers and comments. This is
+//! handy for writing test vectors.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "std")] {
+//! let bytes = b"\xa2\x61\x61\x82\x01\x42\x01\x02\x61\x62\xc1\x1a\x51\x4b\x67\xb0";
+//! let diag = serde_cbor::diag::to_string(bytes).unwrap();
+//! assert_eq!(diag, r#"{"a": [1, h'0102'], "b": 1(1363896240)}"#);
+//!
+//! let indefinite = b"\x9f\x18\x01\x7f\x61\x61\x61\x62\xff\xff";
+//! let diag = serde_cbor::diag::to_string(indefinite).unwrap();
+//! assert_eq!(diag, r#"[_ 1_0, (_ "a", "b")]"#);
+//! # }
+//! ```
+
+mod parse;
+
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+use byteorder::{BigEndian, ByteOrder};
+use core::fmt::{self, Write};
+use core::str;
+use half::f16;
+
+use crate::error::{Error, ErrorCode, Result};
+#[cfg(any(feature = "std", feature = "alloc"))]
+use crate::read::SliceRead;
+
+#[cfg(feature = "std")]
+#[doc(inline)]
+pub use self::parse::parse;
+pub use self::parse::parse_into;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub use self::parse::parse_to_vec;
+use crate::read::{EitherLifetime, Read};
+
+/// Prints the single data item in `slice` in diagnostic notation.
+///
+/// Fails if the data is not well-formed or if there is trailing data after the data item.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn to_string(slice: &[u8]) -> Result<String> {
+    let mut read = SliceRead::new(slice);
+    let mut out = String::new();
+    write_item(&mut read, &mut out)?;
+    match read.next()? {
+        Some(_) => Err(Error::syntax(ErrorCode::TrailingData, read.offset())),
+        None => Ok(out),
+    }
+}
+
+/// Reads one data item from `read` and writes it to `out` in diagnostic notation.
+///
+/// The input following the data item is not consumed, call this function repeatedly to print a
+/// sequence of data items.
+pub fn write_item<'de, R, W>(read: &mut R, out: &mut W) -> Result<()>
+where
+    R: Read<'de>,
+    W: fmt::Write,
+{
+    let mut printer = Printer {
+        read,
+        out,
+        remaining_depth: 128,
+    };
+    printer.print_item()
+}
+
+struct Printer<'a, R, W> {
+    read: &'a mut R,
+    out: &'a mut W,
+    remaining_depth: u8,
+}
+
+impl<'de, 'a, R, W> Printer<'a, R, W>
+where
+    R: Read<'de>,
+    W: fmt::Write,
+{
+    fn error(&self, reason: ErrorCode) -> Error {
+        Error::syntax(reason, self.read.offset())
+    }
+
+    fn write_str(&mut self, s: &str) -> Result<()> {
+        self.out.write_str(s).map_err(fmt_error)
+    }
+
+    fn write_fmt(&mut self, args: fmt::Arguments<'_>) -> Result<()> {
+        self.out.write_fmt(args).map_err(fmt_error)
+    }
+
+    fn parse_u8(&mut self) -> Result<u8> {
+        match self.read.next()? {
+            Some(byte) => Ok(byte),
+            None => Err(self.error(ErrorCode::EofWhileParsingValue)),
+        }
+    }
+
+    // Reads the argument of a header with the given additional information.
+    fn parse_argument(&mut self, info: u8) -> Result<u64> {
+        match info {
+            0..=23 => Ok(u64::from(info)),
+            24 => Ok(u64::from(self.parse_u8()?)),
+            25 => {
+                let mut buf = [0; 2];
+                self.read.read_into(&mut buf)?;
+                Ok(u64::from(BigEndian::read_u16(&buf)))
+            }
+            26 => {
+                let mut buf = [0; 4];
+                self.read.read_into(&mut buf)?;
+                Ok(u64::from(BigEndian::read_u32(&buf)))
+            }
+            27 => {
+                let mut buf = [0; 8];
+                self.read.read_into(&mut buf)?;
+                Ok(BigEndian::read_u64(&buf))
+            }
+            _ => Err(self.error(ErrorCode::UnassignedCode)),
+        }
+    }
+
+    fn parse_len(&mut self, info: u8) -> Result<usize> {
+        let len = self.parse_argument(info)?;
+        if len > usize::max_value() as u64 {
+            return Err(self.error(ErrorCode::LengthOutOfRange));
+        }
+        Ok(len as usize)
+    }
+
+    fn write_indicator(&mut self, info: u8, argument: u64) -> Result<()> {
+        match indicator(info, argument) {
+            Some(n) => self.write_fmt(format_args!("_{}", n)),
+            None => Ok(()),
+        }
+    }
+
+    fn recursion_checked<F>(&mut self, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut Self) -> Result<()>,
+    {
+        self.remaining_depth -= 1;
+        if self.remaining_depth == 0 {
+            return Err(self.error(ErrorCode::RecursionLimitExceeded));
+        }
+        let r = f(self);
+        self.remaining_depth += 1;
+        r
+    }
+
+    // Consumes the break code if it is the next byte.
+    fn parse_break(&mut self) -> Result<bool> {
+        match self.read.peek()? {
+            Some(0xff) => {
+                self.read.discard();
+                Ok(true)
+            }
+            Some(_) => Ok(false),
+            None => Err(self.error(ErrorCode::EofWhileParsingValue)),
+        }
+    }
+
+    fn print_item(&mut self) -> Result<()> {
+        let byte = self.parse_u8()?;
+        let major = byte >> 5;
+        let info = byte & 0x1f;
+        match (major, info) {
+            (0, _) => {
+                let value = self.parse_argument(info)?;
+                self.write_fmt(format_args!("{}", value))?;
+                self.write_indicator(info, value)
+            }
+            (1, _) => {
+                let value = self.parse_argument(info)?;
+                self.write_fmt(format_args!("{}", -1 - i128::from(value)))?;
+                self.write_indicator(info, value)
+            }
+            (2, 31) | (3, 31) => self.print_indefinite_string(major),
+            (2, _) | (3, _) => {
+                let len = self.parse_len(info)?;
+                self.print_string(major, len)?;
+                self.write_indicator(info, len as u64)
+            }
+            (4, _) | (5, _) => {
+                let (open, close) = if major == 4 { ("[", "]") } else { ("{", "}") };
+                self.write_str(open)?;
+                // The space after a marker is only needed before an item, except for `[_ ]`.
+                let (len, first) = if info == 31 {
+                    self.write_str("_ ")?;
+                    (None, "")
+                } else {
+                    let len = self.parse_len(info)?;
+                    let first = match indicator(info, len as u64) {
+                        Some(n) => {
+                            self.write_fmt(format_args!("_{}", n))?;
+                            " "
+                        }
+                        None => "",
+                    };
+                    (Some(len), first)
+                };
+                self.recursion_checked(|p| p.print_items(len, major == 5, first))?;
+                self.write_str(close)
+            }
+            (6, _) => {
+                let tag = self.parse_argument(info)?;
+                self.write_fmt(format_args!("{}", tag))?;
+                self.write_indicator(info, tag)?;
+                self.write_str("(")?;
+                self.recursion_checked(|p| p.print_item())?;
+                self.write_str(")")
+            }
+            (7, _) => self.print_simple(info),
+            _ => unreachable!(),
+        }
+    }
+
+    // Prints the content of an array or map, `len` is `None` for indefinite lengths. `first` is
+    // written before the first item and a comma before the others.
+    fn print_items(&mut self, len: Option<usize>, map: bool, first: &str) -> Result<()> {
+        let mut index = 0;
+        loop {
+            match len {
+                Some(len) if index == len => return Ok(()),
+                None if self.parse_break()? => return Ok(()),
+                _ => {}
+            }
+            self.write_str(if index == 0 { first } else { ", " })?;
+            self.print_item()?;
+            if map {
+                self.write_str(": ")?;
+                self.print_item()?;
+            }
+            index += 1;
+        }
+    }
+
+    fn print_string(&mut self, major: u8, len: usize) -> Result<()> {
+        let end = match self.read.offset().checked_add(len as u64) {
+            Some(end) => end,
+            None => return Err(self.error(ErrorCode::LengthOutOfRange)),
+        };
+        let buf = match self.read.read(len)? {
+            EitherLifetime::Long(buf) => buf,
+            EitherLifetime::Short(buf) => buf,
+        };
+        let result = if major == 2 {
+            write_bytes(self.out, buf)
+        } else {
+            match str::from_utf8(buf) {
+                Ok(s) => write_text(self.out, s),
+                Err(e) => {
+                    let offset = end - (buf.len() - e.valid_up_to()) as u64;
+                    return Err(Error::syntax(ErrorCode::InvalidUtf8, offset));
+                }
+            }
+        };
+        result.map_err(fmt_error)
+    }
+
+    // Chunks are printed separately, empty indefinite length strings are `''_` and `""_`.
+    fn print_indefinite_string(&mut self, major: u8) -> Result<()> {
+        if self.parse_break()? {
+            return self.write_str(if major == 2 { "''_" } else { "\"\"_" });
+        }
+        self.write_str("(_ ")?;
+        let mut first = true;
+        while !self.parse_break()? {
+            if !first {
+                self.write_str(", ")?;
+            }
+            first = false;
+            let byte = self.parse_u8()?;
+            if byte >> 5 != major || byte & 0x1f == 31 {
+                return Err(self.error(ErrorCode::UnexpectedCode));
+            }
+            let info = byte & 0x1f;
+            let len = self.parse_len(info)?;
+            self.print_string(major, len)?;
+            self.write_indicator(info, len as u64)?;
+        }
+        self.write_str(")")
+    }
+
+    fn print_simple(&mut self, info: u8) -> Result<()> {
+        match info {
+            0..=19 => self.write_fmt(format_args!("simple({})", info)),
+            20 => self.write_str("false"),
+            21 => self.write_str("true"),
+            22 => self.write_str("null"),
+            23 => self.write_str("undefined"),
+            24 => {
+                let value = self.parse_u8()?;
+                if value < 0x20 {
+                    return Err(self.error(ErrorCode::UnexpectedCode));
+                }
+                self.write_fmt(format_args!("simple({})", value))
+            }
+            25 => {
+                let bits = self.parse_argument(info)? as u16;
+                write_float(self.out, f64::from(f16::from_bits(bits))).map_err(fmt_error)
+            }
+            26 => {
+                let bits = self.parse_argument(info)? as u32;
+                let value = f32::from_bits(bits);
+                write_float(self.out, f64::from(value)).map_err(fmt_error)?;
+                if f32::from(f16::from_f32(value)).to_bits() == bits {
+                    self.write_str("_2")?;
+                }
+                Ok(())
+            }
+            27 => {
+                let bits = self.parse_argument(info)?;
+                let value = f64::from_bits(bits);
+                write_float(self.out, value).map_err(fmt_error)?;
+                if f64::from(value as f32).to_bits() == bits {
+                    self.write_str("_3")?;
+                }
+                Ok(())
+            }
+            28..=30 => Err(self.error(ErrorCode::UnassignedCode)),
+            _ => Err(self.error(ErrorCode::UnexpectedCode)),
+        }
+    }
+}
+
+// Returns the encoding indicator if the argument is not encoded in its shortest form.
+fn indicator(info: u8, argument: u64) -> Option<u8> {
+    let shortest = match argument {
+        0..=23 => 23,
+        24..=0xff => 24,
+        0x100..=0xffff => 25,
+        0x1_0000..=0xffff_ffff => 26,
+        _ => 27,
+    };
+    if info > shortest {
+        Some(info - 24)
+    } else {
+        None
+    }
+}
+
+fn fmt_error(_: fmt::Error) -> Error {
+    Error::message("an error occurred when writing the diagnostic notation")
+}
+
+/// Writes a byte string as `h'...'`.
+pub(crate) fn write_bytes<W>(out: &mut W, bytes: &[u8]) -> fmt::Result
+where
+    W: fmt::Write + ?Sized,
+{
+    out.write_str("h'")?;
+    for byte in bytes {
+        write!(out, "{:02x}", byte)?;
+    }
+    out.write_str("'")
+}
+
+/// Writes a text string with JSON escapes.
+pub(crate) fn write_text<W>(out: &mut W, s: &str) -> fmt::Result
+where
+    W: fmt::Write + ?Sized,
+{
+    out.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            '\u{0}'..='\u{1f}' | '\u{7f}' => write!(out, "\\u{:04x}", c as u32)?,
+            c => out.write_char(c)?,
+        }
+    }
+    out.write_char('"')
+}
+
+/// Writes a floating point number, the exponent is written as `e+300` and not `e300`.
+pub(crate) fn write_float<W>(out: &mut W, value: f64) -> fmt::Result
+where
+    W: fmt::Write + ?Sized,
+{
+    if value.is_nan() {
+        out.write_str("NaN")
+    } else if value.is_infinite() {
+        out.write_str(if value > 0.0 { "Infinity" } else { "-Infinity" })
+    } else {
+        let mut writer = FloatWriter {
+            out,
+            fraction: false,
+            exponent: false,
+        };
+        write!(writer, "{:?}", value)
+    }
+}
+
+// Rust writes `1e300` where diagnostic notation uses `1.0e+300`.
+struct FloatWriter<'a, W: ?Sized> {
+    out: &'a mut W,
+    fraction: bool,
+    exponent: bool,
+}
+
+impl<'a, W> fmt::Write for FloatWriter<'a, W>
+where
+    W: fmt::Write + ?Sized,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            if self.exponent {
+                self.exponent = false;
+                if c != '-' {
+                    self.out.write_char('+')?;
+                }
+            }
+            match c {
+                '.' => self.fraction = true,
+                'e' => {
+                    if !self.fraction {
+                        self.out.write_str(".0")?;
+                    }
+                    self.exponent = true;
+                }
+                _ => {}
+            }
+            self.out.write_char(c)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,668 @@
+//! Parser for diagnostic notation.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use byteorder::{BigEndian, ByteOrder};
+use core::f64;
+use core::str;
+use half::f16;
+
+use crate::error::{Error, ErrorCode, Result};
+use crate::ser::Serializer;
+use crate::tags::{NEGATIVE_BIGNUM, POSITIVE_BIGNUM};
+#[cfg(feature = "std")]
+use crate::value::Value;
+use crate::write::Write;
+
+/// Parses a data item in diagnostic notation into a `Value`.
+///
+/// ```
+/// use serde_cbor::Value;
+///
+/// let value = serde_cbor::diag::parse("[1, h'0102', 1(-2)]").unwrap();
+/// assert_eq!(value, Value::Array(vec![
+///     Value::UnsignedInteger(1),
+///     Value::Bytes(vec![1, 2]),
+///     Value::Tag(1, Box::new(Value::SignedInteger(-2))),
+/// ]));
+/// ```
+#[cfg(feature = "std")]
+pub fn parse(text: &str) -> Result<Value> {
+    let bytes = parse_to_vec(text)?;
+    crate::from_slice(&bytes)
+}
+
+/// Parses a data item in diagnostic notation and encodes it as CBOR.
+///
+/// ```
+/// let bytes = serde_cbor::diag::parse_to_vec(r#"{_ "a": 1_1, "b": b64'AQI='}"#).unwrap();
+/// assert_eq!(bytes, b"\xbf\x61\x61\x19\x00\x01\x61\x62\x42\x01\x02\xff");
+/// ```
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn parse_to_vec(text: &str) -> Result<Vec<u8>> {
+    let mut vec = Vec::new();
+    parse_into(text, &mut Serializer::new(&mut vec))?;
+    Ok(vec)
+}
+
+/// Parses a data item in diagnostic notation and writes its encoding to the serializer.
+///
+/// The encoding follows the notation: indefinite length markers and encoding indicators are
+/// honored, everything else uses the shortest encoding. Integers that don't fit in 64 bits are
+/// encoded as bignums. Errors report the byte offset in `text`.
+pub fn parse_into<W>(text: &str, serializer: &mut Serializer<W>) -> Result<()>
+where
+    W: Write,
+{
+    let mut parser = Parser {
+        input: text.as_bytes(),
+        pos: 0,
+        ser: serializer,
+        remaining_depth: 128,
+    };
+    parser.skip_whitespace()?;
+    parser.parse_item()?;
+    parser.skip_whitespace()?;
+    if parser.pos < parser.input.len() {
+        return Err(parser.error(ErrorCode::TrailingData));
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum StringKind {
+    Text,
+    Bytes,
+    Hex,
+    Base64,
+}
+
+impl StringKind {
+    fn major(self) -> u8 {
+        match self {
+            StringKind::Text => 3,
+            _ => 2,
+        }
+    }
+}
+
+struct Parser<'a, W> {
+    input: &'a [u8],
+    pos: usize,
+    ser: &'a mut Serializer<W>,
+    remaining_depth: u8,
+}
+
+impl<'a, W> Parser<'a, W>
+where
+    W: Write,
+{
+    fn error(&self, reason: ErrorCode) -> Error {
+        Error::syntax(reason, self.pos as u64)
+    }
+
+    // Returns an EOF error at the end of the input and a syntax error otherwise.
+    fn unexpected(&self) -> Error {
+        if self.pos < self.input.len() {
+            self.error(ErrorCode::InvalidDiagnostic)
+        } else {
+            self.error(ErrorCode::EofWhileParsingValue)
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).cloned()
+    }
+
+    fn starts_with(&self, prefix: &[u8]) -> bool {
+        self.input[self.pos..].starts_with(prefix)
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<()> {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn recursion_checked<F>(&mut self, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut Self) -> Result<()>,
+    {
+        self.remaining_depth -= 1;
+        if self.remaining_depth == 0 {
+            return Err(self.error(ErrorCode::RecursionLimitExceeded));
+        }
+        let r = f(self);
+        self.remaining_depth += 1;
+        r
+    }
+
+    // Skips whitespace and comments, which are either enclosed in slashes or start with `#`.
+    fn skip_whitespace(&mut self) -> Result<()> {
+        self.pos = skip_whitespace(self.input, self.pos)?;
+        Ok(())
+    }
+
+    // Parses an encoding indicator `_0` to `_3`.
+    fn parse_indicator(&mut self) -> Option<u8> {
+        match self.input.get(self.pos..self.pos + 2) {
+            Some(&[b'_', digit @ b'0'..=b'3']) => {
+                self.pos += 2;
+                Some(digit - b'0')
+            }
+            _ => None,
+        }
+    }
+
+    fn write_header(&mut self, major: u8, value: u64, width: Option<u8>) -> Result<()> {
+        match width {
+            None => self.ser.write_u64(major, value),
+            Some(width) if width < 3 && value >> (8 << width) != 0 => {
+                Err(self.error(ErrorCode::InvalidDiagnostic))
+            }
+            Some(width) => self.ser.write_sized(major, value, width),
+        }
+    }
+
+    fn parse_item(&mut self) -> Result<()> {
+        match self.peek() {
+            Some(b'[') => self.parse_collection(4),
+            Some(b'{') => self.parse_collection(5),
+            Some(b'(') => self.parse_indefinite_string(),
+            Some(b'-') | Some(b'0'..=b'9') => self.parse_number(),
+            _ => match self.string_kind() {
+                Some((kind, start)) => self.parse_string(kind, start, true),
+                None => self.parse_name(),
+            },
+        }
+    }
+
+    // Detects the start of a string, returns its kind and the position after the opening quote.
+    fn string_kind(&self) -> Option<(StringKind, usize)> {
+        if self.starts_with(b"\"") {
+            Some((StringKind::Text, self.pos + 1))
+        } else if self.starts_with(b"'") {
+            Some((StringKind::Bytes, self.pos + 1))
+        } else if self.starts_with(b"h'") {
+            Some((StringKind::Hex, self.pos + 2))
+        } else if self.starts_with(b"b64'") {
+            Some((StringKind::Base64, self.pos + 4))
+        } else {
+            None
+        }
+    }
+
+    // The string is decoded twice, first to determine its length and then to write it.
+    fn parse_string(&mut self, kind: StringKind, start: usize, allow_empty: bool) -> Result<()> {
+        let input = self.input;
+        let mut len = 0u64;
+        self.pos = decode_string(input, start, kind, &mut |bytes| {
+            len += bytes.len() as u64;
+            Ok(())
+        })?;
+        let major = kind.major();
+        let width = self.parse_indicator();
+        if width.is_none() && allow_empty && self.peek() == Some(b'_') {
+            // `''_` and `""_` are empty indefinite length strings.
+            if len != 0 {
+                return Err(self.error(ErrorCode::InvalidDiagnostic));
+            }
+            self.pos += 1;
+            return self.ser.write_raw(&[major << 5 | 31, 0xff]);
+        }
+        self.write_header(major, len, width)?;
+        let ser = &mut *self.ser;
+        decode_string(input, start, kind, &mut |bytes| ser.write_raw(bytes))?;
+        Ok(())
+    }
+
+    fn parse_indefinite_string(&mut self) -> Result<()> {
+        self.pos += 1;
+        self.expect(b'_')?;
+        self.skip_whitespace()?;
+        let major = match self.string_kind() {
+            Some((kind, _)) => kind.major(),
+            None => return Err(self.unexpected()),
+        };
+        self.ser.write_raw(&[major << 5 | 31])?;
+        loop {
+            match self.string_kind() {
+                Some((kind, start)) if kind.major() == major => {
+                    self.parse_string(kind, start, false)?
+                }
+                _ => return Err(self.unexpected()),
+            }
+            self.skip_whitespace()?;
+            match self.peek() {
+                Some(b',') => {
+                    self.pos += 1;
+                    self.skip_whitespace()?;
+                }
+                Some(b')') => {
+                    self.pos += 1;
+                    return self.ser.write_raw(&[0xff]);
+                }
+                _ => return Err(self.unexpected()),
+            }
+        }
+    }
+
+    fn parse_collection(&mut self, major: u8) -> Result<()> {
+        let close = if major == 4 { b']' } else { b'}' };
+        self.pos += 1;
+        let mut width = None;
+        let mut indefinite = false;
+        if self.peek() == Some(b'_') {
+            width = self.parse_indicator();
+            if width.is_none() {
+                self.pos += 1;
+                indefinite = true;
+            }
+        }
+        self.skip_whitespace()?;
+        if indefinite {
+            self.ser.write_raw(&[major << 5 | 31])?;
+        } else {
+            let len = count_items(self.input, self.pos, close)?;
+            self.write_header(major, len, width)?;
+        }
+        self.recursion_checked(|p| p.parse_items(close, major == 5))?;
+        if indefinite {
+            self.ser.write_raw(&[0xff])?;
+        }
+        Ok(())
+    }
+
+    fn parse_items(&mut self, close: u8, map: bool) -> Result<()> {
+        if self.peek() == Some(close) {
+            self.pos += 1;
+            return Ok(());
+        }
+        loop {
+            self.parse_item()?;
+            self.skip_whitespace()?;
+            if map {
+                self.expect(b':')?;
+                self.skip_whitespace()?;
+                self.parse_item()?;
+                self.skip_whitespace()?;
+            }
+            match self.peek() {
+                Some(b',') => {
+                    self.pos += 1;
+                    self.skip_whitespace()?;
+                }
+                Some(byte) if byte == close => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                _ => return Err(self.unexpected()),
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<()> {
+        let start = self.pos;
+        let negative = self.peek() == Some(b'-');
+        if negative {
+            self.pos += 1;
+        }
+        if self.starts_with(b"Infinity") {
+            self.pos += 8;
+            let value = if negative {
+                -f64::INFINITY
+            } else {
+                f64::INFINITY
+            };
+            let width = self.parse_indicator();
+            return self.write_float(value, width);
+        }
+        let radix = if self.starts_with(b"0x") {
+            16
+        } else if self.starts_with(b"0o") {
+            8
+        } else if self.starts_with(b"0b") {
+            2
+        } else {
+            10
+        };
+        if radix != 10 {
+            self.pos += 2;
+        }
+        let digits = self.pos;
+        let mut value = 0u128;
+        while let Some(digit) = self.peek().and_then(|b| (b as char).to_digit(radix)) {
+            value = match value
+                .checked_mul(u128::from(radix))
+                .and_then(|v| v.checked_add(u128::from(digit)))
+            {
+                Some(value) => value,
+                None => return Err(self.error(ErrorCode::InvalidDiagnostic)),
+            };
+            self.pos += 1;
+        }
+        if self.pos == digits {
+            return Err(self.unexpected());
+        }
+        if radix == 10 {
+            if let Some(b'.') | Some(b'e') | Some(b'E') = self.peek() {
+                return self.parse_float(start);
+            }
+        }
+        let width = self.parse_indicator();
+        if self.peek() == Some(b'(') {
+            if negative || value > u128::from(u64::max_value()) {
+                return Err(self.error(ErrorCode::InvalidDiagnostic));
+            }
+            self.write_header(6, value as u64, width)?;
+            self.pos += 1;
+            self.skip_whitespace()?;
+            self.recursion_checked(|p| p.parse_item())?;
+            self.skip_whitespace()?;
+            return self.expect(b')');
+        }
+        let (major, value, tag) = if negative && value > 0 {
+            (1, value - 1, NEGATIVE_BIGNUM)
+        } else {
+            (0, value, POSITIVE_BIGNUM)
+        };
+        if value <= u128::from(u64::max_value()) {
+            self.write_header(major, value as u64, width)
+        } else if width.is_none() {
+            self.ser.write_bignum(tag, value)
+        } else {
+            Err(self.error(ErrorCode::InvalidDiagnostic))
+        }
+    }
+
+    fn parse_float(&mut self, start: usize) -> Result<()> {
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            self.skip_digits()?;
+        }
+        if let Some(b'e') | Some(b'E') = self.peek() {
+            self.pos += 1;
+            if let Some(b'+') | Some(b'-') = self.peek() {
+                self.pos += 1;
+            }
+            self.skip_digits()?;
+        }
+        // The input is valid UTF-8 and the number consists of ASCII characters only.
+        let text = str::from_utf8(&self.input[start..self.pos]).expect("ASCII number");
+        let value = match text.parse::<f64>() {
+            Ok(value) => value,
+            Err(_) => return Err(self.error(ErrorCode::InvalidDiagnostic)),
+        };
+        let width = self.parse_indicator();
+        self.write_float(value, width)
+    }
+
+    fn skip_digits(&mut self) -> Result<()> {
+        let start = self.pos;
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Err(self.unexpected());
+        }
+        Ok(())
+    }
+
+    // Floats use the shortest exact encoding unless an encoding indicator asks for another one.
+    fn write_float(&mut self, value: f64, width: Option<u8>) -> Result<()> {
+        match width {
+            None => serde::Serializer::serialize_f64(&mut *self.ser, value),
+            Some(1) => {
+                let half = f16::from_f64(value);
+                if !value.is_nan() && f64::from(half).to_bits() != value.to_bits() {
+                    return Err(self.error(ErrorCode::InvalidDiagnostic));
+                }
+                let mut buf = [0xf9, 0, 0];
+                BigEndian::write_u16(&mut buf[1..], half.to_bits());
+                self.ser.write_raw(&buf)
+            }
+            Some(2) => {
+                let single = value as f32;
+                if !value.is_nan() && f64::from(single).to_bits() != value.to_bits() {
+                    return Err(self.error(ErrorCode::InvalidDiagnostic));
+                }
+                let mut buf = [0xfa, 0, 0, 0, 0];
+                BigEndian::write_f32(&mut buf[1..], single);
+                self.ser.write_raw(&buf)
+            }
+            Some(3) => {
+                let mut buf = [0xfb, 0, 0, 0, 0, 0, 0, 0, 0];
+                BigEndian::write_f64(&mut buf[1..], value);
+                self.ser.write_raw(&buf)
+            }
+            Some(_) => Err(self.error(ErrorCode::InvalidDiagnostic)),
+        }
+    }
+
+    fn parse_name(&mut self) -> Result<()> {
+        let start = self.pos;
+        while let Some(b'a'..=b'z') | Some(b'A'..=b'Z') = self.peek() {
+            self.pos += 1;
+        }
+        match &self.input[start..self.pos] {
+            b"false" => self.ser.write_raw(&[0xf4]),
+            b"true" => self.ser.write_raw(&[0xf5]),
+            b"null" => self.ser.write_raw(&[0xf6]),
+            b"undefined" => self.ser.write_raw(&[0xf7]),
+            b"Infinity" => {
+                let width = self.parse_indicator();
+                self.write_float(f64::INFINITY, width)
+            }
+            b"NaN" => {
+                let width = self.parse_indicator();
+                self.write_float(f64::NAN, width)
+            }
+            b"simple" => {
+                self.expect(b'(')?;
+                self.skip_whitespace()?;
+                let start = self.pos;
+                let mut value = 0u32;
+                while let Some(digit @ b'0'..=b'9') = self.peek() {
+                    value = value
+                        .saturating_mul(10)
+                        .saturating_add(u32::from(digit - b'0'));
+                    self.pos += 1;
+                }
+                if self.pos == start {
+                    return Err(self.unexpected());
+                }
+                match value {
+                    0..=23 => self.ser.write_raw(&[0xe0 | value as u8])?,
+                    32..=255 => self.ser.write_raw(&[0xf8, value as u8])?,
+                    _ => return Err(self.error(ErrorCode::InvalidDiagnostic)),
+                }
+                self.skip_whitespace()?;
+                self.expect(b')')
+            }
+            _ => {
+                self.pos = start;
+                Err(self.unexpected())
+            }
+        }
+    }
+}
+
+fn skip_whitespace(input: &[u8], mut pos: usize) -> Result<usize> {
+    loop {
+        match input.get(pos) {
+            Some(b' ') | Some(b'\t') | Some(b'\r') | Some(b'\n') => pos += 1,
+            Some(b'/') => match input[pos + 1..].iter().position(|&b| b == b'/') {
+                Some(len) => pos += len + 2,
+                None => {
+                    return Err(Error::syntax(
+                        ErrorCode::EofWhileParsingValue,
+                        input.len() as u64,
+                    ))
+                }
+            },
+            Some(b'#') => match input[pos..].iter().position(|&b| b == b'\n') {
+                Some(len) => pos += len + 1,
+                None => pos = input.len(),
+            },
+            _ => return Ok(pos),
+        }
+    }
+}
+
+// Counts the items of an array or the pairs of a map up to the closing bracket.
+fn count_items(input: &[u8], mut pos: usize, close: u8) -> Result<u64> {
+    let eof = || Error::syntax(ErrorCode::EofWhileParsingValue, input.len() as u64);
+    pos = skip_whitespace(input, pos)?;
+    if input.get(pos) == Some(&close) {
+        return Ok(0);
+    }
+    let mut count = 1;
+    let mut depth = 0usize;
+    loop {
+        match *input.get(pos).ok_or_else(eof)? {
+            b'[' | b'{' | b'(' => depth += 1,
+            b']' | b'}' | b')' if depth == 0 => return Ok(count),
+            b']' | b'}' | b')' => depth -= 1,
+            b',' if depth == 0 => count += 1,
+            quote @ b'"' | quote @ b'\'' => {
+                pos += 1;
+                loop {
+                    match *input.get(pos).ok_or_else(eof)? {
+                        b'\\' => pos += 1,
+                        byte if byte == quote => break,
+                        _ => {}
+                    }
+                    pos += 1;
+                }
+            }
+            b'/' | b'#' => {
+                pos = skip_whitespace(input, pos)?;
+                continue;
+            }
+            _ => {}
+        }
+        pos += 1;
+    }
+}
+
+// Decodes the content of a string starting after the opening quote. Returns the position after
+// the closing quote.
+fn decode_string(
+    input: &[u8],
+    mut pos: usize,
+    kind: StringKind,
+    emit: &mut dyn FnMut(&[u8]) -> Result<()>,
+) -> Result<usize> {
+    let quote = if kind == StringKind::Text {
+        b'"'
+    } else {
+        b'\''
+    };
+    let invalid = |pos: usize| Error::syntax(ErrorCode::InvalidDiagnostic, pos as u64);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    loop {
+        let byte = match input.get(pos) {
+            Some(&byte) => byte,
+            None => return Err(Error::syntax(ErrorCode::EofWhileParsingValue, pos as u64)),
+        };
+        pos += 1;
+        match kind {
+            _ if byte == quote => {
+                // Leftover hex digits or base64 characters.
+                if (kind == StringKind::Hex && bits != 0) || bits >= 6 {
+                    return Err(invalid(pos - 1));
+                }
+                return Ok(pos);
+            }
+            StringKind::Text | StringKind::Bytes if byte == b'\\' => {
+                let (c, next) = parse_escape(input, pos)?;
+                pos = next;
+                let mut buf = [0; 4];
+                emit(c.encode_utf8(&mut buf).as_bytes())?;
+            }
+            StringKind::Text | StringKind::Bytes => emit(&[byte])?,
+            _ if byte.is_ascii_whitespace() => {}
+            StringKind::Hex => {
+                let digit = match (byte as char).to_digit(16) {
+                    Some(digit) => digit,
+                    None => return Err(invalid(pos - 1)),
+                };
+                acc = acc << 4 | digit;
+                bits += 4;
+                if bits == 8 {
+                    emit(&[acc as u8])?;
+                    acc = 0;
+                    bits = 0;
+                }
+            }
+            StringKind::Base64 if byte == b'=' => {}
+            StringKind::Base64 => {
+                let value = match byte {
+                    b'A'..=b'Z' => byte - b'A',
+                    b'a'..=b'z' => byte - b'a' + 26,
+                    b'0'..=b'9' => byte - b'0' + 52,
+                    b'+' | b'-' => 62,
+                    b'/' | b'_' => 63,
+                    _ => return Err(invalid(pos - 1)),
+                };
+                acc = acc << 6 | u32::from(value);
+                bits += 6;
+                if bits >= 8 {
+                    bits -= 8;
+                    emit(&[(acc >> bits) as u8])?;
+                    acc &= (1 << bits) - 1;
+                }
+            }
+        }
+    }
+}
+
+// Parses the escape sequence after a backslash, returns the character and the next position.
+fn parse_escape(input: &[u8], pos: usize) -> Result<(char, usize)> {
+    let invalid = || Error::syntax(ErrorCode::InvalidDiagnostic, pos as u64);
+    let c = match input.get(pos) {
+        Some(b'"') => '"',
+        Some(b'\'') => '\'',
+        Some(b'\\') => '\\',
+        Some(b'/') => '/',
+        Some(b'b') => '\u{8}',
+        Some(b'f') => '\u{c}',
+        Some(b'n') => '\n',
+        Some(b'r') => '\r',
+        Some(b't') => '\t',
+        Some(b'u') => {
+            let high = parse_hex4(input, pos + 1).ok_or_else(invalid)?;
+            let (c, next) = match high {
+                // A high surrogate must be followed by an escaped low surrogate.
+                0xd800..=0xdbff => {
+                    if input.get(pos + 5..pos + 7) != Some(b"\\u") {
+                        return Err(invalid());
+                    }
+                    match parse_hex4(input, pos + 7) {
+                        Some(low @ 0xdc00..=0xdfff) => {
+                            (0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00), pos + 11)
+                        }
+                        _ => return Err(invalid()),
+                    }
+                }
+                _ => (high, pos + 5),
+            };
+            let c = core::char::from_u32(c).ok_or_else(invalid)?;
+            return Ok((c, next));
+        }
+        Some(_) => return Err(invalid()),
+        None => return Err(Error::syntax(ErrorCode::EofWhileParsingValue, pos as u64)),
+    };
+    Ok((c, pos + 1))
+}
+
+fn parse_hex4(input: &[u8], pos: usize) -> Option<u32> {
+    let digits = input.get(pos..pos + 4)?;
+    digits.iter().try_fold(0, |acc, &b| {
+        (b as char).to_digit(16).map(|digit| acc << 4 | digit)
+    })
+}
@@ -0,0 +1,208 @@
+//! Low-level push encoder for CBOR data.
+//!
+//! An [`Encoder`](struct.Encoder.html) writes single headers and values, so it can produce data
+//! that the serde data model can't express: indefinite length strings written in chunks, the
+//! simple value `undefined`, floats of a given precision or items of indefinite length whose
+//! content is written by hand. Integers and lengths are always written in their shortest form.
+//!
+//! The encoder does not check that the written items are well-formed. Arrays and maps of known
+//! length must be followed by the given number of items (twice as many for maps) and items of
+//! indefinite length must be closed with [`write_break`](struct.Encoder.html#method.write_break).
+//!
+//! # Examples
+//!
+//! ```
+//! use serde_cbor::encoder::Encoder;
+//! use serde_cbor::ser::SliceWrite;
+//!
+//! let mut buf = [0; 16];
+//! let mut encoder = Encoder::new(SliceWrite::new(&mut buf[..]));
+//! encoder.write_map_header(Some(1)).unwrap();
+//! encoder.write_text("a").unwrap();
+//! // An indefinite length byte string with two chunks.
+//! encoder.write_bytes_header(None).unwrap();
+//! encoder.write_bytes(&[1, 2]).unwrap();
+//! encoder.write_bytes(&[3]).unwrap();
+//! encoder.write_break().unwrap();
+//!
+//! let writer = encoder.into_inner();
+//! let len = writer.bytes_written();
+//! assert_eq!(&buf[..len], b"\xa1\x61a\x5f\x42\x01\x02\x41\x03\xff");
+//! ```
+
+use byteorder::{BigEndian, ByteOrder};
+use serde::ser::Serialize;
+
+use crate::error::{Error, Result};
+use crate::ser::Serializer;
+use crate::write::Write;
+
+/// A push encoder that writes CBOR headers and values to a writer.
+#[derive(Debug)]
+pub struct Encoder<W> {
+    ser: Serializer<W>,
+}
+
+impl<W> Encoder<W>
+where
+    W: Write,
+{
+    /// Creates a new encoder.
+    pub fn new(writer: W) -> Self {
+        Encoder {
+            ser: Serializer::new(writer),
+        }
+    }
+
+    /// Unwraps the writer of the encoder.
+    pub fn into_inner(self) -> W {
+        self.ser.into_inner()
+    }
+
+    // Writes the header of an item of indefinite length.
+    fn write_indefinite(&mut self, major: u8) -> Result<()> {
+        self.ser.write_raw(&[major << 5 | 31])
+    }
+
+    fn write_header(&mut self, major: u8, len: Option<u64>) -> Result<()> {
+        match len {
+            Some(len) => self.ser.write_u64(major, len),
+            None => self.write_indefinite(major),
+        }
+    }
+
+    /// Writes an unsigned integer.
+    pub fn write_unsigned(&mut self, value: u64) -> Result<()> {
+        self.ser.write_u64(0, value)
+    }
+
+    /// Writes the negative integer `-1 - value`.
+    pub fn write_negative(&mut self, value: u64) -> Result<()> {
+        self.ser.write_u64(1, value)
+    }
+
+    /// Writes a signed integer.
+    pub fn write_int(&mut self, value: i64) -> Result<()> {
+        if value < 0 {
+            self.write_negative((-1 - value) as u64)
+        } else {
+            self.write_unsigned(value as u64)
+        }
+    }
+
+    /// Writes the header of a byte string, `None` starts a string of indefinite length.
+    ///
+    /// The content of a string with a known length is written with `write_raw`. A string of
+    /// indefinite length is followed by chunks written with `write_bytes` and a break.
+    pub fn write_bytes_header(&mut self, len: Option<u64>) -> Result<()> {
+        self.write_header(2, len)
+    }
+
+    /// Writes a byte string or a chunk of a byte string of indefinite length.
+    pub fn write_bytes(&mut self, value: &[u8]) -> Result<()> {
+        self.write_bytes_header(Some(value.len() as u64))?;
+        self.ser.write_raw(value)
+    }
+
+    /// Writes the header of a text string, `None` starts a string of indefinite length.
+    ///
+    /// The content of a string with a known length is written with `write_raw`. A string of
+    /// indefinite length is followed by chunks written with `write_text` and a break.
+    pub fn write_text_header(&mut self, len: Option<u64>) -> Result<()> {
+        self.write_header(3, len)
+    }
+
+    /// Writes a text string or a chunk of a text string of indefinite length.
+    pub fn write_text(&mut self, value: &str) -> Result<()> {
+        self.write_text_header(Some(value.len() as u64))?;
+        self.ser.write_raw(value.as_bytes())
+    }
+
+    /// Writes the header of an array, `None` starts an array of indefinite length.
+    pub fn write_array_header(&mut self, len: Option<u64>) -> Result<()> {
+        self.write_header(4, len)
+    }
+
+    /// Writes the header of a map, `None` starts a map of indefinite length.
+    ///
+    /// The length is the number of key-value pairs.
+    pub fn write_map_header(&mut self, len: Option<u64>) -> Result<()> {
+        self.write_header(5, len)
+    }
+
+    /// Writes a tag, it must be followed by the tagged item.
+    pub fn write_tag(&mut self, tag: u64) -> Result<()> {
+        self.ser.write_u64(6, tag)
+    }
+
+    /// Writes a simple value.
+    ///
+    /// Fails for the reserved values 24 to 31.
+    pub fn write_simple(&mut self, value: u8) -> Result<()> {
+        match value {
+            24..=31 => Err(Error::message(
+                "The simple value is reserved and can't be stored in CBOR",
+            )),
+            _ => self.ser.write_u8(7, value),
+        }
+    }
+
+    /// Writes `false` or `true`.
+    pub fn write_bool(&mut self, value: bool) -> Result<()> {
+        self.write_simple(if value { 21 } else { 20 })
+    }
+
+    /// Writes `null`.
+    pub fn write_null(&mut self) -> Result<()> {
+        self.write_simple(22)
+    }
+
+    /// Writes `undefined`.
+    pub fn write_undefined(&mut self) -> Result<()> {
+        self.write_simple(23)
+    }
+
+    /// Writes a half precision float given by its IEEE 754 bits, like `0x3e00` for 1.5.
+    pub fn write_f16(&mut self, bits: u16) -> Result<()> {
+        let mut buf = [0xf9, 0, 0];
+        BigEndian::write_u16(&mut buf[1..], bits);
+        self.ser.write_raw(&buf)
+    }
+
+    /// Writes a single precision float.
+    pub fn write_f32(&mut self, value: f32) -> Result<()> {
+        let mut buf = [0xfa, 0, 0, 0, 0];
+        BigEndian::write_f32(&mut buf[1..], value);
+        self.ser.write_raw(&buf)
+    }
+
+    /// Writes a double precision float.
+    pub fn write_f64(&mut self, value: f64) -> Result<()> {
+        let mut buf = [0xfb, 0, 0, 0, 0, 0, 0, 0, 0];
+        BigEndian::write_f64(&mut buf[1..], value);
+        self.ser.write_raw(&buf)
+    }
+
+    /// Writes a float with the shortest precision that preserves its value.
+    pub fn write_float(&mut self, value: f64) -> Result<()> {
+        serde::Serializer::serialize_f64(&mut self.ser, value)
+    }
+
+    /// Writes the break that ends an item of indefinite length.
+    pub fn write_break(&mut self) -> Result<()> {
+        self.ser.write_raw(&[0xff])
+    }
+
+    /// Writes bytes without a header, like the content of a string or encoded CBOR data.
+    pub fn write_raw(&mut self, bytes: &[u8]) -> Result<()> {
+        self.ser.write_raw(bytes)
+    }
+
+    /// Serializes a value with the default options of `Serializer`.
+    pub fn serialize<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut self.ser)
+    }
+}
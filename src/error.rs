@@ -8,8 +8,22 @@ use std::error;
 #[cfg(feature = "std")]
 use std::io;
 
+#[cfg(any(feature = "std", feature = "alloc"))]
+use crate::de::PathSegment;
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::boxed::Box;
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use alloc::string::{String, ToString};
+
+/// This type represents all possible errors that can occur when serializing or deserializing CBOR
+/// data.
+// Boxed if possible to keep results small.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub struct Error(Box<ErrorImpl>);
+
 /// This type represents all possible errors that can occur when serializing or deserializing CBOR
 /// data.
+#[cfg(not(any(feature = "std", feature = "alloc")))]
 pub struct Error(ErrorImpl);
 
 /// Alias for a `Result` with the error type `serde_cbor::Error`.
@@ -29,108 +43,205 @@ pub enum Category {
 }
 
 impl Error {
+    /// The specific cause of this error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use serde_cbor::error::ErrorCode;
+    ///
+    /// let err = serde_cbor::from_slice::<u8>(b"\x01\x02").unwrap_err();
+    /// match *err.code() {
+    ///     ErrorCode::TrailingData => {}
+    ///     _ => panic!("unexpected error: {}", err),
+    /// }
+    ///
+    /// let err = serde_cbor::from_slice::<u8>(b"\xf6").unwrap_err();
+    /// match *err.code() {
+    ///     ErrorCode::InvalidType => {
+    ///         assert_eq!((err.unexpected(), err.expected()), (Some("null"), Some("u8")));
+    ///     }
+    ///     _ => panic!("unexpected error: {}", err),
+    /// }
+    /// ```
+    pub fn code(&self) -> &ErrorCode {
+        &self.0.code
+    }
+
     /// The byte offset at which the error occurred.
+    ///
+    /// For serialization errors this is the number of bytes written before the error.
     pub fn offset(&self) -> u64 {
         self.0.offset
     }
 
-    pub(crate) fn syntax(code: ErrorCode, offset: u64) -> Error {
+    /// The location of the item at which the error occurred, like `.servers[3].port`.
+    ///
+    /// This is only known if the error occurred inside an array, map or struct, and, when
+    /// deserializing, if the deserializer tracks the path, see
+    /// [`Deserializer::track_path`](../de/struct.Deserializer.html#method.track_path).
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    pub fn path(&self) -> Option<&str> {
+        self.0.path.as_ref().map(|path| &path[..])
+    }
+
+    /// The message of a custom error, if the code is `ErrorCode::Message`.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    pub fn custom_message(&self) -> Option<&str> {
+        self.0.message.as_ref().map(|message| &message[..])
+    }
+
+    /// What was found, like `null` or `integer `300``, if the code is `ErrorCode::InvalidType` or
+    /// `ErrorCode::InvalidValue`.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    pub fn unexpected(&self) -> Option<&str> {
+        self.0.invalid.as_ref().map(|invalid| &invalid.0[..])
+    }
+
+    /// What was expected, like `u8`, if the code is `ErrorCode::InvalidType` or
+    /// `ErrorCode::InvalidValue`.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    pub fn expected(&self) -> Option<&str> {
+        self.0.invalid.as_ref().map(|invalid| &invalid.1[..])
+    }
+
+    /// The underlying IO error, if the code is `ErrorCode::Io`.
+    #[cfg(feature = "std")]
+    pub fn io_error(&self) -> Option<&io::Error> {
+        self.0.io.as_ref()
+    }
+
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn new(code: ErrorCode, offset: u64) -> Error {
+        Error(Box::new(ErrorImpl {
+            code,
+            offset,
+            path: None,
+            message: None,
+            invalid: None,
+            #[cfg(feature = "std")]
+            io: None,
+        }))
+    }
+
+    #[cfg(not(any(feature = "std", feature = "alloc")))]
+    fn new(code: ErrorCode, offset: u64) -> Error {
         Error(ErrorImpl { code, offset })
     }
 
+    // Sets the offset of the error unless it is already known.
+    pub(crate) fn at_offset(mut self, offset: u64) -> Error {
+        if self.0.offset == 0 {
+            self.0.offset = offset;
+        }
+        self
+    }
+
+    // Makes the offset of an error in an item relative to the stream that starts `base` bytes
+    // before the item.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    pub(crate) fn offset_by(mut self, base: u64) -> Error {
+        self.0.offset = self.0.offset.saturating_add(base);
+        self
+    }
+
+    // Prepends an outer segment to the path of the error.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    pub(crate) fn within(mut self, segment: PathSegment<'_>) -> Error {
+        let mut path = String::new();
+        segment.push_to(&mut path);
+        if let Some(ref inner) = self.0.path {
+            path.push_str(inner);
+        }
+        self.0.path = Some(path);
+        self
+    }
+
+    // Sets the path of the error unless an inner item already did.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    pub(crate) fn with_path(mut self, path: &str) -> Error {
+        if self.0.path.is_none() && !path.is_empty() {
+            self.0.path = Some(path.into());
+        }
+        self
+    }
+
+    pub(crate) fn syntax(code: ErrorCode, offset: u64) -> Error {
+        Error::new(code, offset)
+    }
+
     #[cfg(feature = "std")]
     pub(crate) fn io(error: io::Error) -> Error {
-        Error(ErrorImpl {
-            code: ErrorCode::Io(error),
-            offset: 0,
-        })
+        let mut err = Error::new(ErrorCode::Io, 0);
+        err.0.io = Some(error);
+        err
     }
 
     #[cfg(all(not(feature = "std"), feature = "unsealed_read_write"))]
     /// Creates an error signalling that the underlying `Read` encountered an I/O error.
     pub fn io() -> Error {
-        Error(ErrorImpl {
-            code: ErrorCode::Io,
-            offset: 0,
-        })
+        Error::new(ErrorCode::Io, 0)
     }
 
     #[cfg(feature = "unsealed_read_write")]
     /// Creates an error signalling that the scratch buffer was too small to fit the data.
     pub fn scratch_too_small(offset: u64) -> Error {
-        Error(ErrorImpl {
-            code: ErrorCode::ScratchTooSmall,
-            offset,
-        })
+        Error::new(ErrorCode::ScratchTooSmall, offset)
     }
 
     #[cfg(not(feature = "unsealed_read_write"))]
     pub(crate) fn scratch_too_small(offset: u64) -> Error {
-        Error(ErrorImpl {
-            code: ErrorCode::ScratchTooSmall,
-            offset,
-        })
+        Error::new(ErrorCode::ScratchTooSmall, offset)
     }
 
     #[cfg(feature = "unsealed_read_write")]
     /// Creates an error with a custom message.
     ///
-    /// **Note**: When the "std" feature is disabled, the message will be discarded.
-    pub fn message<T: fmt::Display>(_msg: T) -> Error {
-        #[cfg(not(feature = "std"))]
-        {
-            Error(ErrorImpl {
-                code: ErrorCode::Message,
-                offset: 0,
-            })
-        }
-        #[cfg(feature = "std")]
-        {
-            Error(ErrorImpl {
-                code: ErrorCode::Message(_msg.to_string()),
-                offset: 0,
-            })
-        }
+    /// **Note**: When the "std" and "alloc" features are disabled, the message will be discarded.
+    pub fn message<T: fmt::Display>(msg: T) -> Error {
+        Error::custom_error(msg)
     }
 
     #[cfg(not(feature = "unsealed_read_write"))]
-    pub(crate) fn message<T: fmt::Display>(_msg: T) -> Error {
-        #[cfg(not(feature = "std"))]
-        {
-            Error(ErrorImpl {
-                code: ErrorCode::Message,
-                offset: 0,
-            })
-        }
-        #[cfg(feature = "std")]
-        {
-            Error(ErrorImpl {
-                code: ErrorCode::Message(_msg.to_string()),
-                offset: 0,
-            })
-        }
+    pub(crate) fn message<T: fmt::Display>(msg: T) -> Error {
+        Error::custom_error(msg)
+    }
+
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn custom_error<T: fmt::Display>(msg: T) -> Error {
+        let mut err = Error::new(ErrorCode::Message, 0);
+        err.0.message = Some(msg.to_string());
+        err
+    }
+
+    #[cfg(not(any(feature = "std", feature = "alloc")))]
+    fn custom_error<T: fmt::Display>(_msg: T) -> Error {
+        Error::new(ErrorCode::Message, 0)
+    }
+
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn invalid(code: ErrorCode, unexp: de::Unexpected<'_>, exp: &dyn de::Expected) -> Error {
+        let mut err = Error::new(code, 0);
+        err.0.invalid = Some((unexpected_name(unexp), exp.to_string()));
+        err
+    }
+
+    #[cfg(not(any(feature = "std", feature = "alloc")))]
+    fn invalid(code: ErrorCode, _unexp: de::Unexpected<'_>, _exp: &dyn de::Expected) -> Error {
+        Error::new(code, 0)
     }
 
     #[cfg(feature = "unsealed_read_write")]
     /// Creates an error signalling that the underlying read
     /// encountered an end of input.
     pub fn eof(offset: u64) -> Error {
-        Error(ErrorImpl {
-            code: ErrorCode::EofWhileParsingValue,
-            offset,
-        })
+        Error::new(ErrorCode::EofWhileParsingValue, offset)
     }
 
     /// Categorizes the cause of this error.
     pub fn classify(&self) -> Category {
         match self.0.code {
-            #[cfg(feature = "std")]
-            ErrorCode::Message(_) => Category::Data,
-            #[cfg(not(feature = "std"))]
             ErrorCode::Message => Category::Data,
-            #[cfg(feature = "std")]
-            ErrorCode::Io(_) => Category::Io,
-            #[cfg(not(feature = "std"))]
             ErrorCode::Io => Category::Io,
             ErrorCode::ScratchTooSmall => Category::Io,
             ErrorCode::EofWhileParsingValue
@@ -145,7 +256,21 @@ impl Error {
             | ErrorCode::ArrayTooLong
             | ErrorCode::RecursionLimitExceeded
             | ErrorCode::WrongEnumFormat
-            | ErrorCode::WrongStructFormat => Category::Syntax,
+            | ErrorCode::WrongStructFormat
+            | ErrorCode::InvalidDiagnostic
+            | ErrorCode::NonShortestArgument
+            | ErrorCode::IndefiniteLength
+            | ErrorCode::NonPreferredFloat
+            | ErrorCode::UnsortedKeys
+            | ErrorCode::DuplicateKey
+            | ErrorCode::TagLimitExceeded
+            | ErrorCode::LengthLimitExceeded
+            | ErrorCode::ElementLimitExceeded
+            | ErrorCode::ChunkLimitExceeded
+            | ErrorCode::TotalLengthLimitExceeded
+            | ErrorCode::ItemLimitExceeded => Category::Syntax,
+            ErrorCode::InvalidType | ErrorCode::InvalidValue => Category::Data,
+            ErrorCode::__Nonexhaustive => unreachable!(),
         }
     }
 
@@ -195,19 +320,30 @@ impl Error {
 #[cfg(feature = "std")]
 impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
-        match self.0.code {
-            ErrorCode::Io(ref err) => Some(err),
-            _ => None,
-        }
+        self.0
+            .io
+            .as_ref()
+            .map(|err| err as &(dyn error::Error + 'static))
     }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_cause(f)?;
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        {
+            if let Some(ref path) = self.0.path {
+                return if self.0.offset == 0 {
+                    write!(f, " at {}", path)
+                } else {
+                    write!(f, " at {}, offset {}", path, self.0.offset)
+                };
+            }
+        }
         if self.0.offset == 0 {
-            fmt::Display::fmt(&self.0.code, f)
+            Ok(())
         } else {
-            write!(f, "{} at offset {}", self.0.code, self.0.offset)
+            write!(f, " at offset {}", self.0.offset)
         }
     }
 }
@@ -224,11 +360,20 @@ impl de::Error for Error {
     }
 
     fn invalid_type(unexp: de::Unexpected<'_>, exp: &dyn de::Expected) -> Error {
-        if let de::Unexpected::Unit = unexp {
-            Error::custom(format_args!("invalid type: null, expected {}", exp))
-        } else {
-            Error::custom(format_args!("invalid type: {}, expected {}", unexp, exp))
-        }
+        Error::invalid(ErrorCode::InvalidType, unexp, exp)
+    }
+
+    fn invalid_value(unexp: de::Unexpected<'_>, exp: &dyn de::Expected) -> Error {
+        Error::invalid(ErrorCode::InvalidValue, unexp, exp)
+    }
+}
+
+// CBOR calls the unit value null.
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn unexpected_name(unexp: de::Unexpected<'_>) -> String {
+    match unexp {
+        de::Unexpected::Unit => String::from("null"),
+        unexp => unexp.to_string(),
     }
 }
 
@@ -248,10 +393,7 @@ impl From<io::Error> for Error {
 #[cfg(not(feature = "std"))]
 impl From<core::fmt::Error> for Error {
     fn from(_: core::fmt::Error) -> Error {
-        Error(ErrorImpl {
-            code: ErrorCode::Message,
-            offset: 0,
-        })
+        Error::new(ErrorCode::Message, 0)
     }
 }
 
@@ -259,45 +401,126 @@ impl From<core::fmt::Error> for Error {
 struct ErrorImpl {
     code: ErrorCode,
     offset: u64,
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    path: Option<String>,
+    // The details of `ErrorCode::Message`, `ErrorCode::Io`, `ErrorCode::InvalidType` and
+    // `ErrorCode::InvalidValue`, kept out of the code so that its variants are the same for all
+    // features.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    message: Option<String>,
+    // The unexpected and the expected type or value.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    invalid: Option<(String, String)>,
+    #[cfg(feature = "std")]
+    io: Option<io::Error>,
 }
 
+impl ErrorImpl {
+    // Writes the details of the code if they are known, otherwise the code.
+    fn fmt_cause(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        {
+            if let Some(ref message) = self.message {
+                return f.write_str(message);
+            }
+            if let Some((ref unexpected, ref expected)) = self.invalid {
+                return write!(f, "{}: {}, expected {}", self.code, unexpected, expected);
+            }
+        }
+        #[cfg(feature = "std")]
+        {
+            if let Some(ref err) = self.io {
+                return fmt::Display::fmt(err, f);
+            }
+        }
+        fmt::Display::fmt(&self.code, f)
+    }
+}
+
+/// The specific cause of a `serde_cbor::Error`.
+///
+/// More variants may be added in minor releases, so matches need a wildcard arm.
+#[allow(clippy::manual_non_exhaustive)] // `#[non_exhaustive]` requires Rust 1.40.
 #[derive(Debug)]
-pub(crate) enum ErrorCode {
-    #[cfg(feature = "std")]
-    Message(String),
-    #[cfg(not(feature = "std"))]
+pub enum ErrorCode {
+    /// A custom error, for example from a `Serialize` or `Deserialize` implementation.
+    ///
+    /// `Error::custom_message` returns its message, which is discarded without the `std` and
+    /// `alloc` features.
     Message,
-    #[cfg(feature = "std")]
-    Io(io::Error),
-    #[allow(unused)]
-    #[cfg(not(feature = "std"))]
+    /// A failure to read or write bytes on an IO stream.
+    ///
+    /// With the `std` feature `Error::io_error` returns the underlying error.
     Io,
+    /// The scratch buffer was too small to fit the data.
     ScratchTooSmall,
+    /// The input ended in the middle of a data item.
     EofWhileParsingValue,
+    /// The input ended in an indefinite length array.
     EofWhileParsingArray,
+    /// The input ended in an indefinite length map.
     EofWhileParsingMap,
+    /// A length doesn't fit into `usize`.
     LengthOutOfRange,
+    /// A text string is not valid UTF-8.
     InvalidUtf8,
+    /// A reserved header byte or an unassigned simple value.
     UnassignedCode,
+    /// A header byte that is not allowed at this point.
     UnexpectedCode,
+    /// Data after the end of the item.
     TrailingData,
+    /// An array has fewer elements than expected.
     ArrayTooShort,
+    /// An array has more elements than expected.
     ArrayTooLong,
+    /// Arrays, maps and tags are nested too deeply, see `DecodeLimits::max_depth`.
     RecursionLimitExceeded,
+    /// An enum is encoded in a format that is disabled.
     WrongEnumFormat,
+    /// A struct is encoded in a format that is disabled.
     WrongStructFormat,
+    /// Invalid diagnostic notation.
+    InvalidDiagnostic,
+    /// An integer, length or tag is not in its shortest form, rejected in strict mode.
+    NonShortestArgument,
+    /// An indefinite length item, rejected in strict mode.
+    IndefiniteLength,
+    /// A float is not in its shortest form, rejected in strict mode.
+    NonPreferredFloat,
+    /// Map keys are not sorted, rejected in strict mode.
+    #[cfg_attr(not(any(feature = "std", feature = "alloc")), allow(unused))]
+    UnsortedKeys,
+    /// A map contains the same key more than once.
+    #[cfg_attr(not(any(feature = "std", feature = "alloc")), allow(unused))]
+    DuplicateKey,
+    /// Tags are nested too deeply, see `DecodeLimits::max_tag_depth`.
+    TagLimitExceeded,
+    /// A string is too long, see `DecodeLimits::max_length`.
+    LengthLimitExceeded,
+    /// An array or map has too many elements, see `DecodeLimits::max_elements`.
+    ElementLimitExceeded,
+    /// An indefinite length string has too many chunks, see `DecodeLimits::max_chunks`.
+    ChunkLimitExceeded,
+    /// The decoded strings are too long in total, see `DecodeLimits::max_total_length`.
+    TotalLengthLimitExceeded,
+    /// An item is too long, see `ItemScanner::max_item_len`.
+    ItemLimitExceeded,
+    /// The input has a different type than the one that was expected.
+    ///
+    /// `Error::unexpected` and `Error::expected` return the details, which are discarded without
+    /// the `std` and `alloc` features.
+    InvalidType,
+    /// The input has the right type but a wrong value, with the same details as `InvalidType`.
+    InvalidValue,
+    #[doc(hidden)]
+    __Nonexhaustive,
 }
 
 impl fmt::Display for ErrorCode {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            #[cfg(feature = "std")]
-            ErrorCode::Message(ref msg) => f.write_str(msg),
-            #[cfg(not(feature = "std"))]
             ErrorCode::Message => f.write_str("Unknown error"),
-            #[cfg(feature = "std")]
-            ErrorCode::Io(ref err) => fmt::Display::fmt(err, f),
-            #[cfg(not(feature = "std"))]
             ErrorCode::Io => f.write_str("Unknown I/O error"),
             ErrorCode::ScratchTooSmall => f.write_str("Scratch buffer too small"),
             ErrorCode::EofWhileParsingValue => f.write_str("EOF while parsing a value"),
@@ -313,6 +536,23 @@ impl fmt::Display for ErrorCode {
             ErrorCode::RecursionLimitExceeded => f.write_str("recursion limit exceeded"),
             ErrorCode::WrongEnumFormat => f.write_str("wrong enum format"),
             ErrorCode::WrongStructFormat => f.write_str("wrong struct format"),
+            ErrorCode::InvalidDiagnostic => f.write_str("invalid diagnostic notation"),
+            ErrorCode::NonShortestArgument => f.write_str("integer or length not in shortest form"),
+            ErrorCode::IndefiniteLength => f.write_str("indefinite length not allowed"),
+            ErrorCode::NonPreferredFloat => f.write_str("float not in shortest form"),
+            ErrorCode::UnsortedKeys => f.write_str("map keys not sorted"),
+            ErrorCode::DuplicateKey => f.write_str("duplicate map key"),
+            ErrorCode::TagLimitExceeded => f.write_str("tag nesting limit exceeded"),
+            ErrorCode::LengthLimitExceeded => f.write_str("string length limit exceeded"),
+            ErrorCode::ElementLimitExceeded => f.write_str("element count limit exceeded"),
+            ErrorCode::ChunkLimitExceeded => f.write_str("chunk count limit exceeded"),
+            ErrorCode::TotalLengthLimitExceeded => {
+                f.write_str("total string length limit exceeded")
+            }
+            ErrorCode::ItemLimitExceeded => f.write_str("item length limit exceeded"),
+            ErrorCode::InvalidType => f.write_str("invalid type"),
+            ErrorCode::InvalidValue => f.write_str("invalid value"),
+            ErrorCode::__Nonexhaustive => unreachable!(),
         }
     }
 }
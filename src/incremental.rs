@@ -0,0 +1,403 @@
+//! Incremental decoding of data that arrives in pieces.
+//!
+//! CBOR items are self-delimiting, so the end of an item can be found by looking at its headers
+//! alone. An [`ItemScanner`](struct.ItemScanner.html) does this for a buffer that grows while
+//! data arrives, for example from a socket. Each call only looks at the bytes that were added
+//! since the previous call and reports whether the item is complete or how many more bytes are
+//! needed at least. The scanner does no I/O itself.
+//!
+//! An [`IncrementalDecoder`](struct.IncrementalDecoder.html) keeps the buffer as well and
+//! deserializes each item once it is complete.
+//!
+//! # Examples
+//!
+//! ```
+//! use serde_cbor::incremental::{IncrementalDecoder, Progress};
+//!
+//! // ["hello", 1] arrives in three chunks.
+//! let chunks: [&[u8]; 3] = [b"\x82\x65he", b"llo", b"\x01\xf5"];
+//! let mut decoder = IncrementalDecoder::new();
+//!
+//! decoder.feed(chunks[0]);
+//! match decoder.decode::<(String, u8)>().unwrap() {
+//!     Progress::NeedMore(n) => assert_eq!(n, 3),
+//!     Progress::Complete(_) => unreachable!(),
+//! }
+//! decoder.feed(chunks[1]);
+//! decoder.feed(chunks[2]);
+//! match decoder.decode::<(String, u8)>().unwrap() {
+//!     Progress::Complete(value) => assert_eq!(value, ("hello".to_owned(), 1)),
+//!     Progress::NeedMore(_) => unreachable!(),
+//! }
+//! // The next item is already buffered.
+//! match decoder.decode::<bool>().unwrap() {
+//!     Progress::Complete(value) => assert!(value),
+//!     Progress::NeedMore(_) => unreachable!(),
+//! }
+//! ```
+//!
+//! # Asynchronous input
+//!
+//! The [`async_io`](../async_io/index.html) module drives an `IncrementalDecoder` with a
+//! `futures::io::AsyncRead`. It requires the `async` feature.
+//!
+//! # Framed transports
+//!
+//! CBOR items can be used as frames without a length prefix. The scanner keeps its progress
+//! between calls and never consumes input, so it fits the buffer of a codec that only grows
+//! until a frame is split off. The [`codec`](../codec/index.html) module implements such a codec
+//! for `tokio_util`, it requires the `codec` feature.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use byteorder::{BigEndian, ByteOrder};
+use serde::de::DeserializeOwned;
+
+use crate::de::{self, DecodeLimits, Deserializer};
+use crate::error::{Error, ErrorCode, Result};
+
+/// The result of scanning or decoding incomplete input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Progress<T> {
+    /// The item is not complete yet, at least this many more bytes are needed.
+    NeedMore(usize),
+    /// The item is complete.
+    Complete(T),
+}
+
+// The initial byte, the argument and the length of a header.
+type Header = (u8, Option<u64>, usize);
+
+// An item of indefinite length that contains the items being scanned.
+#[derive(Clone, Debug)]
+struct Frame {
+    // Items still needed by the parent of the indefinite item.
+    needed: u64,
+    map: bool,
+    // For maps, whether a key has been started without a value.
+    odd: bool,
+}
+
+/// Finds the end of a single data item in a buffer that grows over time.
+///
+/// The scanner checks that the item is well-formed, but doesn't check the content of text
+/// strings. Error offsets are relative to the start of the item.
+///
+/// The length of items is not limited by default, so the number of bytes needed to complete an
+/// item is chosen by the sender. Use [`max_item_len`](#method.max_item_len) for untrusted input.
+#[derive(Clone, Debug)]
+pub struct ItemScanner {
+    max_len: usize,
+    max_depth: usize,
+    // Offset of the next byte to scan.
+    pos: usize,
+    // Items still needed to complete the current definite length item.
+    needed: u64,
+    // Content bytes of a string still to be skipped.
+    skip: u64,
+    // Major type of the string of indefinite length being scanned.
+    chunks: Option<u8>,
+    stack: Vec<Frame>,
+}
+
+impl Default for ItemScanner {
+    fn default() -> Self {
+        ItemScanner {
+            max_len: usize::max_value(),
+            max_depth: de::DEFAULT_MAX_DEPTH,
+            pos: 0,
+            needed: 1,
+            skip: 0,
+            chunks: None,
+            stack: Vec::new(),
+        }
+    }
+}
+
+impl ItemScanner {
+    /// Creates a scanner for an item at the start of a buffer.
+    pub fn new() -> Self {
+        ItemScanner::default()
+    }
+
+    /// Limits the length of items in bytes.
+    ///
+    /// Scanning fails as soon as an item is known to be longer, instead of asking for more input.
+    pub fn max_item_len(mut self, len: usize) -> Self {
+        self.max_len = len;
+        self
+    }
+
+    /// Limits the number of arrays and maps of indefinite length that can be nested in each
+    /// other, by default to the same 127 levels as `DecodeLimits::max_depth`.
+    ///
+    /// Items of definite length are counted without keeping track of their nesting, so their
+    /// depth is left to the deserializer.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Forgets the progress of the current item.
+    pub fn reset(&mut self) {
+        self.pos = 0;
+        self.needed = 1;
+        self.skip = 0;
+        self.chunks = None;
+        self.stack.clear();
+    }
+
+    /// Continues to scan the item at the start of `input`.
+    ///
+    /// `input` must start with the bytes passed to the previous calls for this item, only the
+    /// bytes after them are scanned. Returns the length of the item once it is complete. The
+    /// scanner then starts over, so the next call expects an input that starts with the
+    /// following item.
+    pub fn scan(&mut self, input: &[u8]) -> Result<Progress<usize>> {
+        let progress = self.scan_inner(input).and_then(|progress| {
+            // The item is at least as long as the input and the missing bytes.
+            let len = match progress {
+                Progress::NeedMore(n) => input.len().saturating_add(n),
+                Progress::Complete(len) => len,
+            };
+            if len > self.max_len {
+                return Err(self.error(ErrorCode::ItemLimitExceeded));
+            }
+            Ok(progress)
+        });
+        match progress {
+            Ok(Progress::NeedMore(_)) => {}
+            _ => self.reset(),
+        }
+        progress
+    }
+
+    fn scan_inner(&mut self, input: &[u8]) -> Result<Progress<usize>> {
+        loop {
+            if self.skip > 0 {
+                let available = (input.len() - self.pos) as u64;
+                if available < self.skip {
+                    self.pos = input.len();
+                    self.skip -= available;
+                    return Ok(need_more(self.skip));
+                }
+                self.pos += self.skip as usize;
+                self.skip = 0;
+            }
+
+            if self.needed == 0 && self.chunks.is_none() {
+                let top = match self.stack.last_mut() {
+                    Some(top) => top,
+                    None => return Ok(Progress::Complete(self.pos)),
+                };
+                // Between the items of an array or map of indefinite length.
+                match input.get(self.pos) {
+                    None => return Ok(Progress::NeedMore(1)),
+                    Some(&0xff) if top.odd => return Err(self.error(ErrorCode::UnexpectedCode)),
+                    Some(&0xff) => {
+                        self.needed = top.needed;
+                        self.stack.pop();
+                        self.pos += 1;
+                        continue;
+                    }
+                    Some(_) => {
+                        if top.map {
+                            top.odd = !top.odd;
+                        }
+                        self.needed = 1;
+                    }
+                }
+            }
+
+            let (byte, argument, len) = match self.header(input)? {
+                Ok(header) => header,
+                Err(missing) => return Ok(Progress::NeedMore(missing)),
+            };
+            let major = byte >> 5;
+
+            if let Some(chunk_major) = self.chunks {
+                // A chunk of a string of indefinite length.
+                match argument {
+                    None if byte == 0xff => self.chunks = None,
+                    Some(argument) if major == chunk_major => self.skip = argument,
+                    _ => return Err(self.error(ErrorCode::UnexpectedCode)),
+                }
+                self.pos += len;
+                continue;
+            }
+
+            match (major, argument) {
+                (0, Some(_)) | (1, Some(_)) => {}
+                (2, Some(len)) | (3, Some(len)) => self.skip = len,
+                (2, None) | (3, None) => self.chunks = Some(major),
+                (4, Some(items)) => self.add_needed(items)?,
+                (5, Some(pairs)) => match pairs.checked_mul(2) {
+                    Some(items) => self.add_needed(items)?,
+                    None => return Err(self.error(ErrorCode::LengthOutOfRange)),
+                },
+                (4, None) | (5, None) => {
+                    if self.stack.len() >= self.max_depth {
+                        return Err(self.error(ErrorCode::RecursionLimitExceeded));
+                    }
+                    self.stack.push(Frame {
+                        needed: self.needed - 1,
+                        map: major == 5,
+                        odd: false,
+                    });
+                    self.needed = 1;
+                }
+                (6, Some(_)) => self.add_needed(1)?,
+                (7, Some(value)) if byte == 0xf8 && value < 0x20 => {
+                    return Err(self.error(ErrorCode::UnexpectedCode))
+                }
+                (7, Some(_)) => {}
+                (7, None) => return Err(self.error(ErrorCode::UnexpectedCode)),
+                _ => return Err(self.error(ErrorCode::UnassignedCode)),
+            }
+            self.needed -= 1;
+            self.pos += len;
+        }
+    }
+
+    // Parses the header at the current position. Returns the number of missing bytes if it is
+    // incomplete, the argument is `None` for indefinite lengths and breaks.
+    fn header(&self, input: &[u8]) -> Result<core::result::Result<Header, usize>> {
+        let rest = &input[self.pos..];
+        let byte = match rest.first() {
+            Some(&byte) => byte,
+            None => return Ok(Err(1)),
+        };
+        let len = match byte & 0x1f {
+            0..=23 | 31 => 1,
+            24 => 2,
+            25 => 3,
+            26 => 5,
+            27 => 9,
+            _ => return Err(self.error(ErrorCode::UnassignedCode)),
+        };
+        if rest.len() < len {
+            return Ok(Err(len - rest.len()));
+        }
+        let argument = match byte & 0x1f {
+            info @ 0..=23 => Some(u64::from(info)),
+            24 => Some(u64::from(rest[1])),
+            25 => Some(u64::from(BigEndian::read_u16(&rest[1..]))),
+            26 => Some(u64::from(BigEndian::read_u32(&rest[1..]))),
+            27 => Some(BigEndian::read_u64(&rest[1..])),
+            _ => None,
+        };
+        Ok(Ok((byte, argument, len)))
+    }
+
+    // The header has not been counted yet, so `needed` is at least 1.
+    fn add_needed(&mut self, items: u64) -> Result<()> {
+        match self.needed.checked_add(items) {
+            Some(needed) => {
+                self.needed = needed;
+                Ok(())
+            }
+            None => Err(self.error(ErrorCode::LengthOutOfRange)),
+        }
+    }
+
+    fn error(&self, code: ErrorCode) -> Error {
+        Error::syntax(code, self.pos as u64)
+    }
+}
+
+// Deserializes a complete item with the given limits.
+pub(crate) fn from_slice<T>(item: &[u8], limits: DecodeLimits) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let mut deserializer = Deserializer::from_slice(item).limits(limits);
+    let value = T::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+fn need_more(n: u64) -> Progress<usize> {
+    if n > usize::max_value() as u64 {
+        Progress::NeedMore(usize::max_value())
+    } else {
+        Progress::NeedMore(n as usize)
+    }
+}
+
+/// Decodes items from data that is fed in arbitrary chunks.
+///
+/// Error offsets are relative to the start of all data fed to the decoder.
+#[derive(Clone, Debug, Default)]
+pub struct IncrementalDecoder {
+    buf: Vec<u8>,
+    // Offset of the first byte in `buf` that has not been decoded.
+    start: usize,
+    // The number of bytes removed from the front of `buf`.
+    removed: u64,
+    limits: DecodeLimits,
+    scanner: ItemScanner,
+}
+
+impl IncrementalDecoder {
+    /// Creates a decoder with an empty buffer.
+    pub fn new() -> Self {
+        IncrementalDecoder::default()
+    }
+
+    /// Limits the length of items in bytes, see
+    /// [`ItemScanner::max_item_len`](struct.ItemScanner.html#method.max_item_len).
+    ///
+    /// The buffer then only grows beyond the limit by the chunks that are fed before the next
+    /// call to `decode`.
+    pub fn max_item_len(mut self, len: usize) -> Self {
+        self.scanner = self.scanner.max_item_len(len);
+        self
+    }
+
+    /// Sets the limits for deserializing items. The scanner takes its depth limit from them.
+    pub fn limits(mut self, limits: DecodeLimits) -> Self {
+        self.scanner = self.scanner.max_depth(limits.max_depth);
+        self.limits = limits;
+        self
+    }
+
+    /// Appends a chunk of data to the buffer.
+    pub fn feed(&mut self, chunk: &[u8]) {
+        // Decoded items are only removed once they take up half of the buffer, so each byte
+        // is moved a constant number of times on average.
+        if self.start > 0 && self.start >= self.buf.len() / 2 {
+            self.buf.drain(..self.start);
+            self.removed += self.start as u64;
+            self.start = 0;
+        }
+        self.buf.extend_from_slice(chunk);
+    }
+
+    /// Returns the data that has been fed but not decoded yet.
+    pub fn buffered(&self) -> &[u8] {
+        &self.buf[self.start..]
+    }
+
+    /// Decodes the next item if it is complete.
+    ///
+    /// Otherwise returns the number of bytes that are needed at least, bytes that have been
+    /// scanned before are not scanned again. An item that is complete but can't be deserialized
+    /// into a `T` is removed from the buffer before the error is returned. After a syntax error
+    /// the decoder can't continue.
+    pub fn decode<T>(&mut self) -> Result<Progress<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let base = self.removed + self.start as u64;
+        let len = match self.scanner.scan(&self.buf[self.start..]) {
+            Ok(Progress::Complete(len)) => len,
+            Ok(Progress::NeedMore(n)) => return Ok(Progress::NeedMore(n)),
+            Err(err) => return Err(err.offset_by(base)),
+        };
+        let value = from_slice(&self.buf[self.start..self.start + len], self.limits);
+        self.start += len;
+        value
+            .map(Progress::Complete)
+            .map_err(|err| err.offset_by(base))
+    }
+}
@@ -71,6 +71,11 @@
 //! To serialize a document in this format use `Serializer::new(writer).packed_format()` or
 //! the shorthand `ser::to_vec_packed`. The deserialization works without any changes.
 //!
+//! # Deterministic Encoding
+//! Signed or hashed documents require that the same data is always encoded to the same bytes.
+//! With `Serializer::new(writer).canonical()` or the shorthand `to_vec_canonical` the entries of
+//! all maps and structs are sorted by their encoded keys and indefinite lengths are avoided.
+//!
 //! # Self describing documents
 //! In some contexts different formats are used but there is no way to declare the format used
 //! out of band. For this reason CBOR has a magic number that may be added before any document.
@@ -148,6 +153,15 @@
 //!
 //! [alloc-lib]: https://doc.rust-lang.org/alloc/
 //!
+//! # Async support
+//!
+//! The `async` feature adds the [`async_io`](async_io/index.html) module to read items from a
+//! `futures::io::AsyncRead` and write them to a `futures::io::AsyncWrite`. It requires at least
+//! version 1.39.0 of Rust.
+//!
+//! The `codec` feature adds the [`codec`](codec/index.html) module with a codec for framed
+//! transports of `tokio_util`.
+//!
 //! *Note*: to use derive macros in serde you will need to declare `serde`
 //! dependency like so:
 //! ``` toml
@@ -239,21 +253,25 @@
 //! While Serde CBOR strives to support all features of Serde and CBOR
 //! there are a few limitations.
 //!
-//! * [Tags] are ignored during deserialization and can't be emitted during
-//!     serialization. This is because Serde has no concept of tagged
-//!     values. See:&nbsp;[#3]
-//! * Unknown [simple values] cause an `UnassignedCode` error.
+//! * [Tags] are ignored during deserialization unless the target type asks for
+//!     them, because Serde has no concept of tagged values. Use the
+//!     [`Tagged`](tags/struct.Tagged.html) wrapper to emit and observe tags.
+//!     See:&nbsp;[#3]
+//! * Unknown [simple values] cause an `UnassignedCode` error unless they are
+//!     decoded into a `Value`.
 //!     The simple values *False* and *True* are recognized and parsed as bool.
 //!     *Null* and *Undefined* are both deserialized as *unit*.
 //!     The *unit* type is serialized as *Null*. See:&nbsp;[#86]
-//! * [128-bit integers] can't be directly encoded in CBOR. If you need them
-//!     store them as a byte string. See:&nbsp;[#77]
+//! * [128-bit integers] that don't fit in 64 bits are encoded as [bignums]
+//!     (tags 2 and 3). Bignums are only accepted when deserializing into
+//!     `u128`, `i128` or `Value`. See:&nbsp;[#77]
 //!
 //! [Tags]: https://tools.ietf.org/html/rfc7049#section-2.4.4
 //! [#3]: https://github.com/pyfisch/cbor/issues/3
 //! [simple values]: https://tools.ietf.org/html/rfc7049#section-3.5
 //! [#86]: https://github.com/pyfisch/cbor/issues/86
 //! [128-bit integers]: https://doc.rust-lang.org/std/primitive.u128.html
+//! [bignums]: https://tools.ietf.org/html/rfc7049#section-2.4.2
 //! [#77]: https://github.com/pyfisch/cbor/issues/77
 
 #![deny(missing_docs)]
@@ -267,10 +285,21 @@ extern crate std;
 #[cfg(feature = "alloc")]
 extern crate alloc;
 
+#[cfg(feature = "async")]
+pub mod async_io;
+#[cfg(feature = "codec")]
+pub mod codec;
 pub mod de;
+pub mod decoder;
+pub mod diag;
+pub mod encoder;
 pub mod error;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod incremental;
+pub mod raw;
 mod read;
 pub mod ser;
+pub mod tags;
 mod write;
 
 #[cfg(feature = "std")]
@@ -284,7 +313,7 @@ pub use crate::de::{Deserializer, StreamDeserializer};
 pub use crate::error::{Error, Result};
 
 #[doc(inline)]
-pub use crate::ser::Serializer;
+pub use crate::ser::{SequenceWriter, Serializer};
 
 // Convenience functions for serialization and deserialization.
 // These functions are only available in `std` mode.
@@ -298,7 +327,7 @@ pub use crate::de::from_slice;
 
 #[cfg(any(feature = "std", feature = "alloc"))]
 #[doc(inline)]
-pub use crate::ser::to_vec;
+pub use crate::ser::{to_vec, to_vec_canonical};
 
 #[cfg(feature = "std")]
 #[doc(inline)]
@@ -0,0 +1,219 @@
+//! Raw CBOR data items.
+//!
+//! [`RawCbor`](struct.RawCbor.html) and [`RawValue`](struct.RawValue.html) hold the encoding of
+//! a single data item without decoding it. They can be used to defer the decoding of a part of
+//! a message until its type is known, or to pass data through unchanged.
+//!
+//! When deserializing from a slice a `RawCbor` borrows the exact bytes of the item from the
+//! input. A `RawValue` owns a copy of them and can be deserialized from any input source. When
+//! serializing, `Serializer` writes the bytes as they are, even in canonical mode.
+//!
+//! The raw data is passed through serde as a newtype struct with a reserved name that contains
+//! a byte string. Other serializers see the byte string, other deserializers are asked for it.
+//!
+//! # Examples
+//!
+//! ```
+//! use serde_cbor::raw::RawCbor;
+//! use serde_derive::{Deserialize, Serialize};
+//!
+//! #[derive(Deserialize, Serialize)]
+//! struct Message<'a> {
+//!     kind: String,
+//!     #[serde(borrow)]
+//!     payload: RawCbor<'a>,
+//! }
+//!
+//! let bytes = serde_cbor::to_vec(&("point", (1, 2))).unwrap();
+//! let (kind, payload): (&str, RawCbor) = serde_cbor::from_slice(&bytes).unwrap();
+//! assert_eq!(payload.as_bytes(), [0x82, 0x01, 0x02]);
+//!
+//! // The payload is decoded once its type is known.
+//! if kind == "point" {
+//!     let point: (i32, i32) = serde_cbor::from_slice(payload.as_bytes()).unwrap();
+//!     assert_eq!(point, (1, 2));
+//! }
+//!
+//! // The payload is written unchanged.
+//! let message = Message { kind: kind.to_owned(), payload };
+//! let bytes = serde_cbor::to_vec(&message).unwrap();
+//! assert!(bytes.ends_with(&[0x67, 0x70, 0x61, 0x79, 0x6c, 0x6f, 0x61, 0x64, 0x82, 0x01, 0x02]));
+//! ```
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::fmt;
+use serde::de;
+use serde::ser;
+
+use crate::de::Deserializer;
+use crate::error::Result;
+
+/// Name of the newtype struct used to pass raw data items through serde.
+pub(crate) const RAW_NAME: &str = "\0cbor_raw";
+
+/// The encoding of a single CBOR data item, borrowed from the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RawCbor<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> RawCbor<'a> {
+    /// Wraps the encoding of a single data item.
+    ///
+    /// Fails if `bytes` is not exactly one well-formed data item.
+    pub fn from_slice(bytes: &'a [u8]) -> Result<RawCbor<'a>> {
+        check_item(bytes)?;
+        Ok(RawCbor { bytes })
+    }
+
+    /// Returns the encoded data item.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Copies the data item into a `RawValue`.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    pub fn to_raw_value(&self) -> RawValue {
+        RawValue {
+            bytes: self.bytes.to_vec(),
+        }
+    }
+}
+
+impl<'a> ser::Serialize for RawCbor<'a> {
+    fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        serializer.serialize_newtype_struct(RAW_NAME, &RawBytes(self.bytes))
+    }
+}
+
+impl<'de: 'a, 'a> de::Deserialize<'de> for RawCbor<'a> {
+    fn deserialize<D>(deserializer: D) -> core::result::Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        struct RawCborVisitor;
+
+        impl<'de> de::Visitor<'de> for RawCborVisitor {
+            type Value = RawCbor<'de>;
+
+            fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt.write_str("raw CBOR data borrowed from the input")
+            }
+
+            fn visit_borrowed_bytes<E>(
+                self,
+                bytes: &'de [u8],
+            ) -> core::result::Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(RawCbor { bytes })
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(RAW_NAME, RawCborVisitor)
+    }
+}
+
+/// The encoding of a single CBOR data item.
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RawValue {
+    bytes: Vec<u8>,
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl RawValue {
+    /// Wraps the encoding of a single data item.
+    ///
+    /// Fails if `bytes` is not exactly one well-formed data item.
+    pub fn from_vec(bytes: Vec<u8>) -> Result<RawValue> {
+        check_item(&bytes)?;
+        Ok(RawValue { bytes })
+    }
+
+    /// Returns the encoded data item.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Borrows the data item as a `RawCbor`.
+    pub fn as_raw_cbor(&self) -> RawCbor<'_> {
+        RawCbor { bytes: &self.bytes }
+    }
+
+    /// Unwraps the encoded data item.
+    pub fn into_vec(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl ser::Serialize for RawValue {
+    fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        serializer.serialize_newtype_struct(RAW_NAME, &RawBytes(&self.bytes))
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'de> de::Deserialize<'de> for RawValue {
+    fn deserialize<D>(deserializer: D) -> core::result::Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        struct RawValueVisitor;
+
+        impl<'de> de::Visitor<'de> for RawValueVisitor {
+            type Value = RawValue;
+
+            fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt.write_str("raw CBOR data")
+            }
+
+            fn visit_bytes<E>(self, bytes: &[u8]) -> core::result::Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(RawValue {
+                    bytes: bytes.to_vec(),
+                })
+            }
+
+            fn visit_byte_buf<E>(self, bytes: Vec<u8>) -> core::result::Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Ok(RawValue { bytes })
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(RAW_NAME, RawValueVisitor)
+    }
+}
+
+// Serializes the content of a raw data item as a byte string.
+struct RawBytes<'a>(&'a [u8]);
+
+impl<'a> ser::Serialize for RawBytes<'a> {
+    fn serialize<S>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+// Checks that `bytes` contains exactly one well-formed data item. The content of text strings is
+// not checked to be valid UTF-8.
+fn check_item(bytes: &[u8]) -> Result<()> {
+    let mut deserializer = Deserializer::from_slice_with_scratch(bytes, &mut []);
+    deserializer.skip_value()?;
+    deserializer.end()
+}
@@ -61,6 +61,16 @@ pub trait Read<'de>: private::Sealed {
 
     #[doc(hidden)]
     fn offset(&self) -> u64;
+
+    #[doc(hidden)]
+    fn slice_since(&self, _start: u64) -> Option<&'de [u8]> {
+        None
+    }
+
+    #[doc(hidden)]
+    fn skip(&mut self, n: usize) -> Result<()> {
+        self.read(n).map(|_| ())
+    }
 }
 
 #[cfg(feature = "unsealed_read_write")]
@@ -113,6 +123,18 @@ pub trait Read<'de> {
 
     /// Returns the offset from the start of the reader.
     fn offset(&self) -> u64;
+
+    /// Returns the input from the offset `start` up to the current position, if it is a slice that
+    /// outlives the reader. The provided implementation returns `None`.
+    fn slice_since(&self, _start: u64) -> Option<&'de [u8]> {
+        None
+    }
+
+    /// Advance the input by n bytes that are not needed. The provided implementation reads them
+    /// with `read`, which doesn't copy them for slices.
+    fn skip(&mut self, n: usize) -> Result<()> {
+        self.read(n).map(|_| ())
+    }
 }
 
 /// Represents a reader that can return its current position
@@ -121,6 +143,7 @@ pub trait Offset {
 }
 
 /// Represents a buffer with one of two lifetimes.
+#[derive(Debug)]
 pub enum EitherLifetime<'short, 'long> {
     /// The short lifetime
     Short(&'short [u8]),
@@ -253,6 +276,26 @@ where
         })
     }
 
+    fn skip(&mut self, mut n: usize) -> Result<()> {
+        if n == 0 {
+            return Ok(());
+        }
+
+        if self.ch.take().is_some() {
+            n -= 1;
+        }
+
+        let mut taken = self.reader.by_ref().take(n as u64);
+        match io::copy(&mut taken, &mut io::sink()) {
+            Ok(r) if r == n as u64 => Ok(()),
+            Ok(_) => Err(Error::syntax(
+                ErrorCode::EofWhileParsingValue,
+                self.offset(),
+            )),
+            Err(e) => Err(Error::io(e)),
+        }
+    }
+
     #[inline]
     fn discard(&mut self) {
         self.ch = None;
@@ -295,6 +338,140 @@ where
     }
 }
 
+/// CBOR input source that reads from a buffered std::io input stream.
+///
+/// Unlike [`IoRead`](struct.IoRead.html), which asks the reader for every header byte
+/// separately, this reads directly from the buffer of the reader. Bytes after the end of the
+/// data are left in the buffer, so the reader can be used for other data afterwards.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct BufIoRead<R>
+where
+    R: io::BufRead,
+{
+    reader: R,
+    scratch: Vec<u8>,
+    offset: u64,
+}
+
+#[cfg(feature = "std")]
+impl<R> BufIoRead<R>
+where
+    R: io::BufRead,
+{
+    /// Creates a new CBOR input source to read from a buffered std::io input stream.
+    pub fn new(reader: R) -> BufIoRead<R> {
+        BufIoRead {
+            reader,
+            scratch: vec![],
+            offset: 0,
+        }
+    }
+
+    #[inline]
+    fn consume(&mut self, n: usize) {
+        self.reader.consume(n);
+        self.offset += n as u64;
+    }
+
+    // Consumes the next n bytes, passing them to f in the chunks they are buffered in.
+    fn read_chunks<F>(&mut self, mut n: usize, mut f: F) -> Result<()>
+    where
+        F: FnMut(&mut Vec<u8>, &[u8]),
+    {
+        while n > 0 {
+            let len = {
+                let buf = match self.reader.fill_buf() {
+                    Ok(buf) => buf,
+                    Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(Error::io(e)),
+                };
+                if buf.is_empty() {
+                    return Err(Error::syntax(ErrorCode::EofWhileParsingValue, self.offset));
+                }
+                let len = cmp::min(n, buf.len());
+                f(&mut self.scratch, &buf[..len]);
+                len
+            };
+            self.consume(len);
+            n -= len;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(feature = "std", not(feature = "unsealed_read_write")))]
+impl<R> private::Sealed for BufIoRead<R> where R: io::BufRead {}
+
+#[cfg(feature = "std")]
+impl<'de, R> Read<'de> for BufIoRead<R>
+where
+    R: io::BufRead,
+{
+    #[inline]
+    fn next(&mut self) -> Result<Option<u8>> {
+        let ch = self.peek()?;
+        if ch.is_some() {
+            self.consume(1);
+        }
+        Ok(ch)
+    }
+
+    #[inline]
+    fn peek(&mut self) -> Result<Option<u8>> {
+        loop {
+            match self.reader.fill_buf() {
+                Ok(buf) => return Ok(buf.first().cloned()),
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(Error::io(e)),
+            }
+        }
+    }
+
+    fn read_to_buffer(&mut self, n: usize) -> Result<()> {
+        self.read_chunks(n, |scratch, chunk| scratch.extend_from_slice(chunk))
+    }
+
+    fn clear_buffer(&mut self) {
+        self.scratch.clear();
+    }
+
+    fn take_buffer<'a>(&'a mut self) -> EitherLifetime<'a, 'de> {
+        EitherLifetime::Short(&self.scratch)
+    }
+
+    fn read_into(&mut self, buf: &mut [u8]) -> Result<()> {
+        let mut index = 0;
+        self.read_chunks(buf.len(), |_, chunk| {
+            buf[index..index + chunk.len()].copy_from_slice(chunk);
+            index += chunk.len();
+        })
+    }
+
+    fn skip(&mut self, n: usize) -> Result<()> {
+        self.read_chunks(n, |_, _| {})
+    }
+
+    #[inline]
+    fn discard(&mut self) {
+        self.consume(1);
+    }
+
+    fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R> Offset for BufIoRead<R>
+where
+    R: io::BufRead,
+{
+    fn byte_offset(&self) -> usize {
+        self.offset as usize
+    }
+}
+
 /// A CBOR input source that reads from a slice of bytes.
 #[cfg(any(feature = "std", feature = "alloc"))]
 #[derive(Debug)]
@@ -403,6 +580,10 @@ impl<'a> Read<'a> for SliceRead<'a> {
     fn offset(&self) -> u64 {
         self.index as u64
     }
+
+    fn slice_since(&self, start: u64) -> Option<&'a [u8]> {
+        self.slice.get(start as usize..self.index)
+    }
 }
 
 /// A CBOR input source that reads from a slice of bytes using a fixed size scratch buffer.
@@ -512,6 +693,10 @@ impl<'a, 'b> Read<'a> for SliceReadFixed<'a, 'b> {
     fn offset(&self) -> u64 {
         self.index as u64
     }
+
+    fn slice_since(&self, start: u64) -> Option<&'a [u8]> {
+        self.slice.get(start as usize..self.index)
+    }
 }
 
 #[cfg(any(feature = "std", feature = "alloc"))]
@@ -7,12 +7,22 @@ use alloc::vec::Vec;
 pub use crate::write::IoWrite;
 pub use crate::write::{SliceWrite, Write};
 
+use crate::de::PathSegment;
 use crate::error::{Error, Result};
+use crate::raw::RAW_NAME;
+use crate::tags::{bignum_bytes, NEGATIVE_BIGNUM, POSITIVE_BIGNUM, SIMPLE_NAME, TAG_NAME};
+#[cfg(feature = "std")]
+use crate::value::Value;
 use byteorder::{BigEndian, ByteOrder};
+use core::cmp::Ordering;
 use half::f16;
 use serde::ser::{self, Serialize};
 #[cfg(feature = "std")]
+use std::collections::btree_map;
+#[cfg(feature = "std")]
 use std::io;
+#[cfg(feature = "std")]
+use std::{slice, vec};
 
 /// Serializes a value to a vector.
 #[cfg(any(feature = "std", feature = "alloc"))]
@@ -36,6 +46,19 @@ where
     Ok(vec)
 }
 
+/// Serializes a value to a vector using deterministic encoding.
+///
+/// See [`Serializer::canonical`](struct.Serializer.html#method.canonical) for details.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn to_vec_canonical<T>(value: &T) -> Result<Vec<u8>>
+where
+    T: ser::Serialize,
+{
+    let mut vec = Vec::new();
+    value.serialize(&mut Serializer::new(&mut vec).canonical())?;
+    Ok(vec)
+}
+
 /// Serializes a value to a writer.
 #[cfg(feature = "std")]
 pub fn to_writer<W, T>(writer: W, value: &T) -> Result<()>
@@ -46,12 +69,59 @@ where
     value.serialize(&mut Serializer::new(&mut IoWrite::new(writer)))
 }
 
+/// The order of map keys in deterministic encoding.
+///
+/// Keys are compared by their encoded bytes. The `Ord` implementation of `Value` sorts map keys
+/// in the same order as `KeyOrder::Bytewise`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyOrder {
+    /// Bytewise lexicographic order of the encoded keys, as described in
+    /// [RFC 8949 section 4.2.1](https://tools.ietf.org/html/rfc8949#section-4.2.1).
+    ///
+    /// This is the default.
+    Bytewise,
+    /// Shorter encoded keys sort first, keys of the same length are compared bytewise.
+    ///
+    /// This is the canonical order of RFC 7049, described in
+    /// [RFC 8949 section 4.2.3](https://tools.ietf.org/html/rfc8949#section-4.2.3). It is
+    /// required by CTAP2.
+    LengthFirst,
+}
+
+impl KeyOrder {
+    /// Compares two encoded keys.
+    pub fn compare(self, a: &[u8], b: &[u8]) -> Ordering {
+        match self {
+            KeyOrder::Bytewise => a.cmp(b),
+            KeyOrder::LengthFirst => a.len().cmp(&b.len()).then_with(|| a.cmp(b)),
+        }
+    }
+}
+
+#[allow(clippy::derivable_impls)] // `#[default]` requires a newer compiler.
+impl Default for KeyOrder {
+    fn default() -> Self {
+        KeyOrder::Bytewise
+    }
+}
+
 /// A structure for serializing Rust values to CBOR.
 #[derive(Debug)]
 pub struct Serializer<W> {
     writer: W,
+    // The number of bytes written, reported in errors.
+    offset: u64,
     packed: bool,
     enum_as_map: bool,
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    canonical: bool,
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    key_order: KeyOrder,
+    // Set while serializing tags and simple values, the next `u8` or `u64` is written with this
+    // major type instead of as an unsigned integer.
+    pending_major: Option<u8>,
+    // Set while serializing a raw data item, the next byte string is written without a header.
+    pending_raw: bool,
 }
 
 impl<W> Serializer<W>
@@ -65,8 +135,15 @@ where
     pub fn new(writer: W) -> Self {
         Serializer {
             writer,
+            offset: 0,
             packed: false,
             enum_as_map: true,
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            canonical: false,
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            key_order: KeyOrder::Bytewise,
+            pending_major: None,
+            pending_raw: false,
         }
     }
 
@@ -119,6 +196,55 @@ where
         self
     }
 
+    /// Enable deterministic encoding.
+    ///
+    /// The same data always produces identical bytes, as described by the core deterministic
+    /// encoding requirements in [RFC 8949 section 4.2.1]. This is useful when CBOR documents
+    /// are signed or hashed.
+    ///
+    /// * The entries of maps and structs are buffered and sorted by their encoded keys, in
+    ///   bytewise lexicographic order unless changed with `key_order`. Duplicate keys cause an
+    ///   error.
+    /// * Sequences of unknown length cause an error instead of being encoded with an
+    ///   indefinite length. Maps of unknown length are counted while buffering.
+    /// * Integers, lengths and tags are always encoded in their shortest form and floats use
+    ///   the shortest precision that preserves their value, as they are without this option.
+    ///
+    /// [RFC 8949 section 4.2.1]: https://tools.ietf.org/html/rfc8949#section-4.2.1
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    pub fn canonical(mut self) -> Self {
+        self.canonical = true;
+        self
+    }
+
+    /// Choose the order of map keys used by deterministic encoding.
+    ///
+    /// This has no effect unless `canonical` is enabled as well.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use serde::Serialize;
+    /// use serde_cbor::ser::{KeyOrder, Serializer};
+    /// use std::collections::BTreeMap;
+    ///
+    /// let mut map = BTreeMap::new();
+    /// map.insert(100, ());
+    /// map.insert(-1, ());
+    ///
+    /// let mut bytes = Vec::new();
+    /// let mut serializer = Serializer::new(&mut bytes)
+    ///     .canonical()
+    ///     .key_order(KeyOrder::LengthFirst);
+    /// map.serialize(&mut serializer).unwrap();
+    /// assert_eq!(bytes, [0xa2, 0x20, 0xf6, 0x18, 0x64, 0xf6]);
+    /// ```
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    pub fn key_order(mut self, order: KeyOrder) -> Self {
+        self.key_order = order;
+        self
+    }
+
     /// Writes a CBOR self-describe tag to the stream.
     ///
     /// Tagging allows a decoder to distinguish different file formats based on their content
@@ -127,7 +253,83 @@ where
     pub fn self_describe(&mut self) -> Result<()> {
         let mut buf = [6 << 5 | 25, 0, 0];
         BigEndian::write_u16(&mut buf[1..], 55799);
-        self.writer.write_all(&buf).map_err(|e| e.into())
+        self.write_raw(&buf)
+    }
+
+    /// Serializes a `Value` without recursion.
+    ///
+    /// Arrays, maps and tags are kept on a stack on the heap, so deeply nested values don't
+    /// overflow the call stack. The output is the same as serializing the value with serde.
+    #[cfg(feature = "std")]
+    pub fn write_value(&mut self, value: &Value) -> Result<()> {
+        let mut stack = Vec::new();
+        let mut next = Some(value);
+        loop {
+            if let Some(value) = next.take() {
+                match *value {
+                    Value::Array(ref items) => {
+                        self.write_u64(4, items.len() as u64)?;
+                        stack.push(ValueFrame::Array(items.iter()));
+                    }
+                    Value::Map(ref entries) => {
+                        let frame = self.write_value_map(entries)?;
+                        stack.push(frame);
+                    }
+                    Value::Tag(tag, ref inner) => {
+                        self.write_u64(6, tag)?;
+                        next = Some(inner);
+                        continue;
+                    }
+                    ref value => value
+                        .serialize(&mut *self)
+                        .map_err(|err| err.at_offset(self.offset))?,
+                }
+            }
+            next = match stack.last_mut() {
+                Some(ValueFrame::Array(ref mut items)) => items.next(),
+                Some(ValueFrame::Map(ref mut entries, ref mut pending)) => {
+                    pending.take().or_else(|| {
+                        entries.next().map(|(key, value)| {
+                            *pending = Some(value);
+                            key
+                        })
+                    })
+                }
+                Some(ValueFrame::Sorted(ref mut entries)) => match entries.next() {
+                    Some((key, value)) => {
+                        self.write_raw(&key)?;
+                        Some(value)
+                    }
+                    None => None,
+                },
+                None => return Ok(()),
+            };
+            if next.is_none() {
+                stack.pop();
+            }
+        }
+    }
+
+    // Writes the header of a map for `write_value`. In canonical mode the keys are encoded and
+    // sorted first.
+    #[cfg(feature = "std")]
+    fn write_value_map<'a>(
+        &mut self,
+        entries: &'a btree_map::BTreeMap<Value, Value>,
+    ) -> Result<ValueFrame<'a>> {
+        if self.canonical {
+            let mut sorted = Vec::with_capacity(entries.len());
+            for (key, value) in entries {
+                let mut ser = self.nested();
+                ser.write_value(key)?;
+                sorted.push((ser.writer, value));
+            }
+            self.sort_entries(&mut sorted)?;
+            self.write_u64(5, sorted.len() as u64)?;
+            return Ok(ValueFrame::Sorted(sorted.into_iter()));
+        }
+        self.write_u64(5, entries.len() as u64)?;
+        Ok(ValueFrame::Map(entries.iter(), None))
     }
 
     /// Unwrap the `Writer` from the `Serializer`.
@@ -137,14 +339,13 @@ where
     }
 
     #[inline]
-    fn write_u8(&mut self, major: u8, value: u8) -> Result<()> {
+    pub(crate) fn write_u8(&mut self, major: u8, value: u8) -> Result<()> {
         if value <= 0x17 {
-            self.writer.write_all(&[major << 5 | value])
+            self.write_raw(&[major << 5 | value])
         } else {
             let buf = [major << 5 | 24, value];
-            self.writer.write_all(&buf)
+            self.write_raw(&buf)
         }
-        .map_err(|e| e.into())
     }
 
     #[inline]
@@ -154,7 +355,7 @@ where
         } else {
             let mut buf = [major << 5 | 25, 0, 0];
             BigEndian::write_u16(&mut buf[1..], value);
-            self.writer.write_all(&buf).map_err(|e| e.into())
+            self.write_raw(&buf)
         }
     }
 
@@ -165,36 +366,161 @@ where
         } else {
             let mut buf = [major << 5 | 26, 0, 0, 0, 0];
             BigEndian::write_u32(&mut buf[1..], value);
-            self.writer.write_all(&buf).map_err(|e| e.into())
+            self.write_raw(&buf)
         }
     }
 
     #[inline]
-    fn write_u64(&mut self, major: u8, value: u64) -> Result<()> {
+    pub(crate) fn write_u64(&mut self, major: u8, value: u64) -> Result<()> {
         if value <= u64::from(u32::max_value()) {
             self.write_u32(major, value as u32)
         } else {
             let mut buf = [major << 5 | 27, 0, 0, 0, 0, 0, 0, 0, 0];
             BigEndian::write_u64(&mut buf[1..], value);
-            self.writer.write_all(&buf).map_err(|e| e.into())
+            self.write_raw(&buf)
         }
     }
 
+    // Writes a header with an argument of 1, 2, 4 or 8 bytes for a `width` of 0 to 3, even if a
+    // shorter encoding exists. The value must fit in the argument.
+    #[inline]
+    pub(crate) fn write_sized(&mut self, major: u8, value: u64, width: u8) -> Result<()> {
+        let len = 1 << width;
+        let mut buf = [major << 5 | (24 + width), 0, 0, 0, 0, 0, 0, 0, 0];
+        BigEndian::write_uint(&mut buf[1..], value, len);
+        self.write_raw(&buf[..1 + len])
+    }
+
+    #[inline]
+    pub(crate) fn write_bignum(&mut self, tag: u64, value: u128) -> Result<()> {
+        let mut buf = [0; 16];
+        let bytes = bignum_bytes(value, &mut buf);
+        self.write_u64(6, tag)?;
+        self.write_u64(2, bytes.len() as u64)?;
+        self.write_raw(bytes)
+    }
+
+    #[inline]
+    pub(crate) fn write_raw(&mut self, bytes: &[u8]) -> Result<()> {
+        if let Err(err) = self.writer.write_all(bytes) {
+            let err: Error = err.into();
+            return Err(err.at_offset(self.offset));
+        }
+        self.offset += bytes.len() as u64;
+        Ok(())
+    }
+
+    // Adds the position and the location of an item to an error from serializing it.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn within(&self, err: Error, segment: PathSegment<'_>) -> Error {
+        err.at_offset(self.offset).within(segment)
+    }
+
+    #[cfg(not(any(feature = "std", feature = "alloc")))]
+    fn within(&self, err: Error, _segment: PathSegment<'_>) -> Error {
+        err.at_offset(self.offset)
+    }
+
+    // Adds the key of a map entry to an error from serializing its value.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn within_key<K>(&self, err: Error, key: &K) -> Error
+    where
+        K: ?Sized + ser::Serialize,
+    {
+        let key = self.serialize_to_vec(key).unwrap_or_default();
+        self.within(err, PathSegment::Key(&key))
+    }
+
+    #[cfg(not(any(feature = "std", feature = "alloc")))]
+    fn within_key<K>(&self, err: Error, _key: &K) -> Error
+    where
+        K: ?Sized + ser::Serialize,
+    {
+        err.at_offset(self.offset)
+    }
+
+    // Creates a serializer into a new buffer with the options of this serializer.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn nested(&self) -> Serializer<Vec<u8>> {
+        // Buffered entries are reordered later, so errors in them are reported at the
+        // current position plus their offset within the entry.
+        Serializer {
+            writer: Vec::new(),
+            offset: self.offset,
+            packed: self.packed,
+            enum_as_map: self.enum_as_map,
+            canonical: self.canonical,
+            key_order: self.key_order,
+            pending_major: None,
+            pending_raw: false,
+        }
+    }
+
+    // Serializes a map key or value into a new buffer, using the options of this serializer.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn serialize_to_vec<T>(&self, value: &T) -> Result<Vec<u8>>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        let mut ser = self.nested();
+        value.serialize(&mut ser)?;
+        Ok(ser.writer)
+    }
+
+    // Sorts map entries by their encoded keys, which must be unique.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn sort_entries<T>(&self, entries: &mut [(Vec<u8>, T)]) -> Result<()> {
+        let order = self.key_order;
+        entries.sort_by(|a, b| order.compare(&a.0, &b.0));
+        if let Some(pair) = entries.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            let err = Error::message("duplicate map key in canonical mode");
+            return Err(self.within(err, PathSegment::Key(&pair[0].0)));
+        }
+        Ok(())
+    }
+
+    // Writes buffered map entries sorted by their keys.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn write_map_entries(&mut self, mut entries: MapEntries) -> Result<()> {
+        self.sort_entries(&mut entries)?;
+        self.write_u64(5, entries.len() as u64)?;
+        for (key, value) in entries {
+            self.write_raw(&key)?;
+            self.write_raw(&value)?;
+        }
+        Ok(())
+    }
+
     #[inline]
     fn serialize_collection<'a>(
         &'a mut self,
         major: u8,
         len: Option<usize>,
     ) -> Result<CollectionSerializer<'a, W>> {
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        {
+            if self.canonical && major == 5 {
+                return Ok(CollectionSerializer {
+                    ser: self,
+                    needs_eof: false,
+                    index: 0,
+                    entries: Some(Vec::new()),
+                });
+            }
+            if self.canonical && len.is_none() {
+                let err = Error::message(
+                    "sequences of unknown length can't be encoded in canonical mode",
+                );
+                return Err(err.at_offset(self.offset));
+            }
+        }
         let needs_eof = match len {
             Some(len) => {
                 self.write_u64(major, len as u64)?;
                 false
             }
             None => {
-                self.writer
-                    .write_all(&[major << 5 | 31])
-                    .map_err(|e| e.into())?;
+                self.write_raw(&[major << 5 | 31])?;
                 true
             }
         };
@@ -202,10 +528,94 @@ where
         Ok(CollectionSerializer {
             ser: self,
             needs_eof,
+            index: 0,
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            entries: None,
         })
     }
 }
 
+// An array or map whose content is written by `write_value`. `Map` holds the value of the last
+// key, `Sorted` holds the encoded keys of a map in canonical mode.
+#[cfg(feature = "std")]
+enum ValueFrame<'a> {
+    Array(slice::Iter<'a, Value>),
+    Map(btree_map::Iter<'a, Value, Value>, Option<&'a Value>),
+    Sorted(vec::IntoIter<(Vec<u8>, &'a Value)>),
+}
+
+/// Writes a CBOR sequence, data items that follow each other without any framing.
+///
+/// CBOR sequences are defined in [RFC 8742](https://tools.ietf.org/html/rfc8742) and use the
+/// media type `application/cbor-seq`. They can be read item by item with a
+/// [`StreamDeserializer`](../de/struct.StreamDeserializer.html). As no header or length has to
+/// be updated, items can be appended to a log file at any time.
+///
+/// # Examples
+///
+/// ```
+/// use serde_cbor::ser::SequenceWriter;
+/// use serde_cbor::Deserializer;
+///
+/// let mut writer = SequenceWriter::new(Vec::new());
+/// writer.write(&"first").unwrap();
+/// writer.write(&[1, 2]).unwrap();
+/// let bytes = writer.into_inner();
+/// assert_eq!(bytes, b"\x65first\x82\x01\x02");
+///
+/// let mut items = Deserializer::from_slice(&bytes).into_iter::<serde_cbor::Value>();
+/// assert_eq!(items.next().unwrap().unwrap(), serde_cbor::Value::Text("first".to_owned()));
+/// ```
+///
+/// # Asynchronous output
+///
+/// [`AsyncWriter`](../async_io/struct.AsyncWriter.html) writes a sequence to a
+/// `futures::io::AsyncWrite`. It requires the `async` feature.
+#[derive(Debug)]
+pub struct SequenceWriter<W> {
+    ser: Serializer<W>,
+}
+
+impl<W> SequenceWriter<W>
+where
+    W: Write,
+{
+    /// Creates a sequence writer that serializes items with the default options.
+    pub fn new(writer: W) -> Self {
+        SequenceWriter::from_serializer(Serializer::new(writer))
+    }
+
+    /// Creates a sequence writer that serializes items with the options of `ser`, for example
+    /// in packed format or with deterministic encoding.
+    pub fn from_serializer(ser: Serializer<W>) -> Self {
+        SequenceWriter { ser }
+    }
+
+    /// Serializes a value and appends it to the sequence.
+    pub fn write<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        value.serialize(&mut self.ser)
+    }
+
+    /// Returns a reference to the writer of the sequence.
+    pub fn get_ref(&self) -> &W {
+        &self.ser.writer
+    }
+
+    /// Returns a mutable reference to the writer of the sequence, for example to take the
+    /// bytes written so far out of a buffer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.ser.writer
+    }
+
+    /// Unwraps the writer of the sequence.
+    pub fn into_inner(self) -> W {
+        self.ser.into_inner()
+    }
+}
+
 impl<'a, W> ser::Serializer for &'a mut Serializer<W>
 where
     W: Write,
@@ -224,7 +634,7 @@ where
     #[inline]
     fn serialize_bool(self, value: bool) -> Result<()> {
         let value = if value { 0xf5 } else { 0xf4 };
-        self.writer.write_all(&[value]).map_err(|e| e.into())
+        self.write_raw(&[value])
     }
 
     #[inline]
@@ -267,20 +677,18 @@ where
     fn serialize_i128(self, value: i128) -> Result<()> {
         if value < 0 {
             if -(value + 1) > i128::from(u64::max_value()) {
-                return Err(Error::message("The number can't be stored in CBOR"));
+                return self.write_bignum(NEGATIVE_BIGNUM, -(value + 1) as u128);
             }
             self.write_u64(1, -(value + 1) as u64)
         } else {
-            if value > i128::from(u64::max_value()) {
-                return Err(Error::message("The number can't be stored in CBOR"));
-            }
-            self.write_u64(0, value as u64)
+            self.serialize_u128(value as u128)
         }
     }
 
     #[inline]
     fn serialize_u8(self, value: u8) -> Result<()> {
-        self.write_u8(0, value)
+        let major = self.pending_major.take().unwrap_or(0);
+        self.write_u8(major, value)
     }
 
     #[inline]
@@ -295,13 +703,14 @@ where
 
     #[inline]
     fn serialize_u64(self, value: u64) -> Result<()> {
-        self.write_u64(0, value)
+        let major = self.pending_major.take().unwrap_or(0);
+        self.write_u64(major, value)
     }
 
     #[inline]
     fn serialize_u128(self, value: u128) -> Result<()> {
         if value > u128::from(u64::max_value()) {
-            return Err(Error::message("The number can't be stored in CBOR"));
+            return self.write_bignum(POSITIVE_BIGNUM, value);
         }
         self.write_u64(0, value as u64)
     }
@@ -311,22 +720,21 @@ where
     fn serialize_f32(self, value: f32) -> Result<()> {
         if value.is_infinite() {
             if value.is_sign_positive() {
-                self.writer.write_all(&[0xf9, 0x7c, 0x00])
+                self.write_raw(&[0xf9, 0x7c, 0x00])
             } else {
-                self.writer.write_all(&[0xf9, 0xfc, 0x00])
+                self.write_raw(&[0xf9, 0xfc, 0x00])
             }
         } else if value.is_nan() {
-            self.writer.write_all(&[0xf9, 0x7e, 0x00])
+            self.write_raw(&[0xf9, 0x7e, 0x00])
         } else if f32::from(f16::from_f32(value)) == value {
             let mut buf = [0xf9, 0, 0];
             BigEndian::write_u16(&mut buf[1..], f16::from_f32(value).to_bits());
-            self.writer.write_all(&buf)
+            self.write_raw(&buf)
         } else {
             let mut buf = [0xfa, 0, 0, 0, 0];
             BigEndian::write_f32(&mut buf[1..], value);
-            self.writer.write_all(&buf)
+            self.write_raw(&buf)
         }
-        .map_err(|e| e.into())
     }
 
     #[inline]
@@ -337,7 +745,7 @@ where
         } else {
             let mut buf = [0xfb, 0, 0, 0, 0, 0, 0, 0, 0];
             BigEndian::write_f64(&mut buf[1..], value);
-            self.writer.write_all(&buf).map_err(|e| e.into())
+            self.write_raw(&buf)
         }
     }
 
@@ -351,15 +759,17 @@ where
     #[inline]
     fn serialize_str(self, value: &str) -> Result<()> {
         self.write_u64(3, value.len() as u64)?;
-        self.writer
-            .write_all(value.as_bytes())
-            .map_err(|e| e.into())
+        self.write_raw(value.as_bytes())
     }
 
     #[inline]
     fn serialize_bytes(self, value: &[u8]) -> Result<()> {
+        if self.pending_raw {
+            self.pending_raw = false;
+            return self.write_raw(value);
+        }
         self.write_u64(2, value.len() as u64)?;
-        self.writer.write_all(value).map_err(|e| e.into())
+        self.write_raw(value)
     }
 
     #[inline]
@@ -377,7 +787,7 @@ where
 
     #[inline]
     fn serialize_none(self) -> Result<()> {
-        self.writer.write_all(&[0xf6]).map_err(|e| e.into())
+        self.write_raw(&[0xf6])
     }
 
     #[inline]
@@ -400,10 +810,15 @@ where
     }
 
     #[inline]
-    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<()>
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<()>
     where
         T: ?Sized + ser::Serialize,
     {
+        if name == SIMPLE_NAME {
+            self.pending_major = Some(7);
+        } else if name == RAW_NAME {
+            self.pending_raw = true;
+        }
         value.serialize(self)
     }
 
@@ -422,7 +837,7 @@ where
             self.write_u64(5, 1u64)?;
             variant.serialize(&mut *self)?;
         } else {
-            self.writer.write_all(&[4 << 5 | 2]).map_err(|e| e.into())?;
+            self.write_raw(&[4 << 5 | 2])?;
             self.serialize_unit_variant(name, variant_index, variant)?;
         }
         value.serialize(self)
@@ -442,9 +857,14 @@ where
     #[inline]
     fn serialize_tuple_struct(
         self,
-        _name: &'static str,
+        name: &'static str,
         len: usize,
     ) -> Result<&'a mut Serializer<W>> {
+        if name == TAG_NAME && len == 2 {
+            // The tag number and the value are written by the fields.
+            self.pending_major = Some(6);
+            return Ok(self);
+        }
         self.serialize_tuple(len)
     }
 
@@ -487,8 +907,23 @@ where
 
     #[inline]
     fn serialize_struct(self, _name: &'static str, len: usize) -> Result<StructSerializer<'a, W>> {
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        {
+            if self.canonical {
+                return Ok(StructSerializer {
+                    ser: self,
+                    idx: 0,
+                    entries: Some(Vec::new()),
+                });
+            }
+        }
         self.write_u64(5, len as u64)?;
-        Ok(StructSerializer { ser: self, idx: 0 })
+        Ok(StructSerializer {
+            ser: self,
+            idx: 0,
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            entries: None,
+        })
     }
 
     #[inline]
@@ -502,7 +937,7 @@ where
         if self.enum_as_map {
             self.write_u64(5, 1u64)?;
         } else {
-            self.writer.write_all(&[4 << 5 | 2]).map_err(|e| e.into())?;
+            self.write_raw(&[4 << 5 | 2])?;
         }
         self.serialize_unit_variant(name, variant_index, variant)?;
         self.serialize_struct(name, len)
@@ -577,10 +1012,16 @@ where
     }
 }
 
+// Encoded keys and values of a map, buffered in canonical mode.
+#[cfg(any(feature = "std", feature = "alloc"))]
+type MapEntries = Vec<(Vec<u8>, Vec<u8>)>;
+
 #[doc(hidden)]
 pub struct StructSerializer<'a, W> {
     ser: &'a mut Serializer<W>,
     idx: u32,
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    entries: Option<MapEntries>,
 }
 
 impl<'a, W> StructSerializer<'a, W>
@@ -592,6 +1033,27 @@ where
     where
         T: ?Sized + ser::Serialize,
     {
+        self.write_field(key, value)
+            .map_err(|err| self.ser.within(err, PathSegment::Field(key)))
+    }
+
+    fn write_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + ser::Serialize,
+    {
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        {
+            if let Some(ref mut entries) = self.entries {
+                let key = if self.ser.packed {
+                    self.ser.serialize_to_vec(&self.idx)?
+                } else {
+                    self.ser.serialize_to_vec(key)?
+                };
+                entries.push((key, self.ser.serialize_to_vec(value)?));
+                self.idx += 1;
+                return Ok(());
+            }
+        }
         if self.ser.packed {
             self.idx.serialize(&mut *self.ser)?;
         } else {
@@ -610,6 +1072,12 @@ where
 
     #[inline]
     fn end_inner(self) -> Result<()> {
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        {
+            if let Some(entries) = self.entries {
+                return self.ser.write_map_entries(entries);
+            }
+        }
         Ok(())
     }
 }
@@ -670,6 +1138,9 @@ where
 pub struct CollectionSerializer<'a, W> {
     ser: &'a mut Serializer<W>,
     needs_eof: bool,
+    index: u64,
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    entries: Option<MapEntries>,
 }
 
 impl<'a, W> CollectionSerializer<'a, W>
@@ -678,8 +1149,14 @@ where
 {
     #[inline]
     fn end_inner(self) -> Result<()> {
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        {
+            if let Some(entries) = self.entries {
+                return self.ser.write_map_entries(entries);
+            }
+        }
         if self.needs_eof {
-            self.ser.writer.write_all(&[0xff]).map_err(|e| e.into())
+            self.ser.write_raw(&[0xff])
         } else {
             Ok(())
         }
@@ -698,7 +1175,11 @@ where
     where
         T: ?Sized + ser::Serialize,
     {
-        value.serialize(&mut *self.ser)
+        let index = self.index;
+        self.index += 1;
+        value
+            .serialize(&mut *self.ser)
+            .map_err(|err| self.ser.within(err, PathSegment::Index(index)))
     }
 
     #[inline]
@@ -719,6 +1200,13 @@ where
     where
         T: ?Sized + ser::Serialize,
     {
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        {
+            if let Some(ref mut entries) = self.entries {
+                entries.push((self.ser.serialize_to_vec(key)?, Vec::new()));
+                return Ok(());
+            }
+        }
         key.serialize(&mut *self.ser)
     }
 
@@ -727,9 +1215,28 @@ where
     where
         T: ?Sized + ser::Serialize,
     {
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        {
+            if let Some(ref mut entries) = self.entries {
+                let encoded = self.ser.serialize_to_vec(value)?;
+                if let Some(entry) = entries.last_mut() {
+                    entry.1 = encoded;
+                }
+                return Ok(());
+            }
+        }
         value.serialize(&mut *self.ser)
     }
 
+    fn serialize_entry<K, V>(&mut self, key: &K, value: &V) -> Result<()>
+    where
+        K: ?Sized + ser::Serialize,
+        V: ?Sized + ser::Serialize,
+    {
+        ser::SerializeMap::serialize_key(self, key)?;
+        ser::SerializeMap::serialize_value(self, value).map_err(|err| self.ser.within_key(err, key))
+    }
+
     #[inline]
     fn end(self) -> Result<()> {
         self.end_inner()
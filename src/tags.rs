@@ -0,0 +1,378 @@
+//! Support for CBOR semantic tags.
+//!
+//! Serde has no notion of tagged values, so tags are passed through the data model using a
+//! reserved type name:
+//!
+//! * A tagged value is serialized as a tuple struct named `TAG_NAME` with two fields, the tag
+//!   number as `u64` followed by the value. `Serializer` writes this as `tag(n, value)`; formats
+//!   that don't know the name see a two element array.
+//! * A simple value without a Rust equivalent is serialized as a newtype struct named
+//!   `SIMPLE_NAME` containing the value as `u8`.
+//! * A type that wants to observe tags deserializes itself as a newtype struct named `TAG_NAME`.
+//!   If the next item is tagged or an unassigned simple value `Deserializer` calls `visit_enum`.
+//!   The variant is an `Option<u64>`, the tag number for tags and `None` for simple values. The
+//!   newtype variant content is the tagged value or the simple value as `u8`. All other items are
+//!   passed to `visit_newtype_struct`.
+//!
+//! Everywhere else tags are skipped during deserialization, like in previous versions. The
+//! exception are bignums (tags 2 and 3) which are decoded when a `u128` or `i128` is expected.
+
+use core::fmt;
+use core::marker::PhantomData;
+use serde::de::{self, IntoDeserializer};
+use serde::ser::{self, SerializeTupleStruct};
+
+/// Name of the newtype and tuple struct used to pass tags through serde.
+pub(crate) const TAG_NAME: &str = "\0cbor_tag";
+
+/// Name of the newtype struct used to pass simple values through serde.
+pub(crate) const SIMPLE_NAME: &str = "\0cbor_simple";
+
+/// Tag number of an unsigned bignum.
+pub(crate) const POSITIVE_BIGNUM: u64 = 2;
+
+/// Tag number of a negative bignum.
+pub(crate) const NEGATIVE_BIGNUM: u64 = 3;
+
+/// Writes `value` in big-endian order to `buf` and returns the bytes without leading zeros.
+pub(crate) fn bignum_bytes(value: u128, buf: &mut [u8; 16]) -> &[u8] {
+    for (i, byte) in buf.iter_mut().enumerate() {
+        *byte = (value >> (8 * (15 - i))) as u8;
+    }
+    let zeros = buf.iter().take_while(|&&b| b == 0).count();
+    &buf[zeros..]
+}
+
+/// Reads the content of a bignum, `None` if it doesn't fit in a `u128`.
+pub(crate) fn bignum_value(bytes: &[u8]) -> Option<u128> {
+    let zeros = bytes.iter().take_while(|&&b| b == 0).count();
+    let bytes = &bytes[zeros..];
+    if bytes.len() > 16 {
+        return None;
+    }
+    Some(bytes.iter().fold(0, |acc, &b| acc << 8 | u128::from(b)))
+}
+
+/// Passes the content of a bignum to the visitor as an `u128` or `i128`.
+pub(crate) fn visit_bignum<'de, V, E>(
+    negative: bool,
+    bytes: &[u8],
+    visitor: V,
+) -> Result<V::Value, E>
+where
+    V: de::Visitor<'de>,
+    E: de::Error,
+{
+    let value = match bignum_value(bytes) {
+        Some(value) => value,
+        None => {
+            return Err(de::Error::invalid_value(
+                de::Unexpected::Bytes(bytes),
+                &"a bignum of at most 128 bits",
+            ))
+        }
+    };
+    if !negative {
+        return visitor.visit_u128(value);
+    }
+    if value > i128::max_value() as u128 {
+        return Err(de::Error::invalid_value(
+            de::Unexpected::Bytes(bytes),
+            &"a negative bignum within the range of i128",
+        ));
+    }
+    visitor.visit_i128(-1 - value as i128)
+}
+
+/// A value that is optionally tagged with a CBOR tag.
+///
+/// # Examples
+///
+/// ```
+/// use serde_cbor::tags::Tagged;
+///
+/// // An epoch-based date/time (tag 1).
+/// let date = Tagged::new(Some(1), 1363896240);
+/// let bytes = serde_cbor::to_vec(&date).unwrap();
+/// assert_eq!(bytes, [0xc1, 0x1a, 0x51, 0x4b, 0x67, 0xb0]);
+///
+/// let decoded: Tagged<u32> = serde_cbor::from_slice(&bytes).unwrap();
+/// assert_eq!(decoded.tag, Some(1));
+/// assert_eq!(decoded.value, 1363896240);
+///
+/// // Untagged data is accepted as well.
+/// let decoded: Tagged<u32> = serde_cbor::from_slice(&[0x18, 0x2a]).unwrap();
+/// assert_eq!(decoded, Tagged::new(None, 42));
+/// ```
+#[derive(Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub struct Tagged<T> {
+    /// The tag number, `None` if the value is not tagged.
+    pub tag: Option<u64>,
+    /// The tagged value.
+    pub value: T,
+}
+
+impl<T> Tagged<T> {
+    /// Creates a new, optionally tagged, value.
+    pub fn new(tag: Option<u64>, value: T) -> Self {
+        Tagged { tag, value }
+    }
+}
+
+impl<T> ser::Serialize for Tagged<T>
+where
+    T: ser::Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        match self.tag {
+            Some(tag) => {
+                let mut state = serializer.serialize_tuple_struct(TAG_NAME, 2)?;
+                state.serialize_field(&tag)?;
+                state.serialize_field(&self.value)?;
+                state.end()
+            }
+            None => self.value.serialize(serializer),
+        }
+    }
+}
+
+impl<'de, T> de::Deserialize<'de> for Tagged<T>
+where
+    T: de::Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        struct TaggedVisitor<T>(PhantomData<T>);
+
+        impl<'de, T> de::Visitor<'de> for TaggedVisitor<T>
+        where
+            T: de::Deserialize<'de>,
+        {
+            type Value = Tagged<T>;
+
+            fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt.write_str("a tagged or untagged CBOR value")
+            }
+
+            fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+            where
+                A: de::EnumAccess<'de>,
+            {
+                use serde::de::VariantAccess;
+
+                match data.variant()? {
+                    (Some(tag), variant) => {
+                        let value = variant.newtype_variant()?;
+                        Ok(Tagged::new(Some(tag), value))
+                    }
+                    (None, _) => Err(de::Error::invalid_type(
+                        de::Unexpected::Other("simple value"),
+                        &self,
+                    )),
+                }
+            }
+
+            fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+            where
+                D: de::Deserializer<'de>,
+            {
+                let value = T::deserialize(deserializer)?;
+                Ok(Tagged::new(None, value))
+            }
+        }
+
+        deserializer.deserialize_newtype_struct(TAG_NAME, TaggedVisitor(PhantomData))
+    }
+}
+
+/// Deserializer for the variant of a tag or simple value, see the module documentation.
+pub(crate) struct TagNumber<E> {
+    tag: Option<u64>,
+    marker: PhantomData<E>,
+}
+
+impl<E> TagNumber<E> {
+    pub(crate) fn new(tag: Option<u64>) -> Self {
+        TagNumber {
+            tag,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'de, E> de::Deserializer<'de> for TagNumber<E>
+where
+    E: de::Error,
+{
+    type Error = E;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, E>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.tag {
+            Some(tag) => visitor.visit_u64(tag),
+            None => visitor.visit_none(),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, E>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.tag {
+            Some(tag) => visitor.visit_some(tag.into_deserializer()),
+            None => visitor.visit_none(),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// Access to a tagged value, see the module documentation.
+pub(crate) struct TagAccess<D> {
+    tag: u64,
+    content: D,
+}
+
+impl<D> TagAccess<D> {
+    pub(crate) fn new(tag: u64, content: D) -> Self {
+        TagAccess { tag, content }
+    }
+}
+
+impl<'de, D> de::EnumAccess<'de> for TagAccess<D>
+where
+    D: de::Deserializer<'de>,
+{
+    type Error = D::Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self), D::Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(TagNumber::new(Some(self.tag)))?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de, D> de::VariantAccess<'de> for TagAccess<D>
+where
+    D: de::Deserializer<'de>,
+{
+    type Error = D::Error;
+
+    fn unit_variant(self) -> Result<(), D::Error> {
+        Err(de::Error::invalid_type(
+            de::Unexpected::NewtypeVariant,
+            &"unit variant",
+        ))
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, D::Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        seed.deserialize(self.content)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, D::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.content.deserialize_any(visitor)
+    }
+
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, D::Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        self.content.deserialize_any(visitor)
+    }
+}
+
+/// Access to an unassigned simple value, see the module documentation.
+pub(crate) struct SimpleAccess<E> {
+    value: u8,
+    marker: PhantomData<E>,
+}
+
+impl<E> SimpleAccess<E> {
+    pub(crate) fn new(value: u8) -> Self {
+        SimpleAccess {
+            value,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'de, E> de::EnumAccess<'de> for SimpleAccess<E>
+where
+    E: de::Error,
+{
+    type Error = E;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self), E>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(TagNumber::new(None))?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de, E> de::VariantAccess<'de> for SimpleAccess<E>
+where
+    E: de::Error,
+{
+    type Error = E;
+
+    fn unit_variant(self) -> Result<(), E> {
+        Err(de::Error::invalid_type(
+            de::Unexpected::NewtypeVariant,
+            &"unit variant",
+        ))
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, E>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        seed.deserialize(self.value.into_deserializer())
+    }
+
+    fn tuple_variant<V>(self, _len: usize, _visitor: V) -> Result<V::Value, E>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err(de::Error::invalid_type(
+            de::Unexpected::NewtypeVariant,
+            &"tuple variant",
+        ))
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], _visitor: V) -> Result<V::Value, E>
+    where
+        V: de::Visitor<'de>,
+    {
+        Err(de::Error::invalid_type(
+            de::Unexpected::NewtypeVariant,
+            &"struct variant",
+        ))
+    }
+}
@@ -1,8 +1,14 @@
 use std::collections::BTreeMap;
 use std::fmt;
+use std::mem;
 
-use serde::de;
+use serde::de::{self, IntoDeserializer};
 
+use crate::error::Error;
+use crate::raw::RAW_NAME;
+use crate::tags::{
+    visit_bignum, SimpleAccess, TagAccess, NEGATIVE_BIGNUM, POSITIVE_BIGNUM, TAG_NAME,
+};
 use crate::value::Value;
 
 impl<'de> de::Deserialize<'de> for Value {
@@ -11,155 +17,627 @@ impl<'de> de::Deserialize<'de> for Value {
     where
         D: de::Deserializer<'de>,
     {
-        struct ValueVisitor;
+        deserializer.deserialize_newtype_struct(TAG_NAME, ValueVisitor)
+    }
+}
 
-        impl<'de> de::Visitor<'de> for ValueVisitor {
-            type Value = Value;
+struct ValueVisitor;
 
-            fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
-                fmt.write_str("any valid CBOR value")
-            }
+impl<'de> de::Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str("any valid CBOR value")
+    }
+
+    #[inline]
+    fn visit_str<E>(self, value: &str) -> Result<Value, E>
+    where
+        E: de::Error,
+    {
+        self.visit_string(String::from(value))
+    }
+
+    #[inline]
+    fn visit_string<E>(self, value: String) -> Result<Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Text(value))
+    }
+    #[inline]
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.visit_byte_buf(v.to_owned())
+    }
+
+    #[inline]
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Bytes(v))
+    }
+
+    #[inline]
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::UnsignedInteger(v))
+    }
 
-            #[inline]
-            fn visit_str<E>(self, value: &str) -> Result<Value, E>
-            where
-                E: de::Error,
-            {
-                self.visit_string(String::from(value))
+    #[inline]
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::SignedInteger(v))
+    }
+
+    #[inline]
+    fn visit_i128<E>(self, v: i128) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        if v >= 0 && v <= i128::from(u64::max_value()) {
+            Ok(Value::UnsignedInteger(v as u64))
+        } else {
+            Ok(Value::LargeSignedInteger(v))
+        }
+    }
+
+    #[inline]
+    fn visit_u128<E>(self, v: u128) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        if v > i128::max_value() as u128 {
+            return Err(E::invalid_value(de::Unexpected::Other("u128"), &self));
+        }
+        self.visit_i128(v as i128)
+    }
+
+    #[inline]
+    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Bool(v))
+    }
+
+    #[inline]
+    fn visit_none<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.visit_unit()
+    }
+
+    #[inline]
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Null)
+    }
+
+    #[inline]
+    fn visit_seq<V>(self, mut visitor: V) -> Result<Self::Value, V::Error>
+    where
+        V: de::SeqAccess<'de>,
+    {
+        let mut vec = Vec::new();
+
+        while let Some(elem) = visitor.next_element()? {
+            vec.push(elem);
+        }
+
+        Ok(Value::Array(vec))
+    }
+
+    #[inline]
+    fn visit_map<V>(self, mut visitor: V) -> Result<Value, V::Error>
+    where
+        V: de::MapAccess<'de>,
+    {
+        let mut values = BTreeMap::new();
+
+        while let Some((key, value)) = visitor.next_entry()? {
+            values.insert(key, value);
+        }
+
+        Ok(Value::Map(values))
+    }
+
+    #[inline]
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Float(v))
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::EnumAccess<'de>,
+    {
+        use serde::de::VariantAccess;
+
+        match data.variant()? {
+            (Some(tag), variant) => Ok(tagged_value(tag, variant.newtype_variant()?)),
+            (None, variant) => Ok(Value::Simple(variant.newtype_variant()?)),
+        }
+    }
+
+    #[inline]
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+// Wraps a value in a tag. Bignums that fit in an i128 are stored as integers.
+pub(crate) fn tagged_value(tag: u64, value: Value) -> Value {
+    if let Value::Bytes(ref bytes) = value {
+        if tag == POSITIVE_BIGNUM || tag == NEGATIVE_BIGNUM {
+            let negative = tag == NEGATIVE_BIGNUM;
+            if let Ok(v) = visit_bignum::<_, Error>(negative, bytes, ValueVisitor) {
+                return v;
             }
+        }
+    }
+    Value::Tag(tag, Box::new(value))
+}
 
-            #[inline]
-            fn visit_string<E>(self, value: String) -> Result<Value, E>
-            where
-                E: de::Error,
-            {
-                Ok(Value::Text(value))
+impl<'de> de::Deserializer<'de> for Value {
+    type Error = Error;
+
+    #[allow(clippy::mem_replace_with_default)] // `mem::take` requires Rust 1.40.
+    fn deserialize_any<V>(mut self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        // `Value` implements `Drop`, so the contents are moved out with `mem::replace`.
+        match self {
+            Value::Null => visitor.visit_unit(),
+            Value::Bool(v) => visitor.visit_bool(v),
+            Value::UnsignedInteger(v) => visitor.visit_u64(v),
+            Value::SignedInteger(v) => visitor.visit_i64(v),
+            Value::LargeSignedInteger(v) => visitor.visit_i128(v),
+            Value::Float(v) => visitor.visit_f64(v),
+            Value::Bytes(ref mut v) => visitor.visit_byte_buf(mem::replace(v, Vec::new())),
+            Value::Text(ref mut v) => visitor.visit_string(mem::replace(v, String::new())),
+            Value::Array(ref mut v) => {
+                visit_array(mem::replace(v, Vec::new()).into_iter(), visitor)
             }
-            #[inline]
-            fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
-            where
-                E: de::Error,
-            {
-                self.visit_byte_buf(v.to_owned())
+            Value::Map(ref mut v) => {
+                visit_map(mem::replace(v, BTreeMap::new()).into_iter(), visitor)
             }
+            Value::Tag(_, ref mut v) => v.take().deserialize_any(visitor),
+            Value::Simple(_) => Err(de::Error::invalid_type(
+                de::Unexpected::Other("simple value"),
+                &visitor,
+            )),
+            Value::__Hidden => unreachable!(),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self {
+            Value::Null => visitor.visit_none(),
+            value => visitor.visit_some(value),
+        }
+    }
 
-            #[inline]
-            fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
-            where
-                E: de::Error,
-            {
-                Ok(Value::Bytes(v))
+    fn deserialize_newtype_struct<V>(
+        mut self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self {
+            Value::Tag(tag, ref mut v) if name == TAG_NAME => {
+                visitor.visit_enum(TagAccess::new(tag, v.take()))
             }
+            Value::Simple(v) if name == TAG_NAME => visitor.visit_enum(SimpleAccess::new(v)),
+            value if name == RAW_NAME => visitor.visit_byte_buf(crate::to_vec(&value)?),
+            value => visitor.visit_newtype_struct(value),
+        }
+    }
 
-            #[inline]
-            fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
-            where
-                E: de::Error,
-            {
-                Ok(Value::UnsignedInteger(v))
+    // Accepts both the map enum format and the legacy array enum format.
+    #[allow(clippy::mem_replace_with_default)] // `mem::take` requires Rust 1.40.
+    fn deserialize_enum<V>(
+        mut self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        let (variant, content) = match self {
+            Value::Map(ref mut map) => {
+                let map = mem::replace(map, BTreeMap::new());
+                if map.len() != 1 {
+                    return Err(de::Error::invalid_length(
+                        map.len(),
+                        &"map with a single key",
+                    ));
+                }
+                let (variant, value) = map.into_iter().next().expect("map has one entry");
+                (variant, VariantContent::Value(value))
             }
+            Value::Array(ref mut array) => {
+                let mut iter = mem::replace(array, Vec::new()).into_iter();
+                match iter.next() {
+                    Some(variant) => (variant, VariantContent::Seq(iter.collect())),
+                    None => {
+                        return Err(de::Error::invalid_length(
+                            0,
+                            &"array with at least one element",
+                        ))
+                    }
+                }
+            }
+            Value::Tag(_, ref mut v) => return v.take().deserialize_enum(name, variants, visitor),
+            variant => (variant, VariantContent::Unit),
+        };
+        visitor.visit_enum(EnumDeserializer { variant, content })
+    }
+
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match bignum(&self) {
+            Some((negative, bytes)) => visit_bignum(negative, bytes, visitor),
+            None => self.deserialize_any(visitor),
+        }
+    }
 
-            #[inline]
-            fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
-            where
-                E: de::Error,
-            {
-                Ok(Value::SignedInteger(v))
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match bignum(&self) {
+            Some((negative, bytes)) => visit_bignum(negative, bytes, visitor),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    #[inline]
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &'de Value {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match *self {
+            Value::Null => visitor.visit_unit(),
+            Value::Bool(v) => visitor.visit_bool(v),
+            Value::UnsignedInteger(v) => visitor.visit_u64(v),
+            Value::SignedInteger(v) => visitor.visit_i64(v),
+            Value::LargeSignedInteger(v) => visitor.visit_i128(v),
+            Value::Float(v) => visitor.visit_f64(v),
+            Value::Bytes(ref v) => visitor.visit_borrowed_bytes(v),
+            Value::Text(ref v) => visitor.visit_borrowed_str(v),
+            Value::Array(ref v) => visit_array(v.iter(), visitor),
+            Value::Map(ref v) => visit_map(v.iter(), visitor),
+            Value::Tag(_, ref v) => de::Deserializer::deserialize_any(&**v, visitor),
+            Value::Simple(_) => Err(de::Error::invalid_type(
+                de::Unexpected::Other("simple value"),
+                &visitor,
+            )),
+            Value::__Hidden => unreachable!(),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match *self {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match *self {
+            Value::Tag(tag, ref v) if name == TAG_NAME => {
+                visitor.visit_enum(TagAccess::new(tag, &**v))
             }
+            Value::Simple(v) if name == TAG_NAME => visitor.visit_enum(SimpleAccess::new(v)),
+            _ if name == RAW_NAME => visitor.visit_byte_buf(crate::to_vec(self)?),
+            _ => visitor.visit_newtype_struct(self),
+        }
+    }
 
-            #[inline]
-            fn visit_i128<E>(self, v: i128) -> Result<Self::Value, E>
-            where
-                E: de::Error,
-            {
-                if v > 2i128.pow(64) - 1 || v < -(2i128.pow(64)) {
-                    return Err(E::custom("Integer value must be between -2^64 and 2^64-1"));
+    // Accepts both the map enum format and the legacy array enum format.
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        let (variant, content) = match *self {
+            Value::Map(ref map) => {
+                if map.len() != 1 {
+                    return Err(de::Error::invalid_length(
+                        map.len(),
+                        &"map with a single key",
+                    ));
                 }
-
-                if v >= 0 {
-                    Ok(Value::UnsignedInteger(v as u64))
-                } else if v >= 2i128.pow(63) {
-                    Ok(Value::SignedInteger(v as i64))
-                } else {
-                    Ok(Value::LargeSignedInteger(v))
+                let (variant, value) = map.iter().next().expect("map has one entry");
+                (variant, VariantContent::Value(value))
+            }
+            Value::Array(ref array) => match array.split_first() {
+                Some((variant, rest)) => (variant, VariantContent::Seq(rest.iter().collect())),
+                None => {
+                    return Err(de::Error::invalid_length(
+                        0,
+                        &"array with at least one element",
+                    ))
                 }
+            },
+            Value::Tag(_, ref v) => {
+                return de::Deserializer::deserialize_enum(&**v, name, variants, visitor)
             }
+            _ => (self, VariantContent::Unit),
+        };
+        visitor.visit_enum(EnumDeserializer { variant, content })
+    }
 
-            #[inline]
-            fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
-            where
-                E: de::Error,
-            {
-                Ok(Value::Bool(v))
-            }
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match bignum(self) {
+            Some((negative, bytes)) => visit_bignum(negative, bytes, visitor),
+            None => self.deserialize_any(visitor),
+        }
+    }
 
-            #[inline]
-            fn visit_none<E>(self) -> Result<Self::Value, E>
-            where
-                E: de::Error,
-            {
-                self.visit_unit()
-            }
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match bignum(self) {
+            Some((negative, bytes)) => visit_bignum(negative, bytes, visitor),
+            None => self.deserialize_any(visitor),
+        }
+    }
 
-            #[inline]
-            fn visit_unit<E>(self) -> Result<Self::Value, E>
-            where
-                E: de::Error,
-            {
-                Ok(Value::Null)
-            }
+    #[inline]
+    fn is_human_readable(&self) -> bool {
+        false
+    }
 
-            #[inline]
-            fn visit_seq<V>(self, mut visitor: V) -> Result<Self::Value, V::Error>
-            where
-                V: de::SeqAccess<'de>,
-            {
-                let mut vec = Vec::new();
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
 
-                while let Some(elem) = visitor.next_element()? {
-                    vec.push(elem);
-                }
+impl<'de> IntoDeserializer<'de, Error> for Value {
+    type Deserializer = Self;
 
-                Ok(Value::Array(vec))
-            }
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
 
-            #[inline]
-            fn visit_map<V>(self, mut visitor: V) -> Result<Value, V::Error>
-            where
-                V: de::MapAccess<'de>,
-            {
-                let mut values = BTreeMap::new();
+impl<'de> IntoDeserializer<'de, Error> for &'de Value {
+    type Deserializer = Self;
 
-                while let Some((key, value)) = visitor.next_entry()? {
-                    values.insert(key, value);
-                }
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+// Returns the sign and content of a bignum that didn't fit in an integer variant.
+fn bignum(value: &Value) -> Option<(bool, &[u8])> {
+    match *value {
+        Value::Tag(tag, ref v) if tag == POSITIVE_BIGNUM || tag == NEGATIVE_BIGNUM => match **v {
+            Value::Bytes(ref bytes) => Some((tag == NEGATIVE_BIGNUM, bytes)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn visit_array<'de, I, V>(iter: I, visitor: V) -> Result<V::Value, Error>
+where
+    I: Iterator,
+    I::Item: IntoDeserializer<'de, Error>,
+    V: de::Visitor<'de>,
+{
+    let mut deserializer = de::value::SeqDeserializer::new(iter);
+    let value = visitor.visit_seq(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+fn visit_map<'de, I, K, T, V>(iter: I, visitor: V) -> Result<V::Value, Error>
+where
+    I: Iterator<Item = (K, T)>,
+    K: IntoDeserializer<'de, Error>,
+    T: IntoDeserializer<'de, Error>,
+    V: de::Visitor<'de>,
+{
+    let mut deserializer = de::value::MapDeserializer::new(iter);
+    let value = visitor.visit_map(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+// The content of an enum variant: nothing for unit variants, the map value in the map format
+// and the remaining array elements in the legacy format.
+enum VariantContent<T> {
+    Unit,
+    Value(T),
+    Seq(Vec<T>),
+}
+
+struct EnumDeserializer<T> {
+    variant: T,
+    content: VariantContent<T>,
+}
+
+impl<'de, T> de::EnumAccess<'de> for EnumDeserializer<T>
+where
+    T: IntoDeserializer<'de, Error>,
+{
+    type Error = Error;
+    type Variant = VariantDeserializer<T>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, VariantDeserializer<T>), Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(self.variant.into_deserializer())?;
+        Ok((
+            variant,
+            VariantDeserializer {
+                content: self.content,
+            },
+        ))
+    }
+}
+
+struct VariantDeserializer<T> {
+    content: VariantContent<T>,
+}
 
-                Ok(Value::Map(values))
+impl<T> VariantDeserializer<T> {
+    // The legacy format stores newtype and struct variants as a single array element.
+    fn single(self, expected: &'static str) -> Result<T, Error> {
+        match self.content {
+            VariantContent::Unit => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &expected,
+            )),
+            VariantContent::Value(value) => Ok(value),
+            VariantContent::Seq(mut values) => {
+                if values.len() != 1 {
+                    return Err(de::Error::invalid_length(values.len(), &"1 element"));
+                }
+                Ok(values.remove(0))
             }
+        }
+    }
+}
+
+impl<'de, T> de::VariantAccess<'de> for VariantDeserializer<T>
+where
+    T: IntoDeserializer<'de, Error>,
+{
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match self.content {
+            VariantContent::Unit => Ok(()),
+            VariantContent::Value(value) => de::Deserialize::deserialize(value.into_deserializer()),
+            VariantContent::Seq(values) => visit_array(values.into_iter(), UnitVisitor),
+        }
+    }
+
+    fn newtype_variant_seed<S>(self, seed: S) -> Result<S::Value, Error>
+    where
+        S: de::DeserializeSeed<'de>,
+    {
+        let value = self.single("newtype variant")?;
+        seed.deserialize(value.into_deserializer())
+    }
 
-            #[inline]
-            fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
-            where
-                E: de::Error,
-            {
-                Ok(Value::Float(v))
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        match self.content {
+            VariantContent::Unit => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"tuple variant",
+            )),
+            VariantContent::Value(value) => {
+                de::Deserializer::deserialize_any(value.into_deserializer(), visitor)
             }
+            VariantContent::Seq(values) => visit_array(values.into_iter(), visitor),
         }
+    }
+
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: de::Visitor<'de>,
+    {
+        let value = self.single("struct variant")?;
+        de::Deserializer::deserialize_any(value.into_deserializer(), visitor)
+    }
+}
+
+// Accepts an empty array or an array containing a single null for legacy unit variants.
+struct UnitVisitor;
 
-        deserializer.deserialize_any(ValueVisitor)
+impl<'de> de::Visitor<'de> for UnitVisitor {
+    type Value = ();
+
+    fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str("unit variant")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<(), A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        seq.next_element::<()>()?;
+        Ok(())
     }
 }
 
 /// Convert a `serde_cbor::Value` into a type `T`
-#[allow(clippy::needless_pass_by_value)]
-pub fn from_value<T>(value: Value) -> Result<T, crate::error::Error>
+///
+/// The value is deserialized directly, without encoding it to CBOR first.
+pub fn from_value<T>(value: Value) -> Result<T, Error>
 where
     T: de::DeserializeOwned,
 {
-    // TODO implement in a way that doesn't require
-    // roundtrip through buffer (i.e. by implementing
-    // `serde::de::Deserializer` for `Value` and then doing
-    // `T::deserialize(value)`).
-    let buf = crate::to_vec(&value)?;
-    crate::from_slice(buf.as_slice())
+    T::deserialize(value)
 }
@@ -3,14 +3,24 @@
 mod de;
 mod ser;
 
+use std::borrow::Cow;
 use std::cmp::{Ord, Ordering, PartialOrd};
 use std::collections::BTreeMap;
+use std::fmt;
+use std::mem;
+
+use half::f16;
+
+use crate::diag::{write_bytes, write_float, write_text};
+use crate::tags::{bignum_bytes, NEGATIVE_BIGNUM, POSITIVE_BIGNUM};
 
 #[doc(inline)]
 pub use self::de::from_value;
 #[doc(inline)]
 pub use self::ser::to_value;
 
+pub(crate) use self::de::tagged_value;
+
 /// The `Value` enum, a loosely typed way of representing any valid CBOR value.
 ///
 /// Maps are sorted according to the canonical ordering
@@ -33,12 +43,12 @@ pub enum Value {
     /// Numbers smaller than -2^63
     /// The smallest value that can be represented is -2^64.
     SignedInteger(i64),
-    /// Integer CBOR possibly-negative numbers within the i28 range.
+    /// Integer CBOR possibly-negative numbers within the i128 range.
     ///
-    /// For numbers smaller than -2^63
+    /// For numbers smaller than -2^63 or larger than 2^64-1.
     ///
-    /// Values smaller than -2^64 can't be serialized
-    /// and will cause an error.
+    /// Values outside of the range -2^64 to 2^64-1 are stored
+    /// as bignums (tags 2 and 3).
     LargeSignedInteger(i128),
     /// Represents a floating point value.
     Float(f64),
@@ -59,12 +69,92 @@ pub enum Value {
     /// to establish canonical order may be slow and therefore insertion
     /// and retrieval of values will be slow too.
     Map(BTreeMap<Value, Value>),
+    /// Represents a tagged value.
+    ///
+    /// The first element is the tag number, the second the value it applies to.
+    Tag(u64, Box<Value>),
+    /// Represents a simple value that has no other representation.
+    ///
+    /// The simple values 20 to 23 are *false*, *true*, *null* and *undefined*;
+    /// they are decoded as `Bool` and `Null` instead.
+    /// The values 24 to 31 are reserved and can't be serialized.
+    Simple(u8),
     // The hidden variant allows the enum to be extended
-    // with variants for tags and simple values.
+    // with further variants.
     #[doc(hidden)]
     __Hidden,
 }
 
+// Arrays, maps and tags are taken apart on a stack, so dropping deeply nested values doesn't
+// overflow the call stack.
+impl Drop for Value {
+    fn drop(&mut self) {
+        let mut stack = Vec::new();
+        self.take_nested(&mut stack);
+        while let Some(mut value) = stack.pop() {
+            value.take_nested(&mut stack);
+        }
+    }
+}
+
+/// Formats the value in [diagnostic notation](../diag/index.html).
+///
+/// ```
+/// use serde_cbor::Value;
+///
+/// let value = Value::Tag(1, Box::new(Value::UnsignedInteger(1363896240)));
+/// assert_eq!(value.to_string(), "1(1363896240)");
+/// ```
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Value::Null => f.write_str("null"),
+            Value::Bool(v) => write!(f, "{}", v),
+            Value::UnsignedInteger(v) => write!(f, "{}", v),
+            Value::SignedInteger(v) => write!(f, "{}", v),
+            Value::LargeSignedInteger(v) if v > i128::from(u64::max_value()) => {
+                let mut buf = [0; 16];
+                write!(f, "{}(", POSITIVE_BIGNUM)?;
+                write_bytes(f, bignum_bytes(v as u128, &mut buf))?;
+                f.write_str(")")
+            }
+            Value::LargeSignedInteger(v) if v < -i128::from(u64::max_value()) - 1 => {
+                let mut buf = [0; 16];
+                write!(f, "{}(", NEGATIVE_BIGNUM)?;
+                write_bytes(f, bignum_bytes(-(v + 1) as u128, &mut buf))?;
+                f.write_str(")")
+            }
+            Value::LargeSignedInteger(v) => write!(f, "{}", v),
+            Value::Float(v) => write_float(f, v),
+            Value::Bytes(ref v) => write_bytes(f, v),
+            Value::Text(ref v) => write_text(f, v),
+            Value::Array(ref v) => {
+                f.write_str("[")?;
+                for (i, item) in v.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                f.write_str("]")
+            }
+            Value::Map(ref v) => {
+                f.write_str("{")?;
+                for (i, (key, value)) in v.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}: {}", key, value)?;
+                }
+                f.write_str("}")
+            }
+            Value::Tag(tag, ref v) => write!(f, "{}({})", tag, v),
+            Value::Simple(v) => write!(f, "simple({})", v),
+            Value::__Hidden => unreachable!(),
+        }
+    }
+}
+
 impl PartialEq for Value {
     fn eq(&self, other: &Value) -> bool {
         self.cmp(other) == Ordering::Equal
@@ -86,7 +176,9 @@ impl Ord for Value {
         // 2. Shorter sequence sorts first.
         // 3. Compare integers by magnitude.
         // 4. Compare byte and text sequences lexically.
-        // 5. Compare the serializations of both types. (expensive)
+        // 5. Compare the items of arrays and maps in order.
+        // 6. Compare tags by their number and content.
+        // 7. Compare simple values and floats by their encoding.
         use self::Value::*;
         if self.major_type() != other.major_type() {
             return self.major_type().cmp(&other.major_type());
@@ -95,7 +187,9 @@ impl Ord for Value {
             (UnsignedInteger(a), UnsignedInteger(b)) => a.cmp(b),
             // Use i128 to avoid possible panic if abs() is called on -2^63
             (SignedInteger(a), SignedInteger(b)) => i128::from(*a).abs().cmp(&i128::from(*b).abs()),
-            (LargeSignedInteger(a), LargeSignedInteger(b)) => a.abs().cmp(&b.abs()),
+            (LargeSignedInteger(a), LargeSignedInteger(b)) if self.major_type() != 6 => {
+                a.abs().cmp(&b.abs())
+            }
             (UnsignedInteger(a), SignedInteger(b)) => {
                 i128::from(*a).abs().cmp(&i128::from(*b).abs())
             }
@@ -112,11 +206,11 @@ impl Ord for Value {
             (Map(a), Map(b)) if a.len() != b.len() => a.len().cmp(&b.len()),
             (Bytes(a), Bytes(b)) => a.cmp(b),
             (Text(a), Text(b)) => a.cmp(b),
-            (a, b) => {
-                let a = crate::to_vec(a).expect("self is serializable");
-                let b = crate::to_vec(b).expect("other is serializable");
-                a.cmp(&b)
-            }
+            (Tag(a, x), Tag(b, y)) => a.cmp(b).then_with(|| x.cmp(y)),
+            (Array(a), Array(b)) => a.cmp(b),
+            (Map(a), Map(b)) => a.cmp(b),
+            (a, b) if a.major_type() == 6 => a.tag_parts().cmp(&b.tag_parts()),
+            (a, b) => a.simple_rank().cmp(&b.simple_rank()),
         }
     }
 }
@@ -151,6 +245,45 @@ impl_from!(Value::Array, Vec<Value>);
 impl_from!(Value::Map, BTreeMap<Value, Value>);
 
 impl Value {
+    // Moves the value out and leaves `Null` behind. `Value` implements `Drop`, so the fields of a
+    // value can't be moved out by a pattern.
+    pub(crate) fn take(&mut self) -> Value {
+        mem::replace(self, Value::Null)
+    }
+
+    // Moves the arrays, maps and tags contained in this value onto the stack.
+    #[allow(clippy::mem_replace_with_default)] // `mem::take` requires Rust 1.40.
+    fn take_nested(&mut self, stack: &mut Vec<Value>) {
+        match *self {
+            Value::Array(ref mut items) => {
+                stack.extend(items.drain(..).filter(Value::is_nested));
+            }
+            Value::Map(ref mut map) => {
+                for (key, value) in mem::replace(map, BTreeMap::new()) {
+                    if key.is_nested() {
+                        stack.push(key);
+                    }
+                    if value.is_nested() {
+                        stack.push(value);
+                    }
+                }
+            }
+            Value::Tag(_, ref mut value) if value.is_nested() => {
+                stack.push(mem::replace(&mut **value, Value::Null));
+            }
+            _ => {}
+        }
+    }
+
+    fn is_nested(&self) -> bool {
+        match *self {
+            Value::Array(ref items) => !items.is_empty(),
+            Value::Map(ref map) => !map.is_empty(),
+            Value::Tag(..) => true,
+            _ => false,
+        }
+    }
+
     fn major_type(&self) -> u8 {
         use self::Value::*;
         match self {
@@ -165,7 +298,9 @@ impl Value {
                 }
             }
             LargeSignedInteger(v) => {
-                if *v >= 0 {
+                if *v > i128::from(u64::max_value()) || *v < -i128::from(u64::max_value()) - 1 {
+                    6
+                } else if *v >= 0 {
                     0
                 } else {
                     1
@@ -176,7 +311,49 @@ impl Value {
             Text(_) => 3,
             Array(_) => 4,
             Map(_) => 5,
+            Tag(_, _) => 6,
+            Simple(_) => 7,
             __Hidden => unreachable!(),
         }
     }
+
+    // The number and content of a tag. Bignums are tags that contain the bytes of their magnitude.
+    fn tag_parts(&self) -> (u64, Cow<'_, Value>) {
+        let (tag, magnitude) = match *self {
+            Value::Tag(tag, ref value) => return (tag, Cow::Borrowed(&**value)),
+            Value::LargeSignedInteger(v) if v < 0 => (NEGATIVE_BIGNUM, -(v + 1) as u128),
+            Value::LargeSignedInteger(v) => (POSITIVE_BIGNUM, v as u128),
+            _ => unreachable!(),
+        };
+        let mut buf = [0; 16];
+        let bytes = bignum_bytes(magnitude, &mut buf).to_vec();
+        (tag, Cow::Owned(Value::Bytes(bytes)))
+    }
+
+    // Ranks values of major type 7 like their encoding, by additional information and argument.
+    // A `Simple` value with the number of `false`, `true` or `null` sorts after it.
+    #[allow(clippy::float_cmp)]
+    fn simple_rank(&self) -> (u8, u64, bool) {
+        match *self {
+            Value::Bool(false) => (20, 0, false),
+            Value::Bool(true) => (21, 0, false),
+            Value::Null => (22, 0, false),
+            Value::Simple(v) if v < 24 => (v, 0, true),
+            Value::Simple(v) => (24, u64::from(v), true),
+            Value::Float(v) if v.is_nan() => (25, 0x7e00, false),
+            Value::Float(v) if v.is_finite() && f64::from(v as f32) != v => {
+                (27, v.to_bits(), false)
+            }
+            Value::Float(v) => {
+                let single = v as f32;
+                let half = f16::from_f32(single);
+                if f32::from(half) == single {
+                    (25, u64::from(half.to_bits()), false)
+                } else {
+                    (26, u64::from(single.to_bits()), false)
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
 }
@@ -9,6 +9,8 @@
 use std::collections::BTreeMap;
 
 use crate::error::Error;
+use crate::raw::RAW_NAME;
+use crate::tags::{bignum_bytes, Tagged, POSITIVE_BIGNUM, SIMPLE_NAME, TAG_NAME};
 use serde::{self, Serialize};
 
 use crate::value::Value;
@@ -30,6 +32,11 @@ impl serde::Serialize for Value {
             Value::Float(v) => serializer.serialize_f64(v),
             Value::Bool(v) => serializer.serialize_bool(v),
             Value::Null => serializer.serialize_unit(),
+            Value::Tag(tag, ref v) => Tagged::new(Some(tag), v).serialize(serializer),
+            Value::Simple(24..=31) => Err(serde::ser::Error::custom(
+                "The simple value is reserved and can't be stored in CBOR",
+            )),
+            Value::Simple(v) => serializer.serialize_newtype_struct(SIMPLE_NAME, &v),
             Value::__Hidden => unreachable!(),
         }
     }
@@ -75,14 +82,8 @@ impl serde::Serializer for Serializer {
     }
 
     fn serialize_i128(self, value: i128) -> Result<Value, Error> {
-        if value > 2i128.pow(64) - 1 || value < -(2i128.pow(64)) {
-            return Err(Error::message("The number can't be stored in CBOR"));
-        }
-
-        if value >= 0 {
+        if value >= 0 && value <= i128::from(u64::max_value()) {
             Ok(Value::UnsignedInteger(value as u64))
-        } else if value >= 2i128.pow(63) {
-            Ok(Value::SignedInteger(value as i64))
         } else {
             Ok(Value::LargeSignedInteger(value))
         }
@@ -108,6 +109,15 @@ impl serde::Serializer for Serializer {
         Ok(Value::UnsignedInteger(value))
     }
 
+    fn serialize_u128(self, value: u128) -> Result<Value, Error> {
+        if value <= i128::max_value() as u128 {
+            return self.serialize_i128(value as i128);
+        }
+        let mut buf = [0; 16];
+        let bytes = bignum_bytes(value, &mut buf).to_vec();
+        Ok(Value::Tag(POSITIVE_BIGNUM, Box::new(Value::Bytes(bytes))))
+    }
+
     #[inline]
     fn serialize_f32(self, value: f32) -> Result<Value, Error> {
         self.serialize_f64(f64::from(value))
@@ -157,13 +167,19 @@ impl serde::Serializer for Serializer {
     #[inline]
     fn serialize_newtype_struct<T: ?Sized>(
         self,
-        _name: &'static str,
+        name: &'static str,
         value: &T,
     ) -> Result<Value, Error>
     where
         T: Serialize,
     {
-        value.serialize(self)
+        match value.serialize(self)? {
+            Value::UnsignedInteger(v) if name == SIMPLE_NAME && v <= 0xff => {
+                Ok(Value::Simple(v as u8))
+            }
+            Value::Bytes(ref bytes) if name == RAW_NAME => crate::from_slice(bytes),
+            value => Ok(value),
+        }
     }
 
     fn serialize_newtype_variant<T: ?Sized>(
@@ -197,6 +213,7 @@ impl serde::Serializer for Serializer {
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
         Ok(SerializeVec {
             vec: Vec::with_capacity(len.unwrap_or(0)),
+            tagged: false,
         })
     }
 
@@ -206,10 +223,12 @@ impl serde::Serializer for Serializer {
 
     fn serialize_tuple_struct(
         self,
-        _name: &'static str,
+        name: &'static str,
         len: usize,
     ) -> Result<Self::SerializeTupleStruct, Error> {
-        self.serialize_tuple(len)
+        let mut state = self.serialize_tuple(len)?;
+        state.tagged = name == TAG_NAME && len == 2;
+        Ok(state)
     }
 
     fn serialize_tuple_variant(
@@ -261,6 +280,8 @@ impl serde::Serializer for Serializer {
 
 pub struct SerializeVec {
     vec: Vec<Value>,
+    // The tuple struct holds the tag number and value of a tagged value.
+    tagged: bool,
 }
 
 pub struct SerializeTupleVariant {
@@ -291,6 +312,15 @@ impl serde::ser::SerializeSeq for SerializeVec {
     }
 
     fn end(self) -> Result<Value, Error> {
+        if self.tagged {
+            let mut iter = self.vec.into_iter();
+            return match (iter.next(), iter.next()) {
+                (Some(Value::UnsignedInteger(tag)), Some(value)) => {
+                    Ok(Value::Tag(tag, Box::new(value)))
+                }
+                _ => Err(Error::message("invalid tagged value")),
+            };
+        }
         Ok(Value::Array(self.vec))
     }
 }
@@ -0,0 +1,280 @@
+#[cfg(feature = "async")]
+mod async_tests {
+    use std::io;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    use futures::executor::block_on;
+    use futures::io::{AsyncRead, AsyncWrite};
+    use futures::sink::SinkExt;
+    use futures::stream::{self, StreamExt};
+    use serde_cbor::async_io::{to_async_writer, AsyncReader, AsyncWriter};
+    use serde_cbor::ser::{SequenceWriter, Serializer};
+
+    // Returns one byte at a time and is pending before each of them.
+    struct Trickle {
+        data: Vec<u8>,
+        pos: usize,
+        ready: bool,
+    }
+
+    impl Trickle {
+        fn new(data: Vec<u8>) -> Trickle {
+            Trickle {
+                data,
+                pos: 0,
+                ready: false,
+            }
+        }
+    }
+
+    impl AsyncRead for Trickle {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            if !self.ready {
+                self.ready = true;
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            self.ready = false;
+            if self.pos == self.data.len() {
+                return Poll::Ready(Ok(0));
+            }
+            buf[0] = self.data[self.pos];
+            self.pos += 1;
+            Poll::Ready(Ok(1))
+        }
+    }
+
+    // Accepts one byte at a time and is pending before each of them.
+    #[derive(Default)]
+    struct TrickleSink {
+        data: Vec<u8>,
+        ready: bool,
+        flushed: usize,
+        closed: bool,
+    }
+
+    impl TrickleSink {
+        fn poll_pending(&mut self, cx: &mut Context<'_>) -> bool {
+            self.ready = !self.ready;
+            if self.ready {
+                cx.waker().wake_by_ref();
+            }
+            self.ready
+        }
+    }
+
+    impl AsyncWrite for TrickleSink {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            if self.poll_pending(cx) {
+                return Poll::Pending;
+            }
+            self.data.push(buf[0]);
+            Poll::Ready(Ok(1))
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            if self.poll_pending(cx) {
+                return Poll::Pending;
+            }
+            self.flushed = self.data.len();
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            if self.poll_pending(cx) {
+                return Poll::Pending;
+            }
+            self.closed = true;
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    struct Failing;
+
+    impl AsyncRead for Failing {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            _buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            Poll::Ready(Err(io::Error::new(io::ErrorKind::BrokenPipe, "broken")))
+        }
+    }
+
+    impl AsyncWrite for Failing {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            _buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            Poll::Ready(Ok(0))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn sequence() -> Vec<u8> {
+        let mut bytes = serde_cbor::to_vec(&("hello", 1)).unwrap();
+        bytes.extend(serde_cbor::to_vec(&"not a number").unwrap());
+        bytes.extend(serde_cbor::to_vec(&vec![7; 100]).unwrap());
+        bytes
+    }
+
+    #[test]
+    fn test_read_item() {
+        block_on(async {
+            let mut reader = AsyncReader::new(Trickle::new(sequence()));
+            let item: Option<(String, u8)> = reader.read_item().await.unwrap();
+            assert_eq!(item, Some(("hello".to_owned(), 1)));
+            // An item of the wrong type is skipped.
+            assert!(reader.read_item::<u8>().await.unwrap_err().is_data());
+            let item: Option<Vec<u8>> = reader.read_item().await.unwrap();
+            assert_eq!(item, Some(vec![7; 100]));
+            assert_eq!(reader.read_item::<u8>().await.unwrap(), None);
+        });
+    }
+
+    #[test]
+    fn test_buffered() {
+        block_on(async {
+            let input: &[u8] = b"\x01\x02\x03";
+            let mut reader = AsyncReader::new(input);
+            assert_eq!(reader.read_item::<u8>().await.unwrap(), Some(1));
+            assert_eq!(reader.buffered(), [2, 3]);
+        });
+    }
+
+    #[test]
+    fn test_stream() {
+        block_on(async {
+            let mut input = serde_cbor::to_vec(&1).unwrap();
+            input.extend(sequence());
+            let reader = AsyncReader::new(Trickle::new(input));
+            let items: Vec<_> = reader.into_stream::<u8>().collect().await;
+            assert_eq!(items.len(), 4);
+            assert_eq!(items[0].as_ref().unwrap(), &1);
+            assert!(items[1..]
+                .iter()
+                .all(|item| item.as_ref().unwrap_err().is_data()));
+        });
+    }
+
+    #[test]
+    fn test_errors() {
+        block_on(async {
+            // The input ends in the middle of an item.
+            let mut input = sequence();
+            input.pop();
+            let len = input.len() as u64;
+            let reader = AsyncReader::new(Trickle::new(input));
+            let items: Vec<_> = reader.into_stream::<serde_cbor::Value>().collect().await;
+            assert_eq!(items.len(), 3);
+            let err = items[2].as_ref().unwrap_err();
+            assert!(err.is_eof());
+            assert_eq!(err.offset(), len);
+
+            let mut reader = AsyncReader::new(Failing);
+            assert!(reader.read_item::<u8>().await.unwrap_err().is_io());
+
+            // The stream ends after a syntax error.
+            let input: &[u8] = b"\x01\xff\x02";
+            let reader = AsyncReader::new(input);
+            let items: Vec<_> = reader.into_stream::<u8>().collect().await;
+            assert_eq!(items.len(), 2);
+            let err = items[1].as_ref().unwrap_err();
+            assert!(err.is_syntax());
+            assert_eq!(err.offset(), 1);
+        });
+    }
+
+    #[test]
+    fn test_max_item_len() {
+        block_on(async {
+            let input = serde_cbor::to_vec(&vec![0u8; 1000]).unwrap();
+            let mut reader = AsyncReader::new(&input[..]).max_item_len(100);
+            let err = reader.read_item::<Vec<u8>>().await.unwrap_err();
+            assert!(err.is_syntax());
+
+            let mut reader = AsyncReader::new(&input[..]).max_item_len(1003);
+            let item: Option<Vec<u8>> = reader.read_item().await.unwrap();
+            assert_eq!(item.map(|item| item.len()), Some(1000));
+        });
+    }
+
+    #[test]
+    fn test_to_async_writer() {
+        block_on(async {
+            let mut output = TrickleSink::default();
+            to_async_writer(&mut output, &("hello", 1)).await.unwrap();
+            assert_eq!(output.data, serde_cbor::to_vec(&("hello", 1)).unwrap());
+            assert_eq!(output.flushed, output.data.len());
+
+            let err = to_async_writer(Failing, &1).await.unwrap_err();
+            assert!(err.is_io());
+        });
+    }
+
+    #[test]
+    fn test_sink() {
+        block_on(async {
+            let items: Vec<Vec<u32>> = (0..2000).map(|i| vec![i; 3]).collect();
+            let mut expected = SequenceWriter::new(Vec::new());
+            for item in &items {
+                expected.write(item).unwrap();
+            }
+            let expected = expected.into_inner();
+            assert!(expected.len() > 8 * 1024);
+
+            let mut writer = AsyncWriter::new(TrickleSink::default());
+            let mut stream = stream::iter(items.iter().map(Ok));
+            writer.send_all(&mut stream).await.unwrap();
+            assert!(writer.buffered().is_empty());
+            writer.close().await.unwrap();
+            let output = writer.into_inner();
+            assert!(output.closed);
+            assert_eq!(output.data, expected);
+
+            let reader = AsyncReader::new(&output.data[..]);
+            let decoded: Vec<Vec<u32>> = reader
+                .into_stream()
+                .map(|item| item.unwrap())
+                .collect()
+                .await;
+            assert_eq!(decoded, items);
+        });
+    }
+
+    #[test]
+    fn test_writer_options() {
+        #[derive(serde_derive::Serialize)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        block_on(async {
+            let mut output = Vec::new();
+            let ser = Serializer::new(Vec::new()).packed_format();
+            let mut writer = AsyncWriter::from_serializer(&mut output, ser);
+            writer.write(&Point { x: 1, y: 2 }).unwrap();
+            assert_eq!(writer.buffered(), b"\xa2\x00\x01\x01\x02");
+            writer.flush().await.unwrap();
+            assert_eq!(output, b"\xa2\x00\x01\x01\x02");
+        });
+    }
+}
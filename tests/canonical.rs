@@ -1,6 +1,14 @@
+#[macro_use]
+extern crate serde_derive;
+
 #[cfg(feature = "std")]
 mod std_tests {
+    use serde::Serialize;
+    use serde_cbor::ser::KeyOrder;
     use serde_cbor::value::Value;
+    use serde_cbor::Serializer;
+    use std::cmp::Ordering;
+    use std::collections::BTreeMap;
 
     #[test]
     fn integer_canonical_sort_order() {
@@ -101,4 +109,213 @@ mod std_tests {
         sorted.sort();
         assert_eq!(expected, sorted);
     }
+
+    #[test]
+    fn tag_and_simple_canonical_sort_order() {
+        let expected = vec![
+            Value::Text("".to_string()),
+            Value::Tag(1, Box::new(Value::UnsignedInteger(2))),
+            Value::Tag(1, Box::new(Value::Text("a".to_string()))),
+            Value::Tag(24, Box::new(Value::UnsignedInteger(0))),
+            Value::Simple(0),
+            Value::Bool(false),
+            Value::Null,
+            Value::Simple(32),
+        ];
+        let mut sorted = expected.clone();
+        sorted.sort();
+        assert_eq!(expected, sorted);
+    }
+
+    #[test]
+    fn canonical_hash_map() {
+        use std::collections::HashMap;
+
+        let mut map = HashMap::new();
+        for key in &["aa", "b", "", "a", "ab"] {
+            map.insert(key.to_string(), 0);
+        }
+        let bytes = serde_cbor::to_vec_canonical(&map).unwrap();
+        assert_eq!(bytes, b"\xa5\x60\x00\x61a\x00\x61b\x00\x62aa\x00\x62ab\x00");
+    }
+
+    #[test]
+    fn canonical_mixed_keys() {
+        // Bytewise order of the encoded keys: 10, 100, -1, "z", "aa", [100], [-1], false.
+        let pairs = vec![
+            (Value::Bool(false), 0),
+            (Value::Array(vec![Value::SignedInteger(-1)]), 0),
+            (Value::Array(vec![Value::UnsignedInteger(100)]), 0),
+            (Value::Text("aa".to_owned()), 0),
+            (Value::Text("z".to_owned()), 0),
+            (Value::SignedInteger(-1), 0),
+            (Value::UnsignedInteger(100), 0),
+            (Value::UnsignedInteger(10), 0),
+        ];
+        let mut bytes = Vec::new();
+        let mut ser = Serializer::new(&mut bytes).canonical();
+        serde::Serializer::collect_map(&mut ser, pairs).unwrap();
+        assert_eq!(
+            bytes,
+            b"\xa8\x0a\x00\x18\x64\x00\x20\x00\x61z\x00\x62aa\x00\x81\x18\x64\x00\x81\x20\x00\xf4\x00"
+                .to_vec()
+        );
+    }
+
+    #[derive(Serialize)]
+    struct Unordered {
+        zeta: u8,
+        alpha: Inner,
+        mu: f64,
+    }
+
+    #[derive(Serialize)]
+    struct Inner {
+        second: bool,
+        first: bool,
+    }
+
+    #[test]
+    fn canonical_struct() {
+        let value = Unordered {
+            zeta: 1,
+            alpha: Inner {
+                second: true,
+                first: false,
+            },
+            mu: 1.5,
+        };
+        let bytes = serde_cbor::to_vec_canonical(&value).unwrap();
+        assert_eq!(
+            bytes,
+            b"\xa3\x62mu\xf9\x3e\x00\x64zeta\x01\x65alpha\xa2\x65first\xf4\x66second\xf5".to_vec()
+        );
+        // The output doesn't depend on the field order.
+        let decoded: Value = serde_cbor::from_slice(&bytes).unwrap();
+        assert_eq!(serde_cbor::to_vec_canonical(&decoded).unwrap(), bytes);
+    }
+
+    #[test]
+    fn canonical_packed_struct() {
+        let value = Unordered {
+            zeta: 1,
+            alpha: Inner {
+                second: true,
+                first: false,
+            },
+            mu: 0.0,
+        };
+        let mut bytes = Vec::new();
+        value
+            .serialize(&mut Serializer::new(&mut bytes).packed_format().canonical())
+            .unwrap();
+        assert_eq!(
+            bytes,
+            b"\xa3\x00\x01\x01\xa2\x00\xf5\x01\xf4\x02\xf9\x00\x00".to_vec()
+        );
+    }
+
+    #[test]
+    fn canonical_duplicate_keys() {
+        let pairs = vec![(1, "a"), (1, "b")];
+        let mut bytes = Vec::new();
+        let mut ser = Serializer::new(&mut bytes).canonical();
+        let result = serde::Serializer::collect_map(&mut ser, pairs);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn canonical_unknown_length() {
+        let mut bytes = Vec::new();
+        let mut ser = Serializer::new(&mut bytes).canonical();
+        assert!(serde::Serializer::collect_seq(&mut ser, (0..3).filter(|_| true)).is_err());
+
+        // Maps of unknown length are counted.
+        let mut bytes = Vec::new();
+        let mut ser = Serializer::new(&mut bytes).canonical();
+        serde::Serializer::collect_map(&mut ser, vec![(2, 0), (1, 0)].into_iter().filter(|_| true))
+            .unwrap();
+        assert_eq!(bytes, [0xa2, 0x01, 0x00, 0x02, 0x00]);
+    }
+
+    // The keys of the examples in RFC 8949 section 4.2, in random order.
+    fn example_keys() -> Vec<Value> {
+        vec![
+            Value::Text("aa".to_owned()),
+            Value::Bool(false),
+            Value::UnsignedInteger(100),
+            Value::Array(vec![Value::SignedInteger(-1)]),
+            Value::Text("z".to_owned()),
+            Value::UnsignedInteger(10),
+            Value::Array(vec![Value::UnsignedInteger(100)]),
+            Value::SignedInteger(-1),
+        ]
+    }
+
+    fn encode_keys(keys: Vec<Value>, order: KeyOrder) -> Vec<u8> {
+        let map = keys.into_iter().map(|k| (k, Value::Null));
+        let mut bytes = Vec::new();
+        let mut ser = Serializer::new(&mut bytes).canonical().key_order(order);
+        serde::Serializer::collect_map(&mut ser, map).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn key_order_bytewise() {
+        // See: https://tools.ietf.org/html/rfc8949#section-4.2.1
+        let expected = b"\xa8\x0a\xf6\x18\x64\xf6\x20\xf6\x61z\xf6\x62aa\xf6\x81\x18\x64\xf6\x81\x20\xf6\xf4\xf6";
+        let bytes = encode_keys(example_keys(), KeyOrder::Bytewise);
+        assert_eq!(&bytes[..], &expected[..]);
+        assert_eq!(KeyOrder::default(), KeyOrder::Bytewise);
+    }
+
+    #[test]
+    fn key_order_length_first() {
+        // See: https://tools.ietf.org/html/rfc8949#section-4.2.3
+        let expected = b"\xa8\x0a\xf6\x20\xf6\xf4\xf6\x18\x64\xf6\x61z\xf6\x81\x20\xf6\x62aa\xf6\x81\x18\x64\xf6";
+        let bytes = encode_keys(example_keys(), KeyOrder::LengthFirst);
+        assert_eq!(&bytes[..], &expected[..]);
+    }
+
+    #[test]
+    fn key_order_value_map() {
+        let map: BTreeMap<Value, Value> = example_keys()
+            .into_iter()
+            .map(|k| (k, Value::Null))
+            .collect();
+        let value = Value::Map(map);
+
+        // Maps in a `Value` are already sorted bytewise.
+        let bytes = serde_cbor::to_vec(&value).unwrap();
+        assert_eq!(bytes, encode_keys(example_keys(), KeyOrder::Bytewise));
+
+        let mut length_first = Vec::new();
+        value
+            .serialize(
+                &mut Serializer::new(&mut length_first)
+                    .canonical()
+                    .key_order(KeyOrder::LengthFirst),
+            )
+            .unwrap();
+        assert_eq!(
+            length_first,
+            encode_keys(example_keys(), KeyOrder::LengthFirst)
+        );
+    }
+
+    #[test]
+    fn key_order_compare() {
+        assert_eq!(
+            KeyOrder::Bytewise.compare(b"\x18\x64", b"\x20"),
+            Ordering::Less
+        );
+        assert_eq!(
+            KeyOrder::LengthFirst.compare(b"\x18\x64", b"\x20"),
+            Ordering::Greater
+        );
+        assert_eq!(
+            KeyOrder::LengthFirst.compare(b"\x61z", b"\x81\x20"),
+            Ordering::Less
+        );
+    }
 }
@@ -0,0 +1,77 @@
+#[cfg(feature = "codec")]
+mod codec_tests {
+    use bytes::BytesMut;
+    use serde_cbor::codec::CborCodec;
+    use serde_cbor::Value;
+    use tokio_util::codec::{Decoder, Encoder};
+
+    #[test]
+    fn test_frames() {
+        let mut codec = CborCodec::<Vec<u32>>::new();
+        let mut input = BytesMut::new();
+        codec.encode(vec![1, 2, 1000], &mut input).unwrap();
+        codec.encode(&[3u32][..], &mut input).unwrap();
+        let mut src = BytesMut::new();
+        let mut items = Vec::new();
+        for byte in input.iter() {
+            src.extend_from_slice(&[*byte]);
+            if let Some(item) = codec.decode(&mut src).unwrap() {
+                items.push(item);
+            }
+        }
+        assert_eq!(items, vec![vec![1, 2, 1000], vec![3]]);
+        assert!(src.is_empty());
+        assert_eq!(codec.decode_eof(&mut src).unwrap(), None);
+    }
+
+    #[test]
+    fn test_data_error() {
+        let mut codec = CborCodec::<u8>::new();
+        let mut src = BytesMut::from(&b"\x63abc\x01"[..]);
+        assert!(codec.decode(&mut src).unwrap_err().is_data());
+        assert_eq!(codec.decode(&mut src).unwrap(), Some(1));
+    }
+
+    #[test]
+    fn test_offsets() {
+        // The offsets are those of the whole input, not of the frame.
+        let mut codec = CborCodec::<String>::new();
+        let mut src = BytesMut::from(&b"\x61a\x61\xff\x1c"[..]);
+        codec.decode(&mut src).unwrap();
+        assert_eq!(codec.decode(&mut src).unwrap_err().offset(), 3);
+        let err = codec.decode(&mut src).unwrap_err();
+        assert_eq!(err.to_string(), "unassigned type at offset 4");
+    }
+
+    #[test]
+    fn test_eof() {
+        let mut codec = CborCodec::<String>::new();
+        let mut src = BytesMut::from(&b"\x63ab"[..]);
+        assert_eq!(codec.decode(&mut src).unwrap(), None);
+        assert!(codec.decode_eof(&mut src).is_err());
+    }
+
+    #[test]
+    fn test_max_frame_len() {
+        // The header announces a byte string of 2^64 - 1 bytes.
+        let mut codec = CborCodec::<Vec<u8>>::new();
+        let mut src = BytesMut::from(&b"\x5b\xff\xff\xff\xff\xff\xff\xff\xff"[..]);
+        assert!(codec.decode(&mut src).unwrap_err().is_syntax());
+        assert!(src.capacity() < 1024);
+
+        let mut codec = CborCodec::<Vec<u8>>::new().max_frame_len(100);
+        let mut src = BytesMut::from(&b"\x82\x58\x40"[..]);
+        assert_eq!(codec.decode(&mut src).unwrap(), None);
+        src.extend_from_slice(&[0; 64]);
+        src.extend_from_slice(b"\x58\x40");
+        assert!(codec.decode(&mut src).unwrap_err().is_syntax());
+
+        let mut codec = CborCodec::<Vec<u8>>::new().max_frame_len(3);
+        let mut src = BytesMut::from(&b"\x43abc"[..]);
+        assert!(codec.decode(&mut src).unwrap_err().is_syntax());
+        let mut codec = CborCodec::<Value>::new().max_frame_len(4);
+        let mut src = BytesMut::from(&b"\x43abc"[..]);
+        let item = codec.decode(&mut src).unwrap();
+        assert_eq!(item, Some(Value::Bytes(b"abc".to_vec())));
+    }
+}
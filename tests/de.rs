@@ -43,6 +43,70 @@ fn test_indefinite_object() {
     assert_eq!(expected, actual);
 }
 
+#[test]
+fn test_strict_arguments() {
+    fn strict(input: &[u8]) -> Result<u64, serde_cbor::Error> {
+        let mut scratch = [0; 16];
+        let mut deserializer =
+            de::Deserializer::from_slice_with_scratch(input, &mut scratch).strict();
+        serde::Deserialize::deserialize(&mut deserializer)
+    }
+
+    assert_eq!(strict(b"\x17").unwrap(), 23);
+    assert_eq!(strict(b"\x18\x18").unwrap(), 24);
+    assert_eq!(strict(b"\x19\x01\x00").unwrap(), 256);
+    assert_eq!(
+        strict(b"\x1b\x00\x00\x00\x01\x00\x00\x00\x00").unwrap(),
+        1 << 32
+    );
+    for input in &[
+        &b"\x18\x05"[..],
+        b"\x19\x00\xff",
+        b"\x1a\x00\x00\xff\xff",
+        b"\x1b\x00\x00\x00\x00\xff\xff\xff\xff",
+        b"\xc1\x18\x05",
+        b"\xd8\x01\x05",
+    ] {
+        let err = strict(input).unwrap_err();
+        assert!(err.is_syntax());
+    }
+    // The offset points at the start of the offending item.
+    assert_eq!(strict(b"\xc1\x18\x05").unwrap_err().offset(), 1);
+    // Without strict mode all of them are accepted.
+    let value: u64 =
+        de::from_slice_with_scratch(b"\x1b\x00\x00\x00\x00\x00\x00\x00\x05", &mut []).unwrap();
+    assert_eq!(value, 5);
+}
+
+#[test]
+fn test_skip_value() {
+    // [_ h'01', "\xff"], tag(1, simple(16)), {_ "a": [1.5]}, 7
+    let input = b"\x82\x5f\x41\x01\xff\x61\xff\xc1\xf0\xbf\x61a\x81\xf9\x3e\x00\xff\x07";
+    let mut deserializer = de::Deserializer::from_slice_with_scratch(input, &mut []);
+    for _ in 0..3 {
+        deserializer.skip_value().unwrap();
+    }
+    let value: u8 = serde::Deserialize::deserialize(&mut deserializer).unwrap();
+    assert_eq!(value, 7);
+    deserializer.end().unwrap();
+
+    for input in &[
+        &b""[..],
+        b"\x82\x01",
+        b"\x9f\x01",
+        b"\xbf\x01\xff",
+        b"\x5f\x61a\xff",
+        b"\x5f\x5f\xff\xff",
+        b"\x62a",
+        b"\xff",
+        b"\x1c",
+        b"\xf8\x10",
+    ] {
+        let mut deserializer = de::Deserializer::from_slice_with_scratch(input, &mut []);
+        assert!(deserializer.skip_value().is_err());
+    }
+}
+
 #[cfg(feature = "std")]
 mod std_tests {
     use std::collections::BTreeMap;
@@ -224,7 +288,13 @@ mod std_tests {
     fn test_self_describing() {
         let value: error::Result<Value> =
             de::from_slice(&[0xd9, 0xd9, 0xf7, 0x66, 0x66, 0x6f, 0x6f, 0x62, 0x61, 0x72]);
-        assert_eq!(value.unwrap(), Value::Text("foobar".to_owned()));
+        assert_eq!(
+            value.unwrap(),
+            Value::Tag(55799, Box::new(Value::Text("foobar".to_owned())))
+        );
+        let value: error::Result<String> =
+            de::from_slice(&[0xd9, 0xd9, 0xf7, 0x66, 0x66, 0x6f, 0x6f, 0x62, 0x61, 0x72]);
+        assert_eq!(value.unwrap(), "foobar");
     }
 
     #[test]
@@ -346,6 +416,59 @@ mod std_tests {
         assert!(it.next().unwrap().unwrap_err().is_eof());
     }
 
+    #[test]
+    fn stream_deserializer_offsets() {
+        let slice = b"\x01\x66foobar\x9f\xff";
+        let mut it = Deserializer::from_slice(slice).into_iter::<Value>();
+        assert_eq!(it.next_with_offsets().unwrap().unwrap().1, 0..1);
+        assert_eq!(it.next_with_offsets().unwrap().unwrap().1, 1..8);
+        assert_eq!(it.next_with_offsets().unwrap().unwrap().1, 8..10);
+        assert!(it.next_with_offsets().is_none());
+        assert_eq!(it.valid_up_to(), 10);
+    }
+
+    #[test]
+    fn stream_deserializer_truncated() {
+        let slice = b"\x01\x82\x02\x66foo";
+        let mut it = Deserializer::from_slice(slice)
+            .into_iter::<Value>()
+            .allow_truncated();
+        assert_eq!(Value::UnsignedInteger(1), it.next().unwrap().unwrap());
+        assert!(it.next().is_none());
+        assert!(it.is_truncated());
+        assert_eq!(it.valid_up_to(), 1);
+
+        let mut it = Deserializer::from_reader(&slice[..])
+            .into_iter::<Value>()
+            .allow_truncated();
+        assert_eq!(it.by_ref().count(), 1);
+        assert!(it.is_truncated());
+        assert_eq!(it.valid_up_to(), 1);
+
+        // Syntax errors are still reported.
+        let slice = b"\x01\x82\x1c\x00";
+        let mut it = Deserializer::from_slice(slice)
+            .into_iter::<Value>()
+            .allow_truncated();
+        assert_eq!(Value::UnsignedInteger(1), it.next().unwrap().unwrap());
+        assert!(it.next().unwrap().unwrap_err().is_syntax());
+        assert!(!it.is_truncated());
+    }
+
+    #[test]
+    fn stream_deserializer_sequence_writer() {
+        let mut writer = serde_cbor::SequenceWriter::new(Vec::new());
+        for i in 0..3 {
+            writer.write(&vec![i; i]).unwrap();
+        }
+        let bytes = writer.into_inner();
+        let items: Vec<Vec<usize>> = Deserializer::from_slice(&bytes)
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(items, vec![vec![], vec![1], vec![2, 2]]);
+    }
+
     #[test]
     fn stream_deserializer_eof_in_indefinite() {
         let slice = b"\x7f\x65Mary \x64Had \x62a \x60\x67Little \x60\x64Lamb\xff";
@@ -736,4 +859,565 @@ mod std_tests {
         let err = serde_cbor::from_slice::<serde_cbor::Value>(&input).expect_err("recursion limit");
         assert!(err.is_syntax());
     }
+
+    fn strict<T>(input: &[u8]) -> Result<T, error::Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let mut deserializer = Deserializer::from_slice(input).strict();
+        let value = serde::Deserialize::deserialize(&mut deserializer)?;
+        deserializer.end()?;
+        Ok(value)
+    }
+
+    #[test]
+    fn test_strict_lengths() {
+        assert_eq!(
+            strict::<String>(b"\x78\x18abcdefghijklmnopqrstuvwx")
+                .unwrap()
+                .len(),
+            24
+        );
+        let err = strict::<String>(b"\x78\x01a").unwrap_err();
+        assert_eq!(err.offset(), 0);
+        let err = strict::<Value>(b"\x82\x41a\x59\x00\x01a").unwrap_err();
+        assert_eq!(err.offset(), 3);
+        let err = strict::<Vec<u32>>(b"\x98\x01\x01").unwrap_err();
+        assert_eq!(err.offset(), 0);
+        let err = strict::<BTreeMap<u32, u32>>(b"\xb8\x01\x01\x01").unwrap_err();
+        assert_eq!(err.offset(), 0);
+    }
+
+    #[test]
+    fn test_strict_indefinite() {
+        for input in &[
+            &b"\x5f\x41a\xff"[..],
+            b"\x7f\x61a\xff",
+            b"\x9f\x01\xff",
+            b"\xbf\x01\x01\xff",
+        ] {
+            let err = strict::<Value>(input).unwrap_err();
+            assert!(err.is_syntax());
+            assert_eq!(err.offset(), 0);
+        }
+        let err = strict::<Vec<Value>>(b"\x82\x01\x9f\xff").unwrap_err();
+        assert_eq!(err.offset(), 2);
+    }
+
+    #[test]
+    fn test_strict_floats() {
+        assert_eq!(strict::<f64>(b"\xf9\x3e\x00").unwrap(), 1.5);
+        assert_eq!(strict::<f64>(b"\xfa\x47\xc3\x50\x00").unwrap(), 100000.0);
+        assert_eq!(
+            strict::<f64>(b"\xfb\x3f\xf1\x99\x99\x99\x99\x99\x9a").unwrap(),
+            1.1
+        );
+        assert!(strict::<f64>(b"\xf9\x7e\x00").unwrap().is_nan());
+        for input in &[
+            &b"\xfa\x3f\xc0\x00\x00"[..],
+            b"\xfb\x3f\xf8\x00\x00\x00\x00\x00\x00",
+            b"\xfb\x40\xf8\x6a\x00\x00\x00\x00\x00",
+            b"\xfa\x7f\xc0\x00\x00",
+            b"\xfb\x7f\xf0\x00\x00\x00\x00\x00\x00",
+        ] {
+            let err = strict::<f64>(input).unwrap_err();
+            assert!(err.is_syntax());
+            assert_eq!(err.offset(), 0);
+        }
+    }
+
+    #[test]
+    fn test_strict_map_keys() {
+        // {10: 0, 100: 0, -1: 0, "z": 0, "aa": 0, [100]: 0, [-1]: 0, false: 0}
+        let sorted = b"\xa8\x0a\x00\x18\x64\x00\x20\x00\x61z\x00\x62aa\x00\x81\x18\x64\x00\x81\x20\x00\xf4\x00";
+        let value = strict::<Value>(sorted).unwrap();
+        assert_eq!(serde_cbor::to_vec_canonical(&value).unwrap(), &sorted[..]);
+
+        let unsorted = b"\xa2\x61b\x00\x61a\x00";
+        let err = strict::<BTreeMap<String, u8>>(unsorted).unwrap_err();
+        assert_eq!(err.offset(), 4);
+        assert!(err.to_string().contains("not sorted"));
+        strict::<BTreeMap<String, u8>>(b"\xa2\x61a\x00\x61b\x00").unwrap();
+
+        let duplicate = b"\xa2\x61a\x00\x61a\x01";
+        let err = strict::<Value>(duplicate).unwrap_err();
+        assert_eq!(err.offset(), 4);
+        assert!(err.to_string().contains("duplicate"));
+
+        // Keys that are maps themselves are checked as well.
+        let nested = b"\xa2\xa1\x01\x00\x00\xa1\x02\x00\x00";
+        strict::<Value>(nested).unwrap();
+        let nested = b"\xa1\xa2\x02\x00\x01\x00\x00";
+        assert_eq!(strict::<Value>(nested).unwrap_err().offset(), 4);
+
+        // Length-first order puts -1 before 100.
+        let length_first = b"\xa2\x20\x00\x18\x64\x00";
+        assert!(strict::<Value>(length_first).is_err());
+        let mut deserializer = Deserializer::from_slice(length_first)
+            .strict()
+            .key_order(serde_cbor::ser::KeyOrder::LengthFirst);
+        <Value as serde::Deserialize>::deserialize(&mut deserializer).unwrap();
+    }
+
+    #[test]
+    fn test_strict_struct() {
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct Signed {
+            payload: Vec<u8>,
+            alg: i32,
+        }
+
+        let value = Signed {
+            payload: vec![1, 2, 3],
+            alg: -7,
+        };
+        let bytes = serde_cbor::to_vec_canonical(&value).unwrap();
+        assert_eq!(strict::<Signed>(&bytes).unwrap(), value);
+        // Fields in declaration order aren't sorted.
+        let bytes = to_vec(&value).unwrap();
+        let err = strict::<Signed>(&bytes).unwrap_err();
+        assert!(err.is_syntax());
+    }
+
+    fn deny_duplicates<T>(input: &[u8]) -> Result<T, error::Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let mut deserializer = Deserializer::from_slice(input).deny_duplicate_keys();
+        let value = serde::Deserialize::deserialize(&mut deserializer)?;
+        deserializer.end()?;
+        Ok(value)
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        use std::collections::HashMap;
+
+        // {"a": 1, "b": 2, "a": 3}
+        let input = b"\xa3\x61a\x01\x61b\x02\x61a\x03";
+        let map: HashMap<String, u8> = de::from_slice(input).unwrap();
+        assert_eq!(map["a"], 3);
+
+        let err = deny_duplicates::<HashMap<String, u8>>(input).unwrap_err();
+        assert_eq!(err.offset(), 7);
+        assert!(err.is_syntax());
+        assert_eq!(err.to_string(), "duplicate map key at offset 7");
+        assert!(deny_duplicates::<BTreeMap<String, u8>>(input).is_err());
+        assert!(deny_duplicates::<Value>(input).is_err());
+
+        // Keys don't have to be sorted.
+        let map = deny_duplicates::<HashMap<String, u8>>(b"\xa2\x61b\x01\x61a\x02").unwrap();
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn test_duplicate_keys_indefinite_map() {
+        let input = b"\xbf\x01\x00\x02\x00\x01\x00\xff";
+        assert_eq!(deny_duplicates::<Value>(input).unwrap_err().offset(), 5);
+        deny_duplicates::<Value>(b"\xbf\x01\x00\x02\x00\xff").unwrap();
+    }
+
+    #[test]
+    fn test_duplicate_keys_many() {
+        use std::collections::HashMap;
+
+        fn push_u32(input: &mut Vec<u8>, major: u8, value: u32) {
+            input.push(major << 5 | 26);
+            input.extend((0..4).rev().map(|i| (value >> (8 * i)) as u8));
+        }
+
+        // A map with 100000 keys in descending order.
+        let mut input = Vec::new();
+        push_u32(&mut input, 5, 100_000);
+        for key in (0..100_000).rev() {
+            push_u32(&mut input, 0, key);
+            input.push(0x00);
+        }
+        match deny_duplicates::<Value>(&input).unwrap() {
+            Value::Map(ref map) => assert_eq!(map.len(), 100_000),
+            value => panic!("unexpected value: {:?}", value),
+        }
+        let map = deny_duplicates::<HashMap<u32, u8>>(&input).unwrap();
+        assert_eq!(map.len(), 100_000);
+
+        // The last key repeats the first one.
+        let offset = input.len() - 6;
+        input.truncate(offset);
+        push_u32(&mut input, 0, 99_999);
+        input.push(0x00);
+        let err = deny_duplicates::<Value>(&input).unwrap_err();
+        assert_eq!(err.offset(), offset as u64);
+    }
+
+    #[test]
+    fn test_duplicate_keys_different_encodings() {
+        for input in &[
+            // 1 and 1 as a two byte integer.
+            &b"\xa2\x01\x00\x19\x00\x01\x00"[..],
+            // "ab" as a definite and an indefinite string.
+            b"\xa2\x62ab\x00\x7f\x61a\x61b\xff\x00",
+            // 1.5 as a half and a double precision float.
+            b"\xa2\xf9\x3e\x00\x00\xfb\x3f\xf8\x00\x00\x00\x00\x00\x00\x00",
+            // [1, 2] as a definite and an indefinite array.
+            b"\xa2\x82\x01\x02\x00\x9f\x01\x02\xff\x00",
+            // tag(1, 1) with a one byte tag number.
+            b"\xa2\xc1\x01\x00\xd8\x01\x01\x00",
+        ] {
+            let err = deny_duplicates::<Value>(input).unwrap_err();
+            assert!(err.to_string().starts_with("duplicate map key"));
+        }
+        // Integers and floats are different keys, as are simple values.
+        deny_duplicates::<Value>(b"\xa2\x01\x00\xf9\x3c\x00\x00").unwrap();
+        deny_duplicates::<Value>(b"\xa2\xf8\x20\x00\xf8\x21\x00").unwrap();
+    }
+
+    #[test]
+    fn test_duplicate_keys_struct() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Header {
+            alg: i32,
+        }
+
+        // {"alg": -7, "kid": 1, "kid": 2}
+        let input = b"\xa3\x63alg\x26\x63kid\x01\x63kid\x02";
+        assert_eq!(de::from_slice::<Header>(input).unwrap(), Header { alg: -7 });
+        let err = deny_duplicates::<Header>(input).unwrap_err();
+        assert_eq!(err.offset(), 11);
+
+        // Nested maps are checked as well.
+        let input = b"\xa1\x61h\xa2\x63alg\x26\x63alg\x27";
+        assert!(deny_duplicates::<BTreeMap<String, Header>>(input).is_err());
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Known {
+        a: u8,
+    }
+
+    #[test]
+    fn test_skip_unknown_fields() {
+        // {"b": [_ simple(16), "\xff"], "a": 1, "c": {_ 1: h'00'}}
+        let input = b"\xa3\x61b\x9f\xf0\x61\xff\xff\x61a\x01\x61c\xbf\x01\x41\x00\xff";
+        assert_eq!(de::from_slice::<Known>(input).unwrap(), Known { a: 1 });
+        assert_eq!(
+            de::from_reader::<Known, _>(&input[..]).unwrap(),
+            Known { a: 1 }
+        );
+        // Ignored items are still checked in strict mode.
+        assert!(strict::<Known>(input).is_err());
+    }
+
+    #[test]
+    fn test_skip_value_reader() {
+        let mut input = to_vec(&vec![0u8; 100_000]).unwrap();
+        input.extend(to_vec(&"x".repeat(100_000)).unwrap());
+        input.push(0x05);
+        let mut deserializer = Deserializer::from_reader(&input[..]);
+        deserializer.skip_value().unwrap();
+        deserializer.skip_value().unwrap();
+        assert_eq!(deserializer.byte_offset(), input.len() - 1);
+        let value: u8 = serde_de::Deserialize::deserialize(&mut deserializer).unwrap();
+        assert_eq!(value, 5);
+    }
+
+    #[test]
+    fn test_buf_reader() {
+        use std::io::{BufReader, Read};
+
+        let mut input = to_vec(&(-1000i32, "x".repeat(50), 1.5f64)).unwrap();
+        input.extend(b"\x9f\x7f\x62ab\x61c\xff\x5f\x41\x01\xff\xff\x01");
+        input.extend(b"rest");
+        for &capacity in &[1, 3, 8, 1024] {
+            let mut reader = BufReader::with_capacity(capacity, &input[..]);
+            let mut deserializer = Deserializer::from_buf_reader(&mut reader);
+            let value: (i32, String, f64) =
+                serde_de::Deserialize::deserialize(&mut deserializer).unwrap();
+            assert_eq!(value, (-1000, "x".repeat(50), 1.5));
+            let value: Value = serde_de::Deserialize::deserialize(&mut deserializer).unwrap();
+            assert_eq!(
+                value,
+                Value::Array(vec![Value::Text("abc".to_owned()), Value::Bytes(vec![1])])
+            );
+            deserializer.skip_value().unwrap();
+            assert_eq!(deserializer.byte_offset(), input.len() - 4);
+            // The bytes after the data are left in the reader.
+            let mut rest = String::new();
+            reader.read_to_string(&mut rest).unwrap();
+            assert_eq!(rest, "rest");
+        }
+
+        let value: Vec<u8> = de::from_buf_reader(BufReader::new(&b"\x82\x01\x02"[..])).unwrap();
+        assert_eq!(value, [1, 2]);
+        let trailing = b"\x82\x01\x02\x03";
+        let err = de::from_buf_reader::<Vec<u8>, _>(&trailing[..]).unwrap_err();
+        assert_eq!(
+            err.offset(),
+            de::from_slice::<Vec<u8>>(trailing).unwrap_err().offset()
+        );
+        for end in 1..13 {
+            let reader = BufReader::with_capacity(4, &input[..end]);
+            let err = de::from_buf_reader::<(i32, String, f64), _>(reader).unwrap_err();
+            assert!(err.is_eof());
+            assert_eq!(err.offset(), end as u64);
+        }
+    }
+
+    fn limited<T>(input: &[u8], limits: de::DecodeLimits) -> Result<T, error::Error>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let mut deserializer = Deserializer::from_slice(input).limits(limits);
+        let value = serde::Deserialize::deserialize(&mut deserializer)?;
+        deserializer.end()?;
+        Ok(value)
+    }
+
+    #[test]
+    fn test_limits() {
+        let limits = de::DecodeLimits::new();
+        for &(input, ok, exceeded, message) in &[
+            (
+                &b"\x81\x81\x01"[..],
+                limits.max_depth(2),
+                limits.max_depth(1),
+                "recursion limit exceeded at offset 2",
+            ),
+            (
+                b"\xc1\x81\xc1\x01",
+                limits.max_tag_depth(2),
+                limits.max_tag_depth(1),
+                "tag nesting limit exceeded at offset 3",
+            ),
+            (
+                b"\x63abc",
+                limits.max_length(3),
+                limits.max_length(2),
+                "string length limit exceeded at offset 1",
+            ),
+            (
+                b"\x7f\x62ab\x61c\xff",
+                limits.max_length(3),
+                limits.max_length(2),
+                "string length limit exceeded at offset 5",
+            ),
+            (
+                b"\x5f\x41a\x41b\x41c\xff",
+                limits.max_chunks(3),
+                limits.max_chunks(2),
+                "chunk count limit exceeded at offset 6",
+            ),
+            (
+                b"\x83\x01\x02\x03",
+                limits.max_elements(3),
+                limits.max_elements(2),
+                "element count limit exceeded at offset 1",
+            ),
+            (
+                b"\x9f\x01\x02\x03\xff",
+                limits.max_elements(3),
+                limits.max_elements(2),
+                "element count limit exceeded at offset 3",
+            ),
+            (
+                b"\xbf\x01\x02\x03\x04\xff",
+                limits.max_elements(2),
+                limits.max_elements(1),
+                "element count limit exceeded at offset 3",
+            ),
+            (
+                b"\x82\x62ab\x62cd",
+                limits.max_total_length(4),
+                limits.max_total_length(3),
+                "total string length limit exceeded at offset 5",
+            ),
+        ] {
+            limited::<Value>(input, ok).unwrap();
+            let err = limited::<Value>(input, exceeded).unwrap_err();
+            assert!(err.is_syntax());
+            assert_eq!(err.to_string(), message);
+
+            let mut deserializer = Deserializer::from_slice(input).limits(exceeded);
+            if message.starts_with("total") {
+                // Skipped strings don't count towards the total length limit.
+                deserializer.skip_value().unwrap();
+            } else {
+                assert_eq!(deserializer.skip_value().unwrap_err().to_string(), message);
+            }
+
+            let mut deserializer = Deserializer::from_buf_reader(input).limits(exceeded);
+            let err = <Value as serde::Deserialize>::deserialize(&mut deserializer).unwrap_err();
+            assert_eq!(err.to_string(), message);
+
+            let mut deserializer = Deserializer::from_slice(input).limits(exceeded);
+            assert_eq!(deserializer.read_value().unwrap_err().to_string(), message);
+        }
+
+        // The default limit on the nesting depth, the same as in earlier versions.
+        let mut input = vec![0x81; 127];
+        input.push(0x01);
+        limited::<Value>(&input, limits).unwrap();
+        serde_cbor::from_slice::<Value>(&input).unwrap();
+        input.insert(0, 0x81);
+        assert!(limited::<Value>(&input, limits).is_err());
+        assert!(serde_cbor::from_slice::<Value>(&input).is_err());
+        limited::<Value>(&input, limits.max_depth(200)).unwrap();
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Server {
+        host: String,
+        port: u16,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Config {
+        name: String,
+        servers: Vec<Server>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Settings {
+        config: Config,
+    }
+
+    #[derive(Debug, Deserialize)]
+    enum Shape {
+        Circle { radius: u8 },
+    }
+
+    fn tracked<T>(input: &[u8]) -> error::Error
+    where
+        T: serde_de::DeserializeOwned + std::fmt::Debug,
+    {
+        let mut deserializer = Deserializer::from_slice(input).track_path();
+        T::deserialize(&mut deserializer).unwrap_err()
+    }
+
+    #[test]
+    fn test_track_path() {
+        let mut settings = b"\xa1\x66config\xa2\x64name\x61n\x67servers\x84".to_vec();
+        for port in &[&b"\x18\x50"[..], b"\x18\x50", b"\x18\x50", b"\xf6"] {
+            settings.extend_from_slice(b"\xa2\x64host\x61h\x64port");
+            settings.extend_from_slice(port);
+        }
+        let err = tracked::<Settings>(&settings);
+        assert_eq!(err.path(), Some(".config.servers[3].port"));
+        assert_eq!(
+            err.to_string(),
+            "invalid type: null, expected u16 at .config.servers[3].port"
+        );
+        // Without tracking there is no path.
+        let err = serde_cbor::from_slice::<Settings>(&settings).unwrap_err();
+        assert_eq!(err.path(), None);
+        assert_eq!(err.to_string(), "invalid type: null, expected u16");
+
+        // Tracking doesn't change the result of valid input.
+        let len = settings.len();
+        settings[len - 1] = 0x01;
+        let mut deserializer = Deserializer::from_slice(&settings).track_path();
+        let result: Settings = serde_de::Deserialize::deserialize(&mut deserializer).unwrap();
+        assert_eq!(result.config.name, "n");
+        assert_eq!(result.config.servers[3].host, "h");
+        assert_eq!(result.config.servers[3].port, 1);
+
+        // The fields of packed structs are numbered.
+        let err = tracked::<Settings>(b"\xa1\x00\xa2\x00\x61n\x01\x81\xa2\x00\x61h\x01\xf6");
+        assert_eq!(err.path(), Some("[0][1][0][1]"));
+
+        // Errors outside of an element or value get the path of the map, and syntax errors
+        // report their offset as well.
+        let err = tracked::<Settings>(b"\xa1\x66config\xa1\x64name\x61n");
+        assert_eq!(err.to_string(), "missing field `servers` at .config");
+        let err = tracked::<Settings>(b"\xa1\x66config\xa1\x64name\x61\xff");
+        assert_eq!(err.to_string(), "invalid UTF-8 at .config.name, offset 15");
+        let err = tracked::<Settings>(b"\xa1\x66config\xa1\x64nime\x61n");
+        assert_eq!(err.path(), Some(".config"));
+        let err = tracked::<Settings>(b"\xa1\x66config\xf6");
+        assert_eq!(err.path(), Some(".config"));
+        let err = tracked::<Settings>(b"\xf6");
+        assert_eq!(err.path(), None);
+
+        for &(input, path) in &[
+            (&b"\xa1\x21\x81\x61x"[..], "[-2][0]"),
+            (b"\xa1\x41\x01\x81\x61x", "[h'01'][0]"),
+        ] {
+            let err = tracked::<BTreeMap<Value, Vec<u8>>>(input);
+            assert_eq!(err.path(), Some(path));
+        }
+        let err = tracked::<BTreeMap<String, Vec<u8>>>(b"\xa1\x61a\x9f\x01\x02\x61x\xff");
+        assert_eq!(err.path(), Some(".a[2]"));
+        let shape: Shape = serde_cbor::from_slice(b"\xa1\x66Circle\xa1\x66radius\x01").unwrap();
+        match shape {
+            Shape::Circle { radius } => assert_eq!(radius, 1),
+        }
+        let err = tracked::<Shape>(b"\xa1\x66Circle\xa1\x66radius\x61x");
+        assert_eq!(err.path(), Some(".Circle.radius"));
+        let err = tracked::<Vec<Shape>>(b"\x81\x82\x66Circle\xa1\x66radius\x61x");
+        assert_eq!(err.path(), Some("[0][1].radius"));
+
+        // Checked keys are still checked.
+        let input = b"\xa2\x61a\x01\x61a\x02";
+        let mut deserializer = Deserializer::from_slice(input)
+            .track_path()
+            .deny_duplicate_keys();
+        let err = <BTreeMap<String, u8> as serde_de::Deserialize>::deserialize(&mut deserializer)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "duplicate map key at offset 4");
+    }
+
+    #[test]
+    fn test_error_codes() {
+        use serde_cbor::error::ErrorCode;
+
+        let err = de::from_slice::<u8>(b"\x01\x02").unwrap_err();
+        match *err.code() {
+            ErrorCode::TrailingData => {}
+            _ => panic!("{:?}", err),
+        }
+        let err = de::from_slice::<Value>(&[0x81; 200]).unwrap_err();
+        match *err.code() {
+            ErrorCode::RecursionLimitExceeded => {}
+            _ => panic!("{:?}", err),
+        }
+        let mut deserializer = Deserializer::from_slice(b"\x82\x00\x01").disable_legacy_enums();
+        let err = <Shape as serde_de::Deserialize>::deserialize(&mut deserializer).unwrap_err();
+        match *err.code() {
+            ErrorCode::WrongEnumFormat => {}
+            _ => panic!("{:?}", err),
+        }
+
+        let err = de::from_slice::<Vec<u32>>(b"\x82\x01\xf6").unwrap_err();
+        match *err.code() {
+            ErrorCode::InvalidType => {}
+            _ => panic!("{:?}", err),
+        }
+        assert_eq!(
+            (err.unexpected(), err.expected()),
+            (Some("null"), Some("u32"))
+        );
+        assert!(err.is_data());
+        assert_eq!(err.to_string(), "invalid type: null, expected u32");
+        let err = de::from_slice::<u8>(b"\x19\x01\x2c").unwrap_err();
+        match *err.code() {
+            ErrorCode::InvalidValue => {}
+            _ => panic!("{:?}", err),
+        }
+        assert_eq!(err.unexpected(), Some("integer `300`"));
+        assert_eq!(err.expected(), Some("u8"));
+        assert_eq!(err.to_string(), "invalid value: integer `300`, expected u8");
+        let err = de::from_slice::<Settings>(b"\xa0").unwrap_err();
+        match *err.code() {
+            ErrorCode::Message => {}
+            _ => panic!("{:?}", err),
+        }
+        assert_eq!(err.custom_message(), Some("missing field `config`"));
+
+        let err = serde_cbor::to_writer(&mut [0u8; 2][..], &"abc").unwrap_err();
+        match *err.code() {
+            ErrorCode::Io => {}
+            _ => panic!("{:?}", err),
+        }
+        let kind = err.io_error().map(|err| err.kind());
+        assert_eq!(kind, Some(std::io::ErrorKind::WriteZero));
+    }
 }
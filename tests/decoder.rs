@@ -0,0 +1,135 @@
+use serde_cbor::de::{MutSliceRead, SliceReadFixed};
+use serde_cbor::decoder::{Decoder, EitherLifetime, Event};
+
+#[test]
+fn test_events() {
+    // [_ {"a": h'0102', "b": 1(-2)}, 1.5, true, null, undefined, simple(16), simple(255)]
+    let bytes = b"\x9f\xa2\x61a\x42\x01\x02\x61b\xc1\x21\xf9\x3e\x00\xf5\xf6\xf7\xf0\xf8\xff\xff";
+    let mut decoder = Decoder::new(SliceReadFixed::new(bytes, &mut []));
+    assert_eq!(decoder.next_event().unwrap(), Some(Event::ArrayStart(None)));
+    assert_eq!(
+        decoder.next_event().unwrap(),
+        Some(Event::MapStart(Some(2)))
+    );
+    assert_eq!(decoder.next_event().unwrap(), Some(Event::Text(Some(1))));
+    assert_eq!(borrowed(decoder.read_bytes(1).unwrap()), b"a");
+    assert_eq!(decoder.next_event().unwrap(), Some(Event::Bytes(Some(2))));
+    assert_eq!(borrowed(decoder.read_bytes(2).unwrap()), [1, 2]);
+    assert_eq!(decoder.next_event().unwrap(), Some(Event::Text(Some(1))));
+    assert_eq!(borrowed(decoder.read_bytes(1).unwrap()), b"b");
+    assert_eq!(decoder.next_event().unwrap(), Some(Event::Tag(1)));
+    assert_eq!(decoder.next_event().unwrap(), Some(Event::NegativeInt(1)));
+    assert_eq!(decoder.next_event().unwrap(), Some(Event::Float(1.5)));
+    assert_eq!(decoder.next_event().unwrap(), Some(Event::Bool(true)));
+    assert_eq!(decoder.next_event().unwrap(), Some(Event::Null));
+    assert_eq!(decoder.next_event().unwrap(), Some(Event::Undefined));
+    assert_eq!(decoder.next_event().unwrap(), Some(Event::Simple(16)));
+    assert_eq!(decoder.next_event().unwrap(), Some(Event::Simple(255)));
+    assert_eq!(decoder.next_event().unwrap(), Some(Event::Break));
+    assert_eq!(decoder.offset(), bytes.len() as u64);
+    assert_eq!(decoder.next_event().unwrap(), None);
+}
+
+fn borrowed<'de>(bytes: EitherLifetime<'_, 'de>) -> &'de [u8] {
+    match bytes {
+        EitherLifetime::Long(bytes) => bytes,
+        EitherLifetime::Short(_) => panic!("expected borrowed bytes"),
+    }
+}
+
+#[test]
+fn test_arguments() {
+    let bytes = b"\x18\x18\x39\x01\x00\x5a\x00\x01\x00\x00\xdb\x00\x00\x00\x01\x00\x00\x00\x00";
+    let mut decoder = Decoder::new(SliceReadFixed::new(bytes, &mut []));
+    assert_eq!(decoder.next_event().unwrap(), Some(Event::UnsignedInt(24)));
+    assert_eq!(decoder.next_event().unwrap(), Some(Event::NegativeInt(256)));
+    assert_eq!(
+        decoder.next_event().unwrap(),
+        Some(Event::Bytes(Some(65536)))
+    );
+    assert_eq!(decoder.next_event().unwrap(), Some(Event::Tag(1 << 32)));
+}
+
+#[test]
+fn test_floats() {
+    let bytes = b"\xf9\x7c\x00\xfa\x47\xc3\x50\x00\xfb\x3f\xf1\x99\x99\x99\x99\x99\x9a";
+    let mut decoder = Decoder::new(SliceReadFixed::new(bytes, &mut []));
+    let infinity = 1.0 / 0.0;
+    assert_eq!(decoder.next_event().unwrap(), Some(Event::Float(infinity)));
+    assert_eq!(decoder.next_event().unwrap(), Some(Event::Float(100000.0)));
+    assert_eq!(decoder.next_event().unwrap(), Some(Event::Float(1.1)));
+}
+
+#[test]
+fn test_indefinite_string() {
+    let mut bytes = *b"\x7f\x62ab\x61c\xff";
+    let mut decoder = Decoder::new(MutSliceRead::new(&mut bytes));
+    assert_eq!(decoder.next_event().unwrap(), Some(Event::Text(None)));
+    assert_eq!(decoder.next_event().unwrap(), Some(Event::Text(Some(2))));
+    assert_eq!(borrowed(decoder.read_bytes(2).unwrap()), b"ab");
+    assert_eq!(decoder.next_event().unwrap(), Some(Event::Text(Some(1))));
+    assert_eq!(borrowed(decoder.read_bytes(1).unwrap()), b"c");
+    assert_eq!(decoder.next_event().unwrap(), Some(Event::Break));
+}
+
+#[test]
+fn test_errors() {
+    for bytes in &[
+        &b"\x1c"[..],
+        b"\x3f",
+        b"\xdf",
+        b"\xfc",
+        b"\xf8\x18",
+        b"\x19\x01",
+        b"\x5b\x00",
+    ] {
+        let mut decoder = Decoder::new(SliceReadFixed::new(bytes, &mut []));
+        assert!(decoder.next_event().is_err());
+    }
+
+    let mut decoder = Decoder::new(SliceReadFixed::new(b"\x44\x01", &mut []));
+    assert_eq!(decoder.next_event().unwrap(), Some(Event::Bytes(Some(4))));
+    let err = decoder.read_bytes(4).unwrap_err();
+    assert!(err.is_eof());
+}
+
+#[cfg(feature = "std")]
+mod std_tests {
+    use serde_cbor::de::IoRead;
+    use serde_cbor::decoder::{Decoder, EitherLifetime, Event};
+
+    #[test]
+    fn test_from_slice() {
+        let bytes = serde_cbor::to_vec(&("hello", -1000i32)).unwrap();
+        let mut decoder = Decoder::from_slice(&bytes);
+        assert_eq!(
+            decoder.next_event().unwrap(),
+            Some(Event::ArrayStart(Some(2)))
+        );
+        assert_eq!(decoder.next_event().unwrap(), Some(Event::Text(Some(5))));
+        match decoder.read_bytes(5).unwrap() {
+            EitherLifetime::Long(bytes) => assert_eq!(bytes, b"hello"),
+            EitherLifetime::Short(_) => panic!("expected borrowed bytes"),
+        }
+        assert_eq!(decoder.next_event().unwrap(), Some(Event::NegativeInt(999)));
+        assert_eq!(decoder.next_event().unwrap(), None);
+    }
+
+    #[test]
+    fn test_reader() {
+        let bytes = b"\x82\x43abc\xf4";
+        let mut decoder = Decoder::new(IoRead::new(&bytes[..]));
+        assert_eq!(
+            decoder.next_event().unwrap(),
+            Some(Event::ArrayStart(Some(2)))
+        );
+        assert_eq!(decoder.next_event().unwrap(), Some(Event::Bytes(Some(3))));
+        match decoder.read_bytes(3).unwrap() {
+            EitherLifetime::Short(bytes) => assert_eq!(bytes, b"abc"),
+            EitherLifetime::Long(_) => panic!("expected a copy"),
+        }
+        assert_eq!(decoder.next_event().unwrap(), Some(Event::Bool(false)));
+        assert_eq!(decoder.offset(), 6);
+        assert_eq!(decoder.next_event().unwrap(), None);
+    }
+}
@@ -0,0 +1,314 @@
+use serde_cbor::de::SliceReadFixed;
+use serde_cbor::diag::{parse_into, write_item};
+use serde_cbor::ser::{Serializer, SliceWrite};
+
+#[test]
+fn test_write_item_no_std() {
+    struct Buffer {
+        data: [u8; 32],
+        len: usize,
+    }
+
+    impl core::fmt::Write for Buffer {
+        fn write_str(&mut self, s: &str) -> core::fmt::Result {
+            let end = self.len + s.len();
+            if end > self.data.len() {
+                return Err(core::fmt::Error);
+            }
+            self.data[self.len..end].copy_from_slice(s.as_bytes());
+            self.len = end;
+            Ok(())
+        }
+    }
+
+    let mut read = SliceReadFixed::new(b"\x82\x01\xc1\x61\x61\x0a", &mut []);
+    let mut out = Buffer {
+        data: [0; 32],
+        len: 0,
+    };
+    write_item(&mut read, &mut out).unwrap();
+    assert_eq!(&out.data[..out.len], b"[1, 1(\"a\")]");
+    write_item(&mut read, &mut out).unwrap();
+    assert_eq!(&out.data[..out.len], b"[1, 1(\"a\")]10");
+}
+
+#[test]
+fn test_parse_into_no_std() {
+    let mut buf = [0u8; 16];
+    let mut serializer = Serializer::new(SliceWrite::new(&mut buf));
+    parse_into("[_ 1, h'ff', \"a\"]", &mut serializer).unwrap();
+    let writer = serializer.into_inner();
+    let end = writer.bytes_written();
+    assert_eq!(&writer.into_inner()[..end], b"\x9f\x01\x41\xff\x61\x61\xff");
+}
+
+#[cfg(feature = "std")]
+mod std_tests {
+    use serde_cbor::de::SliceRead;
+    use serde_cbor::diag::{parse, parse_to_vec, to_string, write_item};
+    use serde_cbor::Value;
+
+    fn diag(bytes: &[u8]) -> String {
+        to_string(bytes).unwrap()
+    }
+
+    #[test]
+    fn test_rfc_examples() {
+        let cases: &[(&[u8], &str)] = &[
+            (b"\x00", "0"),
+            (b"\x17", "23"),
+            (b"\x18\x18", "24"),
+            (b"\x1b\xff\xff\xff\xff\xff\xff\xff\xff", "18446744073709551615"),
+            (b"\x3b\xff\xff\xff\xff\xff\xff\xff\xff", "-18446744073709551616"),
+            (b"\x38\x63", "-100"),
+            (
+                b"\xc2\x49\x01\x00\x00\x00\x00\x00\x00\x00\x00",
+                "2(h'010000000000000000')",
+            ),
+            (b"\xf9\x00\x00", "0.0"),
+            (b"\xf9\x80\x00", "-0.0"),
+            (b"\xf9\x3e\x00", "1.5"),
+            (b"\xf9\x7b\xff", "65504.0"),
+            (b"\xfa\x47\xc3\x50\x00", "100000.0"),
+            (b"\xfa\x7f\x7f\xff\xff", "3.4028234663852886e+38"),
+            (b"\xfb\x7e\x37\xe4\x3c\x88\x00\x75\x9c", "1.0e+300"),
+            (b"\xf9\x00\x01", "5.960464477539063e-8"),
+            (b"\xfb\xc0\x10\x66\x66\x66\x66\x66\x66", "-4.1"),
+            (b"\xf9\x7c\x00", "Infinity"),
+            (b"\xf9\x7e\x00", "NaN"),
+            (b"\xf9\xfc\x00", "-Infinity"),
+            (b"\xf4", "false"),
+            (b"\xf5", "true"),
+            (b"\xf6", "null"),
+            (b"\xf7", "undefined"),
+            (b"\xf0", "simple(16)"),
+            (b"\xf8\xff", "simple(255)"),
+            (
+                b"\xc0\x74\x32\x30\x31\x33\x2d\x30\x33\x2d\x32\x31\x54\x32\x30\x3a\x30\x34\x3a\x30\x30\x5a",
+                "0(\"2013-03-21T20:04:00Z\")",
+            ),
+            (b"\xd8\x18\x45\x64\x49\x45\x54\x46", "24(h'6449455446')"),
+            (b"\x40", "h''"),
+            (b"\x44\x01\x02\x03\x04", "h'01020304'"),
+            (b"\x60", "\"\""),
+            (b"\x62\x22\x5c", "\"\\\"\\\\\""),
+            (b"\x63\xe6\xb0\xb4", "\"\u{6c34}\""),
+            (b"\x80", "[]"),
+            (b"\x83\x01\x82\x02\x03\x82\x04\x05", "[1, [2, 3], [4, 5]]"),
+            (b"\xa0", "{}"),
+            (b"\xa2\x01\x02\x03\x04", "{1: 2, 3: 4}"),
+            (
+                b"\xa2\x61\x61\x01\x61\x62\x82\x02\x03",
+                "{\"a\": 1, \"b\": [2, 3]}",
+            ),
+            (b"\x5f\x42\x01\x02\x43\x03\x04\x05\xff", "(_ h'0102', h'030405')"),
+            (b"\x7f\x65\x73\x74\x72\x65\x61\x64\x6d\x69\x6e\x67\xff", "(_ \"strea\", \"ming\")"),
+            (b"\x9f\xff", "[_ ]"),
+            (
+                b"\x9f\x01\x82\x02\x03\x9f\x04\x05\xff\xff",
+                "[_ 1, [2, 3], [_ 4, 5]]",
+            ),
+            (
+                b"\xbf\x61\x61\x01\x61\x62\x9f\x02\x03\xff\xff",
+                "{_ \"a\": 1, \"b\": [_ 2, 3]}",
+            ),
+        ];
+        for &(bytes, expected) in cases {
+            assert_eq!(diag(bytes), expected);
+        }
+    }
+
+    #[test]
+    fn test_encoding_indicators() {
+        assert_eq!(diag(b"\x18\x01"), "1_0");
+        assert_eq!(diag(b"\x39\x00\x01"), "-2_1");
+        assert_eq!(
+            diag(b"\x1b\x00\x00\x00\x00\xff\xff\xff\xff"),
+            "4294967295_3"
+        );
+        assert_eq!(diag(b"\x58\x01\xff"), "h'ff'_0");
+        assert_eq!(diag(b"\x99\x00\x01\x00"), "[_1 0]");
+        assert_eq!(diag(b"\xba\x00\x00\x00\x00"), "{_2}");
+        assert_eq!(diag(b"\x98\x00"), "[_0]");
+        assert_eq!(diag(b"\xb9\x00\x00"), "{_1}");
+        assert_eq!(diag(b"\xd9\x00\x01\x00"), "1_1(0)");
+        assert_eq!(diag(b"\x5f\x58\x01\x00\xff"), "(_ h'00'_0)");
+        assert_eq!(diag(b"\x5f\xff"), "''_");
+        assert_eq!(diag(b"\x7f\xff"), "\"\"_");
+        assert_eq!(diag(b"\xfa\x3f\xc0\x00\x00"), "1.5_2");
+        assert_eq!(diag(b"\xfb\x3f\xf8\x00\x00\x00\x00\x00\x00"), "1.5_3");
+        assert_eq!(diag(b"\xfb\x3f\xb9\x99\x99\x99\x99\x99\x9a"), "0.1");
+    }
+
+    #[test]
+    fn test_text_escapes() {
+        assert_eq!(diag(b"\x63\x0a\x09\x01"), "\"\\n\\t\\u0001\"");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(to_string(b"\x01\x02").unwrap_err().is_syntax());
+        assert!(to_string(b"\x82\x01").unwrap_err().is_eof());
+        assert!(to_string(b"\x9f\x01").unwrap_err().is_eof());
+        assert!(to_string(b"\xff").unwrap_err().is_syntax());
+        assert!(to_string(b"\x1c").unwrap_err().is_syntax());
+        assert!(to_string(b"\x5f\x61\x61\xff").unwrap_err().is_syntax());
+        let err = to_string(b"\x82\x00\x62\x61\xff").unwrap_err();
+        assert!(err.is_syntax());
+        assert_eq!(err.offset(), 4);
+    }
+
+    #[test]
+    fn test_sequence() {
+        let mut read = SliceRead::new(b"\x01\x61\x61\xa1\x02\xf6");
+        let mut out = String::new();
+        for _ in 0..3 {
+            write_item(&mut read, &mut out).unwrap();
+            out.push_str(", ");
+        }
+        assert_eq!(out, "1, \"a\", {2: null}, ");
+    }
+
+    #[test]
+    fn test_value_display() {
+        let value: Value = serde_cbor::from_slice(
+            b"\xa2\x61\x61\x82\x01\x42\x01\x02\x61\x62\xc1\x1a\x51\x4b\x67\xb0",
+        )
+        .unwrap();
+        assert_eq!(
+            value.to_string(),
+            "{\"a\": [1, h'0102'], \"b\": 1(1363896240)}"
+        );
+        let value = Value::Array(vec![
+            Value::LargeSignedInteger(-(1 << 64) - 1),
+            Value::LargeSignedInteger(-(1 << 64)),
+            Value::LargeSignedInteger(1 << 64),
+            Value::Float(1e300),
+            Value::Simple(99),
+            Value::Null,
+            Value::Bool(true),
+            Value::Text("\"".to_owned()),
+        ]);
+        assert_eq!(
+            value.to_string(),
+            "[3(h'010000000000000000'), -18446744073709551616, 2(h'010000000000000000'), \
+             1.0e+300, simple(99), null, true, \"\\\"\"]"
+        );
+    }
+
+    #[test]
+    fn test_parse_roundtrip() {
+        let cases = &[
+            "0",
+            "-18446744073709551616",
+            "2(h'010000000000000000')",
+            "1.5",
+            "-4.1",
+            "1.0e+300",
+            "5.960464477539063e-8",
+            "Infinity",
+            "-Infinity",
+            "NaN",
+            "simple(16)",
+            "simple(255)",
+            "undefined",
+            "\"\\\"\\\\\\n\u{6c34}\"",
+            "h''",
+            "[1, [2, 3], [_ 4, 5]]",
+            "{_ \"a\": 1, \"b\": [_ 2, 3]}",
+            "{1: 2, 3: 4}",
+            "(_ h'0102', h'030405')",
+            "(_ \"strea\", \"ming\")",
+            "''_",
+            "\"\"_",
+            "[_ ]",
+            "1_0",
+            "-2_1",
+            "4294967295_3",
+            "h'ff'_0",
+            "[_1 0]",
+            "{_2}",
+            "[_0]",
+            "{_1}",
+            "1_1(0)",
+            "(_ h'00'_0)",
+            "1.5_2",
+            "1.5_3",
+            "0(\"2013-03-21T20:04:00Z\")",
+        ];
+        for case in cases {
+            let bytes = parse_to_vec(case).unwrap();
+            assert_eq!(&to_string(&bytes).unwrap(), case);
+        }
+    }
+
+    #[test]
+    fn test_parse_extensions() {
+        let cases: &[(&str, &[u8])] = &[
+            ("'hello'", b"\x45hello"),
+            ("b64'AQI='", b"\x42\x01\x02"),
+            ("b64'-_8'", b"\x42\xfb\xff"),
+            ("h'01 02\n03'", b"\x43\x01\x02\x03"),
+            ("0x1f", b"\x18\x1f"),
+            ("-0b11", b"\x22"),
+            ("0o17", b"\x0f"),
+            ("1e3", b"\xf9\x63\xd0"),
+            ("NaN_3", b"\xfb\x7f\xf8\x00\x00\x00\x00\x00\x00"),
+            ("\"\\ud83d\\ude00\"", b"\x64\xf0\x9f\x98\x80"),
+            ("[ 1 , / comment / 2 # line comment\n ]", b"\x82\x01\x02"),
+            (
+                "[\"]\", ']', h'2c', {\"a,\": [1, 2]}]",
+                b"\x84\x61]\x41]\x41,\xa1\x62a,\x82\x01\x02",
+            ),
+            (
+                "340282366920938463463374607431768211455",
+                b"\xc2\x50\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff",
+            ),
+        ];
+        for &(text, bytes) in cases {
+            assert_eq!(parse_to_vec(text).unwrap(), bytes, "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_parse_value() {
+        let value = parse("{\"a\": [1, -1, 1.5], 24(h'f6'): simple(32)}").unwrap();
+        let expected = serde_cbor::from_slice::<Value>(
+            b"\xa2\x61\x61\x83\x01\x20\xf9\x3e\x00\xd8\x18\x41\xf6\xf8\x20",
+        )
+        .unwrap();
+        assert_eq!(value, expected);
+        assert_eq!(
+            value.to_string(),
+            "{\"a\": [1, -1, 1.5], 24(h'f6'): simple(32)}"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let cases: &[(&str, u64)] = &[
+            ("[1, 2", 5),
+            ("[1 2]", 3),
+            ("{1: 2, 3}", 8),
+            ("h'123'", 5),
+            ("b64'A'", 5),
+            ("256_0", 5),
+            ("1.1_1", 5),
+            ("simple(24)", 9),
+            ("-1(0)", 2),
+            ("nul", 0),
+            ("1 2", 2),
+            ("\"\\x\"", 2),
+            ("(_ h'00', \"a\")", 10),
+            ("'a'_", 3),
+        ];
+        for &(text, offset) in cases {
+            let err = parse_to_vec(text).unwrap_err();
+            assert_eq!(err.offset(), offset, "{}", text);
+        }
+        assert!(parse_to_vec("[1, 2").unwrap_err().is_eof());
+        assert!(parse_to_vec("[1 2]").unwrap_err().is_syntax());
+        let too_deep = "[".repeat(200) + &"]".repeat(200);
+        assert!(parse_to_vec(&too_deep).is_err());
+    }
+}
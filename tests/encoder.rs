@@ -0,0 +1,111 @@
+use serde_cbor::encoder::Encoder;
+use serde_cbor::ser::SliceWrite;
+
+#[test]
+fn test_headers() {
+    let mut buf = [0; 64];
+    let mut encoder = Encoder::new(SliceWrite::new(&mut buf[..]));
+    encoder.write_array_header(None).unwrap();
+    encoder.write_unsigned(24).unwrap();
+    encoder.write_negative(255).unwrap();
+    encoder.write_int(-1000).unwrap();
+    encoder.write_int(i64::min_value()).unwrap();
+    encoder.write_map_header(Some(1)).unwrap();
+    encoder.write_text_header(Some(1)).unwrap();
+    encoder.write_raw(b"a").unwrap();
+    encoder.write_tag(1).unwrap();
+    encoder.write_unsigned(1 << 32).unwrap();
+    encoder.write_break().unwrap();
+    let len = encoder.into_inner().bytes_written();
+    assert_eq!(
+        &buf[..len],
+        &b"\x9f\x18\x18\x38\xff\x39\x03\xe7\x3b\x7f\xff\xff\xff\xff\xff\xff\xff\
+           \xa1\x61a\xc1\x1b\x00\x00\x00\x01\x00\x00\x00\x00\xff"[..]
+    );
+}
+
+#[test]
+fn test_simple_values() {
+    let mut buf = [0; 16];
+    let mut encoder = Encoder::new(SliceWrite::new(&mut buf[..]));
+    encoder.write_bool(false).unwrap();
+    encoder.write_bool(true).unwrap();
+    encoder.write_null().unwrap();
+    encoder.write_undefined().unwrap();
+    encoder.write_simple(16).unwrap();
+    encoder.write_simple(255).unwrap();
+    assert!(encoder.write_simple(24).is_err());
+    assert!(encoder.write_simple(31).is_err());
+    let len = encoder.into_inner().bytes_written();
+    assert_eq!(&buf[..len], b"\xf4\xf5\xf6\xf7\xf0\xf8\xff");
+}
+
+#[test]
+fn test_floats() {
+    let mut buf = [0; 32];
+    let mut encoder = Encoder::new(SliceWrite::new(&mut buf[..]));
+    encoder.write_f16(0x3e00).unwrap();
+    encoder.write_f32(1.5).unwrap();
+    encoder.write_f64(1.5).unwrap();
+    encoder.write_float(1.5).unwrap();
+    encoder.write_float(1.1).unwrap();
+    let len = encoder.into_inner().bytes_written();
+    assert_eq!(
+        &buf[..len],
+        &b"\xf9\x3e\x00\xfa\x3f\xc0\x00\x00\xfb\x3f\xf8\x00\x00\x00\x00\x00\x00\
+           \xf9\x3e\x00\xfb\x3f\xf1\x99\x99\x99\x99\x99\x9a"[..]
+    );
+}
+
+#[test]
+fn test_chunked_strings() {
+    let mut buf = [0; 32];
+    let mut encoder = Encoder::new(SliceWrite::new(&mut buf[..]));
+    encoder.write_text_header(None).unwrap();
+    encoder.write_text("strea").unwrap();
+    encoder.write_text("ming").unwrap();
+    encoder.write_break().unwrap();
+    encoder.write_bytes_header(None).unwrap();
+    encoder.write_bytes(&[1, 2]).unwrap();
+    encoder.write_bytes(&[]).unwrap();
+    encoder.write_break().unwrap();
+    let len = encoder.into_inner().bytes_written();
+    assert_eq!(
+        &buf[..len],
+        &b"\x7f\x65strea\x64ming\xff\x5f\x42\x01\x02\x40\xff"[..]
+    );
+}
+
+#[test]
+fn test_full_buffer() {
+    let mut buf = [0; 2];
+    let mut encoder = Encoder::new(SliceWrite::new(&mut buf[..]));
+    assert!(encoder.write_text("abc").is_err());
+}
+
+#[cfg(feature = "std")]
+mod std_tests {
+    use serde_cbor::encoder::Encoder;
+    use serde_cbor::value::Value;
+
+    #[test]
+    fn test_serialize() {
+        let mut encoder = Encoder::new(Vec::new());
+        encoder.write_map_header(None).unwrap();
+        encoder.serialize("list").unwrap();
+        encoder.serialize(&[1, 2, 3]).unwrap();
+        encoder.write_text("undefined").unwrap();
+        encoder.write_undefined().unwrap();
+        encoder.write_break().unwrap();
+        let bytes = encoder.into_inner();
+        assert_eq!(
+            serde_cbor::diag::to_string(&bytes).unwrap(),
+            r#"{_ "list": [1, 2, 3], "undefined": undefined}"#
+        );
+        let value: Value = serde_cbor::from_slice(&bytes).unwrap();
+        match value {
+            Value::Map(ref map) => assert_eq!(map.len(), 2),
+            _ => panic!("expected a map"),
+        }
+    }
+}
@@ -0,0 +1,214 @@
+#[cfg(feature = "std")]
+mod std_tests {
+    use serde_cbor::de::DecodeLimits;
+    use serde_cbor::incremental::{IncrementalDecoder, ItemScanner, Progress};
+    use serde_cbor::value::Value;
+
+    // Feeds the item one byte at a time and checks that it's only complete at the end.
+    fn scan_bytewise(item: &[u8]) {
+        let mut scanner = ItemScanner::new();
+        for end in 0..item.len() {
+            match scanner.scan(&item[..end]).unwrap() {
+                Progress::NeedMore(n) => assert!(n >= 1 && end + n <= item.len()),
+                Progress::Complete(len) => panic!("complete after {} of {} bytes", len, end),
+            }
+        }
+        assert_eq!(scanner.scan(item).unwrap(), Progress::Complete(item.len()));
+    }
+
+    #[test]
+    fn test_scan_items() {
+        let value: Value = serde_cbor::from_slice(
+            b"\xa2\x61a\x82\x01\x38\x63\x61b\xc1\xfb\x3f\xf1\x99\x99\x99\x99\x99\x9a",
+        )
+        .unwrap();
+        for item in &[
+            &b"\x00"[..],
+            b"\x1b\x00\x00\x00\x01\x00\x00\x00\x00",
+            b"\x65hello",
+            b"\x80",
+            b"\x83\x01\x82\x02\x03\xa1\x04\x05",
+            b"\x9f\x01\x9f\xff\xbf\x61a\x5f\x41\x01\x40\xff\xff\xff",
+            b"\x7f\x62ab\x60\x61c\xff",
+            b"\xc1\xc2\x41\x00",
+            b"\xf8\x20",
+            &serde_cbor::to_vec(&value).unwrap(),
+        ] {
+            scan_bytewise(item);
+        }
+    }
+
+    #[test]
+    fn test_need_more() {
+        let mut scanner = ItemScanner::new();
+        assert_eq!(scanner.scan(b"").unwrap(), Progress::NeedMore(1));
+        assert_eq!(scanner.scan(b"\x82").unwrap(), Progress::NeedMore(1));
+        assert_eq!(
+            scanner.scan(b"\x82\x1a\x00").unwrap(),
+            Progress::NeedMore(3)
+        );
+        assert_eq!(
+            scanner
+                .scan(b"\x82\x1a\x00\x00\x00\x01\x59\x01\x00")
+                .unwrap(),
+            Progress::NeedMore(256)
+        );
+        let mut input = b"\x82\x1a\x00\x00\x00\x01\x59\x01\x00".to_vec();
+        input.extend(vec![0; 200]);
+        assert_eq!(scanner.scan(&input).unwrap(), Progress::NeedMore(56));
+        input.extend(vec![0; 56]);
+        // Data after the item is not part of it.
+        input.push(0xf6);
+        assert_eq!(
+            scanner.scan(&input).unwrap(),
+            Progress::Complete(input.len() - 1)
+        );
+        assert_eq!(scanner.scan(&[0xf6]).unwrap(), Progress::Complete(1));
+    }
+
+    #[test]
+    fn test_scan_errors() {
+        for (input, offset) in &[
+            (&b"\xff"[..], 0),
+            (b"\x1c", 0),
+            (b"\x82\x01\xff", 2),
+            (b"\xbf\x01\xff", 2),
+            (b"\x5f\x61a\xff", 1),
+            (b"\x7f\x7f\xff\xff", 1),
+            (b"\xf8\x10", 0),
+            (b"\xbb\xff\xff\xff\xff\xff\xff\xff\xff", 0),
+        ] {
+            let mut scanner = ItemScanner::new();
+            let err = scanner.scan(input).unwrap_err();
+            assert!(err.is_syntax());
+            assert_eq!(err.offset(), *offset);
+        }
+    }
+
+    #[test]
+    fn test_decoder() {
+        let mut bytes = serde_cbor::to_vec(&vec!["a"; 100]).unwrap();
+        bytes.extend(serde_cbor::to_vec(&-5).unwrap());
+        bytes.extend(serde_cbor::to_vec(&"not a number").unwrap());
+        bytes.extend(serde_cbor::to_vec(&7).unwrap());
+
+        let mut decoder = IncrementalDecoder::new();
+        let mut chunks = bytes.chunks(7);
+        let strings = loop {
+            decoder.feed(chunks.next().unwrap());
+            if let Progress::Complete(value) = decoder.decode::<Vec<String>>().unwrap() {
+                break value;
+            }
+        };
+        assert_eq!(strings.len(), 100);
+        for chunk in chunks {
+            decoder.feed(chunk);
+        }
+        assert_eq!(decoder.decode::<i8>().unwrap(), Progress::Complete(-5));
+        // An item of the wrong type is removed.
+        assert!(decoder.decode::<i8>().unwrap_err().is_data());
+        assert_eq!(decoder.decode::<i8>().unwrap(), Progress::Complete(7));
+        assert_eq!(decoder.decode::<i8>().unwrap(), Progress::NeedMore(1));
+        assert!(decoder.buffered().is_empty());
+    }
+    #[test]
+    fn test_decoder_many_items() {
+        let mut decoder = IncrementalDecoder::new();
+        let items: Vec<u8> = (0..200_000).map(|i| (i % 24) as u8).collect();
+        decoder.feed(&items);
+        for &item in &items[..100_000] {
+            assert_eq!(decoder.decode::<u8>().unwrap(), Progress::Complete(item));
+        }
+        // Decoded items are dropped from the buffer.
+        decoder.feed(b"\x61");
+        assert_eq!(decoder.buffered().len(), 100_001);
+        for &item in &items[100_000..] {
+            assert_eq!(decoder.decode::<u8>().unwrap(), Progress::Complete(item));
+        }
+        assert_eq!(decoder.decode::<String>().unwrap(), Progress::NeedMore(1));
+        decoder.feed(b"\x61");
+        assert_eq!(
+            decoder.decode::<String>().unwrap(),
+            Progress::Complete("a".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_max_item_len() {
+        let mut scanner = ItemScanner::new().max_item_len(8);
+        assert_eq!(scanner.scan(b"\x67abc").unwrap(), Progress::NeedMore(4));
+        assert_eq!(scanner.scan(b"\x67abcdefg").unwrap(), Progress::Complete(8));
+
+        // The header announces more than the limit.
+        for input in &[
+            &b"\x68"[..],
+            b"\x5b\xff\xff\xff\xff\xff\xff\xff\xff",
+            b"\x9f\x01\x02\x03\x04\x05\x06\x07",
+            b"\x83\x01\x02\x1b",
+        ] {
+            let mut scanner = ItemScanner::new().max_item_len(8);
+            let err = scanner.scan(input).unwrap_err();
+            assert!(err.is_syntax());
+            assert_eq!(
+                err.to_string().split(" at ").next(),
+                Some("item length limit exceeded")
+            );
+        }
+
+        let mut decoder = IncrementalDecoder::new().max_item_len(4);
+        decoder.feed(b"\x63abc\x64abcd");
+        assert_eq!(
+            decoder.decode::<String>().unwrap(),
+            Progress::Complete("abc".to_owned())
+        );
+        assert!(decoder.decode::<String>().is_err());
+    }
+
+    #[test]
+    fn test_decoder_offsets() {
+        let mut decoder = IncrementalDecoder::new();
+        decoder.feed(b"\x01\x02\x61\xff\x82\x01");
+        for _ in 0..2 {
+            decoder.decode::<u8>().unwrap();
+        }
+        decoder.feed(&[0]);
+        // The offsets are those of the whole input, not of the item.
+        let err = decoder.decode::<String>().unwrap_err();
+        assert_eq!(err.offset(), 3);
+        let err = decoder.decode::<u8>().unwrap_err();
+        assert!(err.is_data());
+        decoder.feed(b"\x1c");
+        let err = decoder.decode::<Value>().unwrap_err();
+        assert_eq!(err.to_string(), "unassigned type at offset 7");
+    }
+
+    #[test]
+    fn test_decoder_depth() {
+        // The scanner and the deserializer accept the same depth.
+        let mut input = vec![0x9f; 127];
+        input.push(0x01);
+        input.extend(vec![0xff; 127]);
+        let mut decoder = IncrementalDecoder::new();
+        decoder.feed(&input);
+        decoder.feed(b"\x9f");
+        decoder.feed(&input);
+        decoder.feed(b"\xff");
+        match decoder.decode::<Value>().unwrap() {
+            Progress::Complete(_) => {}
+            Progress::NeedMore(_) => panic!(),
+        }
+        let err = decoder.decode::<Value>().unwrap_err();
+        assert_eq!(
+            err.to_string().split(" at ").next(),
+            Some("recursion limit exceeded")
+        );
+        assert_eq!(err.offset(), input.len() as u64 + 127);
+
+        let limits = DecodeLimits::new().max_depth(2);
+        let mut decoder = IncrementalDecoder::new().limits(limits);
+        decoder.feed(b"\x9f\x9f\xff\xff\x9f\x9f\x9f");
+        decoder.decode::<Value>().unwrap();
+        let err = decoder.decode::<Value>().unwrap_err();
+        assert_eq!(err.to_string(), "recursion limit exceeded at offset 6");
+    }
+}
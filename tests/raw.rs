@@ -0,0 +1,140 @@
+use serde_cbor::de::Deserializer;
+use serde_cbor::raw::RawCbor;
+use serde_cbor::ser::{Serializer, SliceWrite};
+
+use serde::de::Deserialize;
+use serde::ser::Serialize;
+
+#[test]
+fn test_from_slice() {
+    for bytes in &[
+        &b"\x01"[..],
+        b"\x82\x01\x9f\xff",
+        b"\xbf\x61a\x5f\x41\x01\xff\xff",
+        b"\xc1\x1a\x00\x00\x00\x01",
+        b"\x7f\x62ab\xff",
+    ] {
+        assert_eq!(RawCbor::from_slice(bytes).unwrap().as_bytes(), *bytes);
+    }
+    for bytes in &[
+        &b""[..],
+        b"\x01\x02",
+        b"\x82\x01",
+        b"\xff",
+        b"\xbf\x01\xff",
+        b"\x5f\x61a\xff",
+        b"\xc1",
+        b"\x1c",
+    ] {
+        assert!(RawCbor::from_slice(bytes).is_err());
+    }
+}
+
+#[test]
+fn test_borrowed() {
+    let bytes = b"\x83\x01\x9f\x02\x03\xff\x04";
+    let mut deserializer = Deserializer::from_slice_with_scratch(bytes, &mut []);
+    let (a, raw, b) = <(u8, RawCbor, u8)>::deserialize(&mut deserializer).unwrap();
+    assert_eq!((a, b), (1, 4));
+    assert_eq!(raw.as_bytes(), b"\x9f\x02\x03\xff");
+}
+
+#[test]
+fn test_serialize() {
+    let raw = RawCbor::from_slice(b"\x9f\x19\x00\x01\xff").unwrap();
+    let mut buf = [0; 16];
+    let mut serializer = Serializer::new(SliceWrite::new(&mut buf[..]));
+    (raw, 2).serialize(&mut serializer).unwrap();
+    let len = serializer.into_inner().bytes_written();
+    assert_eq!(&buf[..len], b"\x82\x9f\x19\x00\x01\xff\x02");
+}
+
+#[cfg(feature = "std")]
+mod std_tests {
+    use serde::de::Deserialize;
+    use serde_cbor::raw::{RawCbor, RawValue};
+    use serde_cbor::value::Value;
+    use serde_cbor::Deserializer;
+    use serde_derive::{Deserialize, Serialize};
+    use std::collections::HashMap;
+
+    #[derive(Debug, Deserialize, Serialize)]
+    struct Envelope<T> {
+        id: u32,
+        payload: T,
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let bytes = b"\xa2\x62id\x07\x67payload\xa1\x61x\x5f\x41\x01\x42\x02\x03\xff";
+        let envelope: Envelope<RawCbor> = serde_cbor::from_slice(bytes).unwrap();
+        assert_eq!(envelope.id, 7);
+        assert_eq!(envelope.payload.as_bytes(), &bytes[13..]);
+        assert_eq!(serde_cbor::to_vec(&envelope).unwrap(), &bytes[..]);
+        assert_eq!(serde_cbor::to_vec_canonical(&envelope).unwrap(), &bytes[..]);
+    }
+
+    #[test]
+    fn test_reader() {
+        // The indefinite strings are captured with their original chunks.
+        let bytes = b"\x82\xbf\x7f\x61a\x62bc\xff\x01\xff\xf5";
+        let (raw, flag): (RawValue, bool) = serde_cbor::from_reader(&bytes[..]).unwrap();
+        assert_eq!(raw.as_bytes(), &bytes[1..11]);
+        assert!(flag);
+
+        let mut deserializer = Deserializer::from_reader(&bytes[..]);
+        assert!(<(RawCbor, bool)>::deserialize(&mut deserializer).is_err());
+    }
+
+    #[test]
+    fn test_mut_slice() {
+        let mut bytes = *b"\x82\x5f\x41\x01\x41\x02\xff\xf4";
+        let expected = bytes;
+        let (raw, flag): (RawValue, bool) = serde_cbor::de::from_mut_slice(&mut bytes).unwrap();
+        assert_eq!(raw.as_bytes(), &expected[1..7]);
+        assert!(!flag);
+    }
+
+    #[test]
+    fn test_keys() {
+        let bytes = b"\xa2\x82\x01\x02\x61a\x82\x01\x03\x61b";
+        let key = RawValue::from_vec(vec![0x82, 0x01, 0x02]).unwrap();
+        let mut deserializer = Deserializer::from_slice(bytes).deny_duplicate_keys();
+        let map = HashMap::<RawValue, String>::deserialize(&mut deserializer).unwrap();
+        assert_eq!(map[&key], "a");
+        let mut deserializer = Deserializer::from_reader(&bytes[..]).deny_duplicate_keys();
+        let map = HashMap::<RawValue, String>::deserialize(&mut deserializer).unwrap();
+        assert_eq!(map[&key], "a");
+
+        let bytes = b"\xa2\x82\x01\x02\x61a\x82\x01\x02\x61b";
+        let mut deserializer = Deserializer::from_reader(&bytes[..]).deny_duplicate_keys();
+        assert!(HashMap::<RawValue, String>::deserialize(&mut deserializer).is_err());
+    }
+
+    #[test]
+    fn test_value() {
+        let raw = RawValue::from_vec(b"\xa1\x61a\x9f\x01\xff".to_vec()).unwrap();
+        let value = serde_cbor::value::to_value(&raw).unwrap();
+        assert_eq!(
+            value,
+            Value::Map(
+                vec![(
+                    Value::Text("a".to_owned()),
+                    Value::Array(vec![Value::UnsignedInteger(1)])
+                )]
+                .into_iter()
+                .collect()
+            )
+        );
+        let raw: RawValue = serde_cbor::value::from_value(value).unwrap();
+        assert_eq!(raw.as_bytes(), b"\xa1\x61a\x81\x01");
+    }
+
+    #[test]
+    fn test_from_vec() {
+        assert!(RawValue::from_vec(vec![0x82, 0x01]).is_err());
+        let raw = RawValue::from_vec(vec![0x82, 0x01, 0x02]).unwrap();
+        assert_eq!(raw.as_raw_cbor().to_raw_value(), raw);
+        assert_eq!(raw.into_vec(), [0x82, 0x01, 0x02]);
+    }
+}
@@ -1,5 +1,8 @@
+#[macro_use]
+extern crate serde_derive;
+
 use serde::Serialize;
-use serde_cbor::ser::{Serializer, SliceWrite};
+use serde_cbor::ser::{SequenceWriter, Serializer, SliceWrite};
 
 #[test]
 fn test_str() {
@@ -41,13 +44,48 @@ fn serialize_and_compare<T: Serialize>(value: T, expected: &[u8]) {
     assert_eq!(&slice[..end], expected);
 }
 
+#[test]
+fn test_sequence_writer() {
+    let mut slice = [0u8; 16];
+    let serializer = Serializer::new(SliceWrite::new(&mut slice)).packed_format();
+    let mut writer = SequenceWriter::from_serializer(serializer);
+    writer.write("a").unwrap();
+    writer.write(&(1, -1)).unwrap();
+    writer.write(&()).unwrap();
+    let end = writer.into_inner().bytes_written();
+    assert_eq!(&slice[..end], b"\x61a\x82\x01\x20\xf6");
+}
+
+#[test]
+fn test_error_offset() {
+    let mut slice = [0u8; 4];
+    let mut serializer = Serializer::new(SliceWrite::new(&mut slice));
+    let err = ["a", "abc"].serialize(&mut serializer).unwrap_err();
+    assert!(err.is_scratch_too_small());
+    assert_eq!(err.offset(), 4);
+}
+
 #[cfg(feature = "std")]
 mod std_tests {
     use serde::Serializer;
     use serde_cbor::ser;
-    use serde_cbor::{from_slice, to_vec};
+    use serde_cbor::{from_slice, to_vec, to_vec_canonical};
     use std::collections::BTreeMap;
 
+    #[test]
+    fn test_sequence_writer_buffer() {
+        let mut sequence = ser::SequenceWriter::new(Vec::new());
+        let mut output = Vec::new();
+        for i in 0..4 {
+            sequence.write(&i).unwrap();
+            if sequence.get_ref().len() >= 2 {
+                output.append(sequence.get_mut());
+            }
+        }
+        assert!(sequence.get_ref().is_empty());
+        assert_eq!(output, [0, 1, 2, 3]);
+    }
+
     #[test]
     fn test_string() {
         let value = "foobar".to_owned();
@@ -245,6 +283,93 @@ mod std_tests {
         // to test in Travis.
     }
 
+    struct Port(u16);
+
+    impl serde::Serialize for Port {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            if self.0 == 0 {
+                return Err(serde::ser::Error::custom("unsupported port"));
+            }
+            serializer.serialize_u16(self.0)
+        }
+    }
+
+    #[derive(Serialize)]
+    struct Server {
+        ports: Vec<Port>,
+    }
+
+    #[derive(Serialize)]
+    struct Config {
+        servers: BTreeMap<String, Server>,
+    }
+
+    struct Duplicates;
+
+    impl serde::Serialize for Duplicates {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_map(vec![("a", 1), ("a", 2)])
+        }
+    }
+
+    #[derive(Serialize)]
+    struct Outer {
+        inner: Duplicates,
+    }
+
+    // Fails once more than `remaining` bytes are written.
+    struct Limited {
+        remaining: usize,
+    }
+
+    impl std::io::Write for Limited {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if buf.len() > self.remaining {
+                return Ok(0);
+            }
+            self.remaining -= buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_error_context() {
+        let mut servers = BTreeMap::new();
+        servers.insert(
+            "web".to_owned(),
+            Server {
+                ports: vec![Port(80), Port(0)],
+            },
+        );
+        let config = Config { servers };
+        let err = to_vec(&config).unwrap_err();
+        assert!(err.is_data());
+        assert_eq!(err.path(), Some(".servers.web.ports[1]"));
+        assert_eq!(err.offset(), 24);
+        assert_eq!(
+            err.to_string(),
+            "unsupported port at .servers.web.ports[1], offset 24"
+        );
+
+        let err = to_vec_canonical(&config).unwrap_err();
+        assert_eq!(err.path(), Some(".servers.web.ports[1]"));
+
+        let mut map = BTreeMap::new();
+        map.insert(vec![1u8], "first");
+        map.insert(vec![2u8], "second");
+        let err = ser::to_writer(Limited { remaining: 12 }, &map).unwrap_err();
+        assert!(err.is_io());
+        assert_eq!(err.path(), Some("[[2]]"));
+        assert_eq!(err.offset(), 12);
+
+        let err = to_vec_canonical(&Outer { inner: Duplicates }).unwrap_err();
+        assert_eq!(err.path(), Some(".inner.a"));
+    }
+
     #[test]
     fn test_half() {
         let vec = to_vec(&42.5f32).unwrap();
@@ -183,4 +183,28 @@ mod std_tests {
         "3BFFFFFFFFFFFFFFFF"
     );
     testcase!(test_u128, u128, 17, "11");
+    testcase!(
+        test_i128_bignum,
+        i128,
+        -18446744073709551617i128,
+        "C349010000000000000000"
+    );
+    testcase!(
+        test_i128_min,
+        i128,
+        i128::min_value(),
+        "C3507FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF"
+    );
+    testcase!(
+        test_u128_bignum,
+        u128,
+        18446744073709551616u128,
+        "C249010000000000000000"
+    );
+    testcase!(
+        test_u128_max,
+        u128,
+        u128::max_value(),
+        "C250FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF"
+    );
 }
@@ -0,0 +1,121 @@
+#[macro_use]
+extern crate serde_derive;
+
+use serde::Serialize;
+use serde_cbor::ser::{Serializer, SliceWrite};
+use serde_cbor::tags::Tagged;
+
+#[test]
+fn test_tagged_no_std() {
+    let tagged = Tagged::new(Some(32), "http://www.example.com");
+
+    let mut slice = [0u8; 64];
+    let writer = SliceWrite::new(&mut slice);
+    let mut serializer = Serializer::new(writer);
+    tagged.serialize(&mut serializer).unwrap();
+    let writer = serializer.into_inner();
+    let end = writer.bytes_written();
+    let slice = writer.into_inner();
+    assert_eq!(&slice[..2], b"\xd8\x20");
+
+    let deserialized: Tagged<&str> =
+        serde_cbor::de::from_slice_with_scratch(&slice[..end], &mut []).unwrap();
+    assert_eq!(tagged, deserialized);
+}
+
+#[cfg(feature = "std")]
+mod std_tests {
+    use serde_cbor::tags::Tagged;
+    use serde_cbor::{from_slice, to_vec};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Payload {
+        kind: Tagged<Vec<u8>>,
+        time: Tagged<u64>,
+    }
+
+    #[test]
+    fn test_tag_sizes() {
+        let cases: &[(u64, &[u8])] = &[
+            (0, b"\xc0\x00"),
+            (23, b"\xd7\x00"),
+            (24, b"\xd8\x18\x00"),
+            (256, b"\xd9\x01\x00\x00"),
+            (65536, b"\xda\x00\x01\x00\x00\x00"),
+            (
+                u64::max_value(),
+                b"\xdb\xff\xff\xff\xff\xff\xff\xff\xff\x00",
+            ),
+        ];
+        for &(tag, expected) in cases {
+            let tagged = Tagged::new(Some(tag), 0u8);
+            assert_eq!(to_vec(&tagged).unwrap(), expected);
+            let decoded: Tagged<u8> = from_slice(expected).unwrap();
+            assert_eq!(decoded, tagged);
+        }
+    }
+
+    #[test]
+    fn test_untagged() {
+        let untagged = Tagged::new(None, 42u64);
+        let bytes = to_vec(&untagged).unwrap();
+        assert_eq!(bytes, b"\x18\x2a");
+        let decoded: Tagged<u64> = from_slice(&bytes).unwrap();
+        assert_eq!(decoded, untagged);
+    }
+
+    #[test]
+    fn test_tags_ignored_by_plain_types() {
+        let value: u64 = from_slice(b"\xc1\x1a\x51\x4b\x67\xb0").unwrap();
+        assert_eq!(value, 1_363_896_240);
+    }
+
+    #[test]
+    fn test_nested_tags() {
+        let inner = Tagged::new(Some(2), vec![1u8, 2]);
+        let outer = Tagged::new(Some(55799), inner);
+        let bytes = to_vec(&outer).unwrap();
+        assert_eq!(bytes, b"\xd9\xd9\xf7\xc2\x82\x01\x02");
+        let decoded: Tagged<Tagged<Vec<u8>>> = from_slice(&bytes).unwrap();
+        assert_eq!(decoded, outer);
+    }
+
+    #[test]
+    fn test_tagged_fields() {
+        let payload = Payload {
+            kind: Tagged::new(Some(24), vec![0xf6]),
+            time: Tagged::new(None, 1),
+        };
+        let bytes = to_vec(&payload).unwrap();
+        let decoded: Payload = from_slice(&bytes).unwrap();
+        assert_eq!(decoded, payload);
+    }
+
+    #[test]
+    fn test_bignum_decoding() {
+        // Leading zeros and indefinite length byte strings are accepted.
+        let value: u128 = from_slice(b"\xc2\x43\x00\x00\x2a").unwrap();
+        assert_eq!(value, 42);
+        let value: i128 = from_slice(b"\xc3\x5f\x41\x01\x41\x00\xff").unwrap();
+        assert_eq!(value, -257);
+        // Other integer types don't decode bignums.
+        assert!(from_slice::<u64>(b"\xc2\x41\x2a").is_err());
+    }
+
+    #[test]
+    fn test_bignum_out_of_range() {
+        let too_large =
+            b"\xc2\x51\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00";
+        assert!(from_slice::<u128>(too_large).is_err());
+        assert!(from_slice::<u128>(b"\xc3\x41\x01").is_err());
+        let below_min = b"\xc3\x50\x80\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00";
+        assert!(from_slice::<i128>(below_min).is_err());
+        assert!(from_slice::<i128>(b"\xc2\x01").is_err());
+    }
+
+    #[test]
+    fn test_tagged_eof() {
+        let result: serde_cbor::Result<Tagged<u8>> = from_slice(b"\xd9\x01");
+        assert!(result.unwrap_err().is_eof());
+    }
+}
@@ -23,7 +23,11 @@ mod std_tests {
         unit_array: Vec<UnitStruct>,
     }
 
+    use serde::Deserialize;
+    use serde_cbor::de::{DecodeLimits, Deserializer};
+    use serde_cbor::error::Category;
     use serde_cbor::value::Value;
+    use serde_cbor::Serializer;
     use std::iter::FromIterator;
 
     #[test]
@@ -97,4 +101,416 @@ mod std_tests {
         let reference = b"\xa2\x00\x11\x01\x18\x2a";
         assert_eq!(data, reference);
     }
+
+    #[test]
+    fn tag_roundtrip() {
+        let value = Value::Tag(
+            1,
+            Box::new(Value::Tag(24, Box::new(Value::Bytes(vec![0xf6])))),
+        );
+        let data = serde_cbor::to_vec(&value).unwrap();
+        assert_eq!(data, b"\xc1\xd8\x18\x41\xf6");
+        let decoded: Value = serde_cbor::from_slice(&data).unwrap();
+        assert_eq!(decoded, value);
+    }
+
+    #[test]
+    fn tag_in_collections() {
+        let data = b"\xa1\x01\x82\xd8\x18\x41\x01\x03";
+        let value: Value = serde_cbor::from_slice(data).unwrap();
+        let mut map = BTreeMap::new();
+        map.insert(
+            Value::UnsignedInteger(1),
+            Value::Array(vec![
+                Value::Tag(24, Box::new(Value::Bytes(vec![1]))),
+                Value::UnsignedInteger(3),
+            ]),
+        );
+        assert_eq!(value, Value::Map(map));
+        assert_eq!(serde_cbor::to_vec(&value).unwrap(), data);
+    }
+
+    #[test]
+    fn simple_roundtrip() {
+        for &(simple, data) in &[
+            (0u8, &b"\xe0"[..]),
+            (19, b"\xf3"),
+            (32, b"\xf8\x20"),
+            (255, b"\xf8\xff"),
+        ] {
+            let value = Value::Simple(simple);
+            assert_eq!(serde_cbor::to_vec(&value).unwrap(), data);
+            let decoded: Value = serde_cbor::from_slice(data).unwrap();
+            assert_eq!(decoded, value);
+        }
+    }
+
+    #[test]
+    fn simple_invalid() {
+        assert!(serde_cbor::to_vec(&Value::Simple(24)).is_err());
+        let result: serde_cbor::Result<Value> = serde_cbor::from_slice(b"\xf8\x18");
+        assert!(result.unwrap_err().is_syntax());
+        let result: serde_cbor::Result<u8> = serde_cbor::from_slice(b"\xe0");
+        assert!(result.unwrap_err().is_syntax());
+    }
+
+    #[test]
+    fn simple_ordering() {
+        assert!(Value::Simple(24) > Value::Bool(true));
+        assert!(Value::Simple(20) != Value::Bool(false));
+        assert!(Value::Simple(20) > Value::Bool(false));
+        assert!(Value::Simple(20) < Value::Bool(true));
+
+        let mut map = BTreeMap::new();
+        for (i, key) in vec![
+            Value::Simple(31),
+            Value::Bool(false),
+            Value::Simple(20),
+            Value::Array(vec![Value::Simple(24)]),
+            Value::Array(vec![Value::Simple(25)]),
+            Value::Tag(1, Box::new(Value::Simple(30))),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            map.insert(key, i);
+        }
+        assert_eq!(map.len(), 6);
+
+        // Encodable values sort like their canonical encoding.
+        let values = vec![
+            Value::Simple(0),
+            Value::Bool(false),
+            Value::Simple(20),
+            Value::Bool(true),
+            Value::Null,
+            Value::Simple(23),
+            Value::Simple(32),
+            Value::Float(0.0),
+            Value::Float(-0.0),
+            Value::Float(std::f64::INFINITY),
+            Value::Float(std::f64::NAN),
+            Value::Float(0.1f32.into()),
+            Value::Float(0.1),
+            Value::LargeSignedInteger(i128::max_value()),
+            Value::LargeSignedInteger(i128::min_value()),
+            Value::Tag(3, Box::new(Value::Bytes(vec![0xff; 17]))),
+            Value::Tag(4, Box::new(Value::Null)),
+            Value::Array(vec![Value::Null, Value::Simple(0)]),
+            Value::Array(vec![Value::Float(1.0), Value::Null]),
+        ];
+        for a in &values {
+            for b in &values {
+                let encoded = (
+                    serde_cbor::to_vec(a).unwrap(),
+                    serde_cbor::to_vec(b).unwrap(),
+                );
+                if encoded.0 == encoded.1 {
+                    continue;
+                }
+                assert_eq!(a.cmp(b), encoded.0.cmp(&encoded.1), "{:?} {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn tagged_to_value() {
+        use serde_cbor::tags::Tagged;
+
+        let value =
+            serde_cbor::value::to_value(Tagged::new(Some(32), "http://example.com")).unwrap();
+        assert_eq!(
+            value,
+            Value::Tag(32, Box::new(Value::Text("http://example.com".to_owned())))
+        );
+        let value = serde_cbor::value::to_value(Value::Simple(16)).unwrap();
+        assert_eq!(value, Value::Simple(16));
+    }
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    enum Enum {
+        Unit,
+        NewType(i32),
+        Tuple(String, bool),
+        Struct { x: i32, y: i32 },
+    }
+
+    #[test]
+    fn from_value_struct() {
+        let value = serde_cbor::value::to_value(SmallStruct { spam: 1, eggs: 2 }).unwrap();
+        let owned: SmallStruct = serde_cbor::value::from_value(value).unwrap();
+        assert_eq!((owned.spam, owned.eggs), (1, 2));
+    }
+
+    #[test]
+    fn from_value_borrowed() {
+        let value = Value::Array(vec![
+            Value::Text("text".to_owned()),
+            Value::Bytes(b"bytes".to_vec()),
+            Value::Null,
+        ]);
+        let borrowed: (&str, &[u8], Option<u8>) = serde::Deserialize::deserialize(&value).unwrap();
+        assert_eq!(borrowed, ("text", &b"bytes"[..], None));
+    }
+
+    #[test]
+    fn from_value_packed() {
+        let data = serde_cbor::ser::to_vec_packed(&SmallStruct { spam: 17, eggs: 42 }).unwrap();
+        let value: Value = serde_cbor::from_slice(&data).unwrap();
+        let small: SmallStruct = serde_cbor::value::from_value(value).unwrap();
+        assert_eq!((small.spam, small.eggs), (17, 42));
+    }
+
+    #[test]
+    fn from_value_enum() {
+        let cases = vec![
+            Enum::Unit,
+            Enum::NewType(10),
+            Enum::Tuple("x".to_owned(), true),
+            Enum::Struct { x: 5, y: -5 },
+        ];
+        for case in cases {
+            let value = serde_cbor::value::to_value(&case).unwrap();
+            assert_eq!(serde_cbor::value::from_value::<Enum>(value).unwrap(), case);
+
+            let mut legacy = Vec::new();
+            serde::Serialize::serialize(
+                &case,
+                &mut serde_cbor::Serializer::new(&mut legacy).legacy_enums(),
+            )
+            .unwrap();
+            let value: Value = serde_cbor::from_slice(&legacy).unwrap();
+            assert_eq!(serde_cbor::value::from_value::<Enum>(value).unwrap(), case);
+        }
+    }
+
+    #[test]
+    fn from_value_tagged() {
+        use serde_cbor::tags::Tagged;
+
+        let value = Value::Tag(1, Box::new(Value::UnsignedInteger(1_363_896_240)));
+        let tagged: Tagged<u64> = serde_cbor::value::from_value(value.clone()).unwrap();
+        assert_eq!(tagged, Tagged::new(Some(1), 1_363_896_240));
+        let untagged: u64 = serde_cbor::value::from_value(value.clone()).unwrap();
+        assert_eq!(untagged, 1_363_896_240);
+        let copy: Value = serde::Deserialize::deserialize(&value).unwrap();
+        assert_eq!(copy, value);
+        let simple: Value = serde_cbor::value::from_value(Value::Simple(99)).unwrap();
+        assert_eq!(simple, Value::Simple(99));
+    }
+
+    #[test]
+    fn from_value_not_encodable() {
+        let value = Value::Simple(24);
+        assert!(serde_cbor::to_vec(&value).is_err());
+        let copy: Value = serde_cbor::value::from_value(value.clone()).unwrap();
+        assert_eq!(copy, value);
+    }
+
+    #[test]
+    fn bignum_roundtrip() {
+        let cases: &[(Value, &[u8])] = &[
+            (
+                Value::LargeSignedInteger(1 << 70),
+                b"\xc2\x49\x40\x00\x00\x00\x00\x00\x00\x00\x00",
+            ),
+            (
+                Value::LargeSignedInteger(-(1 << 70)),
+                b"\xc3\x49\x3f\xff\xff\xff\xff\xff\xff\xff\xff",
+            ),
+            (
+                Value::Tag(2, Box::new(Value::Bytes(vec![0xff; 16]))),
+                b"\xc2\x50\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff",
+            ),
+        ];
+        for &(ref value, bytes) in cases {
+            assert_eq!(&serde_cbor::to_vec(value).unwrap()[..], bytes);
+            assert_eq!(&serde_cbor::from_slice::<Value>(bytes).unwrap(), value);
+        }
+
+        // Bignums that fit in 64 bits become plain integers.
+        let small: Value = serde_cbor::from_slice(b"\xc2\x42\x01\x00").unwrap();
+        assert_eq!(small, Value::UnsignedInteger(256));
+        // Tags 2 and 3 with other content are kept.
+        let text: Value = serde_cbor::from_slice(b"\xc2\x61\x61").unwrap();
+        assert_eq!(text, Value::Tag(2, Box::new(Value::Text("a".to_owned()))));
+    }
+
+    #[test]
+    fn bignum_to_from_value() {
+        let value = serde_cbor::value::to_value(u128::max_value()).unwrap();
+        assert_eq!(value, Value::Tag(2, Box::new(Value::Bytes(vec![0xff; 16]))));
+        let number: u128 = serde_cbor::value::from_value(value).unwrap();
+        assert_eq!(number, u128::max_value());
+
+        let value = serde_cbor::value::to_value(i128::min_value()).unwrap();
+        assert_eq!(value, Value::LargeSignedInteger(i128::min_value()));
+        let number: i128 = serde_cbor::value::from_value(value).unwrap();
+        assert_eq!(number, i128::min_value());
+
+        assert_eq!(
+            serde_cbor::value::to_value(7u128).unwrap(),
+            Value::UnsignedInteger(7)
+        );
+    }
+
+    #[test]
+    fn from_value_errors() {
+        let value = Value::Array(vec![Value::UnsignedInteger(1), Value::UnsignedInteger(2)]);
+        assert!(serde_cbor::value::from_value::<(u8,)>(value).is_err());
+        let value = Value::Text("Missing".to_owned());
+        assert!(serde_cbor::value::from_value::<Enum>(value).is_err());
+        let value = Value::Null;
+        assert!(serde_cbor::value::from_value::<u32>(value)
+            .unwrap_err()
+            .to_string()
+            .contains("expected u32"));
+    }
+
+    fn write_value(value: &Value, canonical: bool) -> Vec<u8> {
+        let mut serializer = Serializer::new(Vec::new());
+        if canonical {
+            serializer = serializer.canonical();
+        }
+        serializer.write_value(value).unwrap();
+        serializer.into_inner()
+    }
+
+    #[test]
+    fn read_write_value() {
+        for input in &[
+            &b"\x01"[..],
+            b"\x83\x01\x82\x02\x03\xa1\x61a\x80",
+            b"\x9f\x01\xbf\x61a\x7f\x61b\xff\x02\x61c\xff\xff",
+            b"\xa3\x62bb\x01\x61c\x02\xf5\xc1\x01",
+            b"\xc2\x42\x01\x00",
+            b"\xc3\x49\x01\x00\x00\x00\x00\x00\x00\x00\x00",
+            b"\xd8\x20\xc2\x61a",
+            b"\x82\xf8\x20\xf9\x3e\x00",
+        ] {
+            let expected: Value = serde_cbor::from_slice(input).unwrap();
+            let mut deserializer = Deserializer::from_slice(input);
+            assert_eq!(deserializer.read_value().unwrap(), expected);
+            deserializer.end().unwrap();
+
+            assert_eq!(
+                write_value(&expected, false),
+                serde_cbor::to_vec(&expected).unwrap()
+            );
+            assert_eq!(
+                write_value(&expected, true),
+                serde_cbor::to_vec_canonical(&expected).unwrap()
+            );
+        }
+
+        // The same errors with default options, in strict mode and with unique keys.
+        let deserializer = |input, mode| match mode {
+            0 => Deserializer::from_slice(input),
+            1 => Deserializer::from_slice(input).strict(),
+            _ => Deserializer::from_slice(input).deny_duplicate_keys(),
+        };
+        for &(input, mode) in &[
+            (&b"\x82\x01"[..], 0),
+            (b"\x9c", 0),
+            (b"\xc1", 0),
+            (b"\xbf\x01\xff", 0),
+            (b"\xa1\xff\x01", 0),
+            (b"\x9f\x01\xff", 1),
+            (b"\x98\x01\x01", 1),
+            (b"\xd8\x01\x01", 1),
+            (b"\xa2\x02\x01\x01\x02", 1),
+            (b"\xa2\x81\x01\x01\x81\x01\x02", 1),
+            (b"\xa2\x81\x01\x01\x9f\x01\xff\x02", 2),
+        ] {
+            let expected = Value::deserialize(&mut deserializer(input, mode)).unwrap_err();
+            let err = deserializer(input, mode).read_value().unwrap_err();
+            assert_eq!(err.to_string(), expected.to_string());
+        }
+    }
+
+    #[test]
+    fn deep_values() {
+        let depth = 100_000;
+        let nested = |prefix: &[u8], item: &[u8], suffix: &[u8]| {
+            let mut bytes = Vec::new();
+            for _ in 0..depth {
+                bytes.extend_from_slice(prefix);
+            }
+            bytes.extend_from_slice(item);
+            for _ in 0..depth {
+                bytes.extend_from_slice(suffix);
+            }
+            bytes
+        };
+        for (input, expected) in &[
+            (nested(b"\x81", b"\x01", b""), nested(b"\x81", b"\x01", b"")),
+            (
+                nested(b"\x9f", b"\x01", b"\xff"),
+                nested(b"\x81", b"\x01", b""),
+            ),
+            (
+                nested(b"\xbf\x61a", b"\xf6", b"\xff"),
+                nested(b"\xa1\x61a", b"\xf6", b""),
+            ),
+            (nested(b"\xc1", b"\x01", b""), nested(b"\xc1", b"\x01", b"")),
+        ] {
+            let limits = DecodeLimits::new().max_depth(depth);
+            let mut deserializer = Deserializer::from_slice(input).limits(limits);
+            deserializer.skip_value().unwrap();
+            deserializer.end().unwrap();
+
+            let mut deserializer = Deserializer::from_slice(input)
+                .limits(limits)
+                .deny_duplicate_keys();
+            let value = deserializer.read_value().unwrap();
+            deserializer.end().unwrap();
+            assert_eq!(write_value(&value, false), *expected);
+            assert_eq!(write_value(&value, true), *expected);
+            // Dropping the value doesn't recurse either.
+            drop(value);
+
+            let limits = limits.max_depth(depth - 1);
+            let mut deserializer = Deserializer::from_slice(input).limits(limits);
+            let err = deserializer.skip_value().unwrap_err();
+            assert_eq!(err.classify(), Category::Syntax);
+            let mut deserializer = Deserializer::from_slice(input).limits(limits);
+            let err = deserializer.read_value().unwrap_err();
+            assert_eq!(err.classify(), Category::Syntax);
+        }
+    }
+
+    #[test]
+    fn deep_keys() {
+        let depth = 100_000;
+        let key = |prefix: u8, item: u8, suffix: &[u8]| {
+            let mut bytes = vec![prefix; depth];
+            bytes.push(item);
+            for _ in 0..depth {
+                bytes.extend_from_slice(suffix);
+            }
+            bytes
+        };
+        let limits = DecodeLimits::new().max_depth(depth + 1);
+        let mut input = vec![0xa1];
+        input.extend(key(0x9f, 0x01, b"\xff"));
+        input.push(0xf6);
+        let mut deserializer = Deserializer::from_slice(&input)
+            .limits(limits)
+            .deny_duplicate_keys();
+        deserializer.read_value().unwrap();
+
+        // Keys are normalized to find duplicates in different encodings. Comparing the values
+        // of two keys would recurse.
+        input[0] = 0xa2;
+        input.extend(key(0x81, 0x01, b""));
+        input.push(0xf6);
+        let mut deserializer = Deserializer::from_slice(&input)
+            .limits(limits)
+            .deny_duplicate_keys();
+        let err = deserializer.read_value().unwrap_err();
+        assert_eq!(
+            err.to_string().split(" at ").next(),
+            Some("duplicate map key")
+        );
+    }
 }